@@ -1,7 +1,7 @@
 use compio::net::ToSocketAddrsAsync;
 use deboa::{
     dns::DnsResolver,
-    errors::{DeboaError::Dns, DnsError},
+    errors::{DeboaError::Dns, DnsError, ErrorSource},
 };
 use rand::seq::SliceRandom;
 use std::net::IpAddr;
@@ -17,7 +17,11 @@ impl DnsResolver for DefaultDnsResolver {
             .to_socket_addrs_async()
             .await;
         if let Err(e) = addrs {
-            return Err(Dns(DnsError::Resolve { host, message: e.to_string() }));
+            return Err(Dns(DnsError::Resolve {
+                host,
+                message: e.to_string(),
+                source: Some(ErrorSource::new(e)),
+            }));
         };
 
         let mut ips: Vec<IpAddr> = addrs
@@ -3,12 +3,13 @@ use compio::net::TcpStream;
 use cyper_core::{CompioExecutor, HyperStream};
 use deboa::{
     conn::{HttpConnection, ProtoConnection},
-    errors::{ConnectionError, DeboaError},
+    errors::{ConnectionError, DeboaError, ErrorSource},
+    protocol::ProtocolSettings,
     request::Http2Request,
     Result,
 };
 use http::version::Version;
-use hyper::client::conn::http2::handshake;
+use hyper::rt::{Read, Write};
 
 impl HttpConnection for Http2Connection {
     type Sender = Http2Request;
@@ -26,23 +27,41 @@ impl ProtoConnection for Http2Connection {
         Version::HTTP_2
     }
 
-    async fn connect(stream: HyperStream<TcpStream>) -> Result<Self::Connection> {
-        let (sender, conn) = handshake(CompioExecutor, stream)
-            .await
-            .map_err(|e| {
-                DeboaError::Connection(ConnectionError::Handshake { message: e.to_string() })
-            })?;
+    async fn connect(
+        stream: HyperStream<TcpStream>,
+        settings: &ProtocolSettings,
+    ) -> Result<Self::Connection> {
+        connect_io(stream, settings).await
+    }
+}
 
-        compio::runtime::spawn(async move {
-            match conn.await {
-                Ok(_) => (),
-                Err(err) => {
-                    log::error!("Error: {:#}", err)
-                }
-            };
-        })
-        .detach();
+/// Handshake over any connection, a TCP connection, a Unix socket or an
+/// upgraded HTTP/1.1 connection.
+pub(crate) async fn connect_io<T>(io: T, settings: &ProtocolSettings) -> Result<Http2Connection>
+where
+    T: Read + Write + Unpin + Send + 'static,
+{
+    let (sender, conn) = settings
+        .http2()
+        .builder(CompioExecutor)
+        .handshake(io)
+        .await
+        .map_err(|e| {
+            DeboaError::Connection(ConnectionError::Handshake {
+                message: e.to_string(),
+                source: Some(ErrorSource::new(e)),
+            })
+        })?;
 
-        Ok(BaseHttpConnection::new(sender))
-    }
+    compio::runtime::spawn(async move {
+        match conn.await {
+            Ok(_) => (),
+            Err(err) => {
+                log::error!("Error: {:#}", err)
+            }
+        };
+    })
+    .detach();
+
+    Ok(BaseHttpConnection::new(sender))
 }
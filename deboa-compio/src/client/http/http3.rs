@@ -2,12 +2,14 @@ use crate::client::http::conn::{BaseHttpConnection, Http3Connection};
 use compio_quic::Connection;
 use deboa::{
     conn::{HttpConnection, ProtoConnection},
-    errors::{ConnectionError, DeboaError},
+    errors::{ConnectionError, DeboaError, ErrorSource},
+    protocol::ProtocolSettings,
     Result,
 };
 use deboa_h3::compio::{Http3Request, SendRequest};
 use futures::future;
 use http::version::Version;
+use std::sync::atomic::Ordering;
 
 impl HttpConnection for Http3Connection {
     type Sender = Http3Request;
@@ -25,17 +27,28 @@ impl ProtoConnection for Http3Connection {
         Version::HTTP_3
     }
 
-    async fn connect(stream: Self::RuntimeStream) -> Result<Self::Connection> {
+    async fn connect(
+        stream: Self::RuntimeStream,
+        _settings: &ProtocolSettings,
+    ) -> Result<Self::Connection> {
         let (mut conn, sender) = compio_quic::h3::client::new(stream)
             .await
-            .map_err(|e| DeboaError::Connection(ConnectionError::Udp { message: e.to_string() }))?;
+            .map_err(|e| {
+                DeboaError::Connection(ConnectionError::Udp {
+                    message: e.to_string(),
+                    source: Some(ErrorSource::new(e)),
+                })
+            })?;
 
+        let sender = SendRequest::new(sender);
+        let closed = sender.closed();
         compio::runtime::spawn(async move {
             future::poll_fn(|cx| conn.poll_close(cx)).await;
+            closed.store(true, Ordering::Release);
             Ok::<(), Box<dyn std::error::Error + Send + Sync>>(())
         })
         .detach();
 
-        Ok(BaseHttpConnection::new(SendRequest::new(sender)))
+        Ok(BaseHttpConnection::new(sender))
     }
 }
@@ -1,6 +1,6 @@
 use deboa::{
     dns::DnsResolver,
-    errors::{DeboaError::Dns, DnsError},
+    errors::{DeboaError::Dns, DnsError, ErrorSource},
 };
 use rand::seq::SliceRandom;
 use std::net::IpAddr;
@@ -28,7 +28,11 @@ impl DnsResolver for DefaultDnsResolver {
             })
             .await;
         if let Err(e) = addrs {
-            return Err(Dns(DnsError::Resolve { host, message: e.to_string() }));
+            return Err(Dns(DnsError::Resolve {
+                host,
+                message: e.to_string(),
+                source: Some(ErrorSource::new(e)),
+            }));
         };
 
         let mut ips: Vec<IpAddr> = addrs
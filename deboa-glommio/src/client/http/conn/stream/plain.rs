@@ -13,6 +13,7 @@ pub(crate) async fn create_stream(addr: IpAddr, host: &str, port: u16) -> Result
         Err(e) => {
             return Err(DeboaError::Connection(ConnectionError::Tcp {
                 message: format!("Could not connect to server: {}", e),
+                source: None,
             }));
         }
     };
@@ -4,7 +4,7 @@ use crate::{
     rt::stream::GlommioStream,
 };
 use deboa::{
-    errors::{ConnectionError, DeboaError},
+    errors::{ConnectionError, DeboaError, ErrorSource},
     Result,
 };
 use futures_rustls::TlsConnector;
@@ -27,7 +27,10 @@ pub(crate) async fn tls_connection<'a>(
     let hostname = ServerName::try_from(host.to_string());
 
     if let Err(e) = hostname {
-        return Err(DeboaError::Connection(ConnectionError::Tls { message: e.to_string() }));
+        return Err(DeboaError::Connection(ConnectionError::Tls {
+            message: e.to_string(),
+            source: Some(ErrorSource::new(e)),
+        }));
     }
 
     let stream = connector
@@ -37,6 +40,7 @@ pub(crate) async fn tls_connection<'a>(
     if let Err(e) = stream {
         return Err(DeboaError::Connection(ConnectionError::Tls {
             message: format!("Could not connect to server: {}", e),
+            source: None,
         }));
     }
 
@@ -85,6 +89,7 @@ pub(crate) fn setup_rust_tls<'a>(
             if let Err(e) = cert {
                 return Err(DeboaError::Connection(ConnectionError::Tls {
                     message: format!("Invalid CA certificate: {}", e),
+                    source: None,
                 }));
             }
 
@@ -92,6 +97,7 @@ pub(crate) fn setup_rust_tls<'a>(
             if let Err(e) = result {
                 return Err(DeboaError::Connection(ConnectionError::Tls {
                     message: format!("Could not add CA certificate to the store: {}", e),
+                    source: None,
                 }));
             }
 
@@ -117,6 +123,7 @@ pub(crate) fn setup_rust_tls<'a>(
         if let Err(e) = pair {
             return Err(DeboaError::Connection(ConnectionError::Tls {
                 message: format!("Invalid client identity: {}", e),
+                source: None,
             }));
         }
 
@@ -4,12 +4,13 @@ use crate::{
 };
 use deboa::{
     conn::{HttpConnection, ProtoConnection},
-    errors::{ConnectionError, DeboaError},
+    errors::{ConnectionError, DeboaError, ErrorSource},
+    protocol::ProtocolSettings,
     request::Http2Request,
     Result,
 };
 use http::version::Version;
-use hyper::client::conn::http2::handshake;
+use hyper::rt::{Read, Write};
 use smol_hyper::rt::FuturesIo;
 
 impl HttpConnection for Http2Connection {
@@ -28,23 +29,41 @@ impl ProtoConnection for Http2Connection {
         Version::HTTP_2
     }
 
-    async fn connect(stream: GlommioStream) -> Result<Self::Connection> {
-        let (sender, conn) = handshake(GlommioExecutor::new(), FuturesIo::new(stream))
-            .await
-            .map_err(|e| {
-                DeboaError::Connection(ConnectionError::Handshake { message: e.to_string() })
-            })?;
+    async fn connect(
+        stream: Self::RuntimeStream,
+        settings: &ProtocolSettings,
+    ) -> Result<Self::Connection> {
+        connect_io(FuturesIo::new(stream), settings).await
+    }
+}
 
-        glommio::spawn_local(async move {
-            match conn.await {
-                Ok(_) => (),
-                Err(err) => {
-                    log::error!("Error: {:#}", err)
-                }
-            };
-        })
-        .detach();
+/// Handshake over any connection, a runtime stream or an upgraded HTTP/1.1
+/// connection.
+pub(crate) async fn connect_io<T>(io: T, settings: &ProtocolSettings) -> Result<Http2Connection>
+where
+    T: Read + Write + Unpin + 'static,
+{
+    let (sender, conn) = settings
+        .http2()
+        .builder(GlommioExecutor::new())
+        .handshake(io)
+        .await
+        .map_err(|e| {
+            DeboaError::Connection(ConnectionError::Handshake {
+                message: e.to_string(),
+                source: Some(ErrorSource::new(e)),
+            })
+        })?;
 
-        Ok(BaseHttpConnection::new(sender))
-    }
+    glommio::spawn_local(async move {
+        match conn.await {
+            Ok(_) => (),
+            Err(err) => {
+                log::error!("Error: {:#}", err)
+            }
+        };
+    })
+    .detach();
+
+    Ok(BaseHttpConnection::new(sender))
 }
@@ -6,20 +6,14 @@ use compio_tls::{
 };
 use deboa::{
     errors::{ConnectionError, DeboaError},
+    protocol::ProtocolPolicy,
     Result,
 };
 use glommio::net::TcpStream;
 
 #[inline]
-pub(crate) fn alpn() -> &'static [&'static str] {
-    &[
-        #[cfg(feature = "http3")]
-        "h3",
-        #[cfg(feature = "http2")]
-        "h2",
-        #[cfg(feature = "http1")]
-        "http/1.1",
-    ]
+pub(crate) fn alpn(policy: ProtocolPolicy) -> Vec<&'static str> {
+    super::alpn_protocols(policy)
 }
 
 pub struct TlsConnectionBuilder<'a> {
@@ -28,7 +22,7 @@ pub struct TlsConnectionBuilder<'a> {
     identity: Option<&'a DeboaIdentity>,
     certificate: Option<&'a DeboaCertificate>,
     skip_server_verification: bool,
-    alpn: &'a [&'a str],
+    alpn: Vec<&'a str>,
 }
 
 impl<'a> TlsConnectionBuilder<'a> {
@@ -39,7 +33,7 @@ impl<'a> TlsConnectionBuilder<'a> {
             identity: None,
             certificate: None,
             skip_server_verification: false,
-            alpn: alpn(),
+            alpn: alpn(ProtocolPolicy::default()),
         }
     }
 
@@ -58,8 +52,8 @@ impl<'a> TlsConnectionBuilder<'a> {
         self
     }
 
-    pub fn alpn(mut self, alpn: &'a [&str]) -> Self {
-        self.alpn = alpn;
+    pub fn alpn(mut self, alpn: &[&'a str]) -> Self {
+        self.alpn = alpn.to_vec();
         self
     }
 
@@ -74,7 +68,7 @@ impl<'a> TlsConnectionBuilder<'a> {
             &mut builder
         };
 
-        let builder = builder.request_alpns(self.alpn);
+        let builder = builder.request_alpns(&self.alpn);
 
         let builder = if let Some(ca) = self.certificate {
             let cert: Certificate = ca
@@ -82,6 +76,7 @@ impl<'a> TlsConnectionBuilder<'a> {
                 .map_err(|e| {
                     DeboaError::Connection(ConnectionError::Tls {
                         message: format!("Invalid CA certificate: {}", e),
+                        source: None,
                     })
                 })?;
             builder.add_root_certificate(cert)
@@ -95,6 +90,7 @@ impl<'a> TlsConnectionBuilder<'a> {
                 .map_err(|e| {
                     DeboaError::Connection(ConnectionError::Tls {
                         message: format!("Invalid client identity: {}", e),
+                        source: None,
                     })
                 })?;
             builder.identity(ident)
@@ -107,6 +103,7 @@ impl<'a> TlsConnectionBuilder<'a> {
             .map_err(|e| {
                 DeboaError::Connection(ConnectionError::Tls {
                     message: format!("Could not build TLS connector: {}", e),
+                    source: None,
                 })
             })?;
 
@@ -118,6 +115,7 @@ impl<'a> TlsConnectionBuilder<'a> {
             .map_err(|e| {
                 DeboaError::Connection(ConnectionError::Tls {
                     message: format!("Could not connect to server: {}", e),
+                    source: None,
                 })
             })?;
 
@@ -3,6 +3,7 @@
 use crate::cert::{DeboaCertificate, DeboaIdentity};
 use deboa::{
     errors::{ConnectionError, DeboaError},
+    protocol::ProtocolPolicy,
     Result,
 };
 use rustls::{
@@ -19,15 +20,15 @@ pub(crate) fn default_provider() -> CryptoProvider {
 }
 
 #[inline]
-pub(crate) fn alpn() -> Vec<Vec<u8>> {
-    vec![
-        #[cfg(feature = "http3")]
-        b"h3".to_vec(),
-        #[cfg(feature = "http2")]
-        b"h2".to_vec(),
-        #[cfg(feature = "http1")]
-        b"http/1.1".to_vec(),
-    ]
+pub(crate) fn alpn(policy: ProtocolPolicy) -> Vec<Vec<u8>> {
+    super::alpn_protocols(policy)
+        .into_iter()
+        .map(|protocol| {
+            protocol
+                .as_bytes()
+                .to_vec()
+        })
+        .collect()
 }
 
 /// Builder for TLS connections using rustls
@@ -45,7 +46,7 @@ impl Default for TlsConnectionBuilder<'_> {
             identity: None,
             certificate: None,
             skip_server_verification: false,
-            alpn: alpn(),
+            alpn: alpn(ProtocolPolicy::default()),
             provider: default_provider(),
         }
     }
@@ -78,97 +79,109 @@ impl<'a> TlsConnectionBuilder<'a> {
 
     /// Build the TLS client configuration
     pub fn build_config(self) -> Result<ClientConfig> {
-        let client_config = {
-            if self.skip_server_verification {
-                ClientConfig::builder()
-                    .dangerous()
-                    .with_custom_certificate_verifier(verify::SkipServerVerification::new(
-                        self.provider,
-                    ))
-                    .with_no_client_auth()
-            } else {
-                #[cfg(feature = "__webpki_rustls_verifier")]
-                let config = {
-                    let config = ClientConfig::builder_with_provider(self.provider.into())
-                        .with_protocol_versions(rustls::ALL_VERSIONS)
+        let config = if self.skip_server_verification {
+            ClientConfig::builder_with_provider(
+                self.provider
+                    .clone()
+                    .into(),
+            )
+            .with_protocol_versions(rustls::ALL_VERSIONS)
+            .map_err(|e| {
+                DeboaError::Connection(ConnectionError::Tls {
+                    message: format!("Failed to set TLS version: {}", e),
+                    source: None,
+                })
+            })?
+            .dangerous()
+            .with_custom_certificate_verifier(verify::SkipServerVerification::new(self.provider))
+        } else {
+            #[cfg(feature = "__webpki_rustls_verifier")]
+            let config = {
+                let config = ClientConfig::builder_with_provider(self.provider.into())
+                    .with_protocol_versions(rustls::ALL_VERSIONS)
+                    .map_err(|e| {
+                        DeboaError::Connection(ConnectionError::Tls {
+                            message: format!("Failed to set TLS version: {}", e),
+                            source: None,
+                        })
+                    })?;
+
+                let mut root_store =
+                    rustls::RootCertStore { roots: webpki_roots::TLS_SERVER_ROOTS.to_vec() };
+                let config = if let Some(ca) = self.certificate {
+                    let cert = ca
+                        .try_into()
                         .map_err(|e| {
                             DeboaError::Connection(ConnectionError::Tls {
-                                message: format!("Failed to set TLS version: {}", e),
+                                message: format!("Invalid CA certificate: {}", e),
+                                source: None,
                             })
                         })?;
 
-                    let mut root_store =
-                        rustls::RootCertStore { roots: webpki_roots::TLS_SERVER_ROOTS.to_vec() };
-                    let config = if let Some(ca) = self.certificate {
-                        let cert = ca
-                            .try_into()
-                            .map_err(|e| {
-                                DeboaError::Connection(ConnectionError::Tls {
-                                    message: format!("Invalid CA certificate: {}", e),
-                                })
-                            })?;
-
-                        root_store
-                            .add(cert)
-                            .map_err(|e| {
-                                DeboaError::Connection(ConnectionError::Tls {
-                                    message: format!(
-                                        "Could not add CA certificate to the store: {}",
-                                        e
-                                    ),
-                                })
-                            })?;
-
-                        config.with_root_certificates(root_store)
-                    } else {
-                        config.with_root_certificates(root_store)
-                    };
-
-                    config
-                };
-
-                #[cfg(feature = "__platform_rustls_verifier")]
-                let config = {
-                    use rustls_platform_verifier::BuilderVerifierExt;
-                    rustls::ClientConfig::builder_with_provider(default_provider())
-                        .with_protocol_versions(rustls::ALL_VERSIONS)
+                    root_store
+                        .add(cert)
                         .map_err(|e| {
                             DeboaError::Connection(ConnectionError::Tls {
-                                message: format!("Failed to set TLS version: {}", e),
-                            })
-                        })?
-                        .with_platform_verifier()
-                };
-
-                let mut config = if let Some(id) = self.identity {
-                    let pair: (CertificateDer<'_>, PrivateKeyDer<'_>) = id
-                        .try_into()
-                        .map_err(|e| {
-                            DeboaError::Connection(ConnectionError::Tls {
-                                message: format!("Invalid client identity: {}", e),
+                                message: format!(
+                                    "Could not add CA certificate to the store: {}",
+                                    e
+                                ),
+                                source: None,
                             })
                         })?;
 
-                    config
-                        .with_client_auth_cert(vec![pair.0], pair.1)
-                        .map_err(|e| {
-                            DeboaError::Connection(ConnectionError::Tls {
-                                message: format!("Failed to set client identity: {}", e),
-                            })
-                        })?
+                    config.with_root_certificates(root_store)
                 } else {
-                    config.with_no_client_auth()
+                    config.with_root_certificates(root_store)
                 };
 
-                config.enable_early_data = true;
-
-                config.alpn_protocols = self.alpn;
-
                 config
-            }
+            };
+
+            #[cfg(feature = "__platform_rustls_verifier")]
+            let config = {
+                use rustls_platform_verifier::BuilderVerifierExt;
+                rustls::ClientConfig::builder_with_provider(default_provider())
+                    .with_protocol_versions(rustls::ALL_VERSIONS)
+                    .map_err(|e| {
+                        DeboaError::Connection(ConnectionError::Tls {
+                            message: format!("Failed to set TLS version: {}", e),
+                            source: None,
+                        })
+                    })?
+                    .with_platform_verifier()
+            };
+
+            config
         };
 
-        Ok(client_config)
+        let mut config = if let Some(id) = self.identity {
+            let pair: (CertificateDer<'_>, PrivateKeyDer<'_>) = id
+                .try_into()
+                .map_err(|e| {
+                    DeboaError::Connection(ConnectionError::Tls {
+                        message: format!("Invalid client identity: {}", e),
+                        source: None,
+                    })
+                })?;
+
+            config
+                .with_client_auth_cert(vec![pair.0], pair.1)
+                .map_err(|e| {
+                    DeboaError::Connection(ConnectionError::Tls {
+                        message: format!("Failed to set client identity: {}", e),
+                        source: None,
+                    })
+                })?
+        } else {
+            config.with_no_client_auth()
+        };
+
+        config.enable_early_data = true;
+
+        config.alpn_protocols = self.alpn;
+
+        Ok(config)
     }
 }
 
@@ -176,7 +189,7 @@ impl<'a> TlsConnectionBuilder<'a> {
 /// TCP connection module for TLS
 pub mod tcp {
     use deboa::{
-        errors::{ConnectionError, DeboaError},
+        errors::{ConnectionError, DeboaError, ErrorSource},
         Result,
     };
     use futures_rustls::{client::TlsStream, TlsConnector};
@@ -193,8 +206,12 @@ pub mod tcp {
     ) -> Result<TlsStream<TcpStream>> {
         let connector = TlsConnector::from(Arc::new(config));
 
-        let host = ServerName::try_from(host.to_string())
-            .map_err(|e| DeboaError::Connection(ConnectionError::Tls { message: e.to_string() }))?;
+        let host = ServerName::try_from(host.to_string()).map_err(|e| {
+            DeboaError::Connection(ConnectionError::Tls {
+                message: e.to_string(),
+                source: Some(ErrorSource::new(e)),
+            })
+        })?;
 
         connector
             .connect(host, inner_stream)
@@ -202,6 +219,7 @@ pub mod tcp {
             .map_err(|e| {
                 DeboaError::Connection(ConnectionError::Tls {
                     message: format!("Could not connect to server: {}", e),
+                    source: None,
                 })
             })
     }
@@ -210,43 +228,169 @@ pub mod tcp {
 #[cfg(feature = "http3")]
 /// UDP connection module for TLS
 pub mod udp {
-    use compio_quic::{Connection, Endpoint};
+    use compio_quic::{
+        congestion::{BbrConfig, CubicConfig, NewRenoConfig},
+        crypto::rustls::QuicClientConfig,
+        Connection, Endpoint, IdleTimeout, TransportConfig, VarInt,
+    };
     use deboa::{
-        errors::{ConnectionError, DeboaError},
+        errors::{ConnectionError, DeboaError, ErrorSource},
+        protocol::{CongestionController, Http3Settings},
         Result,
     };
     use rustls::ClientConfig;
-    use std::{net::SocketAddr, sync::Arc};
+    use std::{
+        collections::HashMap,
+        net::SocketAddr,
+        sync::{Arc, Mutex},
+    };
 
-    /// Establish a TLS connection over UDP
-    pub async fn connect(
-        config: ClientConfig,
-        endpoint: &mut Endpoint,
-        socket_addr: SocketAddr,
-        host: &str,
-    ) -> Result<Connection> {
-        let quic_config =
-            compio_quic::crypto::rustls::QuicClientConfig::try_from(config).map_err(|e| {
-                DeboaError::Connection(ConnectionError::Tls {
-                    message: format!("Could not create QUIC client config: {}", e),
+    /// QUIC endpoints of a client, one per local address, and the client
+    /// configuration its connections share. Sharing the configuration keeps
+    /// the TLS session cache, resumed connections can use 0-RTT.
+    #[derive(Default)]
+    pub struct QuicEndpoints {
+        endpoints: Mutex<HashMap<SocketAddr, Endpoint>>,
+        client_config: Mutex<Option<compio_quic::ClientConfig>>,
+    }
+
+    impl QuicEndpoints {
+        /// Get the endpoint bound to a local address, created on first use.
+        pub async fn endpoint(&self, local_addr: SocketAddr) -> Result<Endpoint> {
+            if let Some(endpoint) = self
+                .endpoints
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .get(&local_addr)
+            {
+                return Ok(endpoint.clone());
+            }
+
+            let endpoint = Endpoint::client(local_addr)
+                .await
+                .map_err(|e| {
+                    DeboaError::Connection(ConnectionError::Udp {
+                        message: e.to_string(),
+                        source: Some(ErrorSource::new(e)),
+                    })
+                })?;
+            // Another connection may have created it in the meantime.
+            Ok(self
+                .endpoints
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .entry(local_addr)
+                .or_insert(endpoint)
+                .clone())
+        }
+
+        /// Get the client configuration, built on first use.
+        pub fn client_config<F>(&self, build: F) -> Result<compio_quic::ClientConfig>
+        where
+            F: FnOnce() -> Result<compio_quic::ClientConfig>,
+        {
+            let mut client_config = self
+                .client_config
+                .lock()
+                .unwrap_or_else(|e| e.into_inner());
+            if let Some(client_config) = client_config.as_ref() {
+                return Ok(client_config.clone());
+            }
+
+            let built = build()?;
+            *client_config = Some(built.clone());
+            Ok(built)
+        }
+    }
+
+    /// Build the QUIC transport configuration from the HTTP/3 settings.
+    pub fn transport_config(settings: &Http3Settings) -> Result<TransportConfig> {
+        let mut transport = TransportConfig::default();
+        if let Some(idle_timeout) = settings.idle_timeout() {
+            let idle_timeout = IdleTimeout::try_from(idle_timeout).map_err(|e| {
+                DeboaError::Connection(ConnectionError::Udp {
+                    message: format!("Invalid idle timeout: {}", e),
+                    source: None,
                 })
             })?;
+            transport.max_idle_timeout(Some(idle_timeout));
+        }
+        if let Some(max) = settings.max_concurrent_bidi_streams() {
+            transport.max_concurrent_bidi_streams(VarInt::from_u32(max));
+        }
+        if let Some(max) = settings.max_concurrent_uni_streams() {
+            transport.max_concurrent_uni_streams(VarInt::from_u32(max));
+        }
+        if let Some(initial_rtt) = settings.initial_rtt() {
+            transport.initial_rtt(initial_rtt);
+        }
+        transport.keep_alive_interval(settings.keep_alive_interval());
+        match settings.congestion_controller() {
+            CongestionController::Cubic => {
+                transport.congestion_controller_factory(Arc::new(CubicConfig::default()))
+            }
+            CongestionController::NewReno => {
+                transport.congestion_controller_factory(Arc::new(NewRenoConfig::default()))
+            }
+            CongestionController::Bbr => {
+                transport.congestion_controller_factory(Arc::new(BbrConfig::default()))
+            }
+        };
+        Ok(transport)
+    }
 
-        let client_config = compio_quic::ClientConfig::new(Arc::new(quic_config));
+    /// Build the QUIC client configuration from the TLS configuration and the
+    /// HTTP/3 settings.
+    pub fn client_config(
+        config: ClientConfig,
+        settings: &Http3Settings,
+    ) -> Result<compio_quic::ClientConfig> {
+        let quic_config = QuicClientConfig::try_from(config).map_err(|e| {
+            DeboaError::Connection(ConnectionError::Tls {
+                message: format!("Could not create QUIC client config: {}", e),
+                source: None,
+            })
+        })?;
+
+        let mut client_config = compio_quic::ClientConfig::new(Arc::new(quic_config));
+        client_config.transport_config(Arc::new(transport_config(settings)?));
+        Ok(client_config)
+    }
 
-        let conn = endpoint
-            .connect(socket_addr, host, Some(client_config))
+    /// Establish a TLS connection over UDP. With `zero_rtt`, a resumed
+    /// connection is returned before the handshake completes.
+    pub async fn connect(
+        endpoint: &Endpoint,
+        config: compio_quic::ClientConfig,
+        socket_addr: SocketAddr,
+        host: &str,
+        zero_rtt: bool,
+    ) -> Result<Connection> {
+        let connecting = endpoint
+            .connect(socket_addr, host, Some(config))
             .map_err(|e| {
                 DeboaError::Connection(ConnectionError::Udp {
                     message: format!("Could not connect to server: {}", e),
+                    source: None,
                 })
             })?;
 
-        let conn = conn
+        // Without a session to resume, the handshake runs to completion.
+        let connecting = if zero_rtt {
+            match connecting.into_0rtt() {
+                Ok(conn) => return Ok(conn),
+                Err(connecting) => connecting,
+            }
+        } else {
+            connecting
+        };
+
+        let conn = connecting
             .await
             .map_err(|e| {
                 DeboaError::Connection(ConnectionError::Udp {
                     message: format!("Could not connect to server: {}", e),
+                    source: None,
                 })
             })?;
 
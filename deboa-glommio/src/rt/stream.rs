@@ -3,17 +3,20 @@ use async_native_tls::TlsStream;
 use futures::io::{self, AsyncRead, AsyncWrite};
 #[cfg(feature = "rust-tls")]
 use futures_rustls::client::TlsStream;
-use glommio::net::TcpStream;
+use glommio::net::{TcpStream, UnixStream};
 use std::{
     pin::Pin,
     task::{Context, Poll},
 };
 
-/// A stream that can be either plain TCP or TLS-secured.
+/// A stream that can be either plain TCP, TLS-secured or a Unix socket.
 pub enum GlommioStream {
     /// A plain TCP connection.
     Plain(TcpStream),
 
+    /// A Unix socket connection.
+    Unix(UnixStream),
+
     /// A TCP connection secured by native TLS.
     #[cfg(feature = "native-tls")]
     Tls(TlsStream<TcpStream>),
@@ -41,6 +44,7 @@ impl AsyncRead for GlommioStream {
     ) -> Poll<io::Result<usize>> {
         match &mut *self {
             GlommioStream::Plain(stream) => Pin::new(stream).poll_read(cx, buf),
+            GlommioStream::Unix(stream) => Pin::new(stream).poll_read(cx, buf),
             #[cfg(any(feature = "native-tls", feature = "rust-tls"))]
             GlommioStream::Tls(stream) => Pin::new(stream).poll_read(cx, buf),
         }
@@ -65,6 +69,7 @@ impl AsyncWrite for GlommioStream {
     ) -> Poll<io::Result<usize>> {
         match &mut *self {
             GlommioStream::Plain(stream) => Pin::new(stream).poll_write(cx, buf),
+            GlommioStream::Unix(stream) => Pin::new(stream).poll_write(cx, buf),
             #[cfg(any(feature = "native-tls", feature = "rust-tls"))]
             GlommioStream::Tls(stream) => Pin::new(stream).poll_write(cx, buf),
         }
@@ -73,6 +78,7 @@ impl AsyncWrite for GlommioStream {
     fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
         match &mut *self {
             GlommioStream::Plain(stream) => Pin::new(stream).poll_close(cx),
+            GlommioStream::Unix(stream) => Pin::new(stream).poll_close(cx),
             #[cfg(any(feature = "native-tls", feature = "rust-tls"))]
             GlommioStream::Tls(stream) => Pin::new(stream).poll_close(cx),
         }
@@ -81,6 +87,7 @@ impl AsyncWrite for GlommioStream {
     fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
         match &mut *self {
             GlommioStream::Plain(stream) => Pin::new(stream).poll_flush(cx),
+            GlommioStream::Unix(stream) => Pin::new(stream).poll_flush(cx),
             #[cfg(any(feature = "native-tls", feature = "rust-tls"))]
             GlommioStream::Tls(stream) => Pin::new(stream).poll_flush(cx),
         }
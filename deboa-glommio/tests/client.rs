@@ -0,0 +1,124 @@
+//! End-to-end: paths resolve against the base url of the client, and the
+//! default headers are sent unless the request sets its own.
+
+use std::convert::Infallible;
+use std::net::SocketAddr;
+
+use deboa_glommio::Client;
+use http::{header, HeaderMap, HeaderValue};
+use http_body_util::Full;
+use hyper::body::Bytes;
+
+/// Answers with the path, the user agent and the tenant header of the request.
+fn serve(addr: SocketAddr) {
+    let listener = glommio::net::TcpListener::bind(addr).expect("bind");
+    glommio::spawn_local(async move {
+        while let Ok(stream) = listener
+            .accept()
+            .await
+        {
+            glommio::spawn_local(async move {
+                let service = hyper::service::service_fn(|req: hyper::Request<_>| async move {
+                    let header = |name: &str| {
+                        req.headers()
+                            .get(name)
+                            .and_then(|value| value.to_str().ok())
+                            .unwrap_or("-")
+                            .to_string()
+                    };
+                    let body = format!(
+                        "{} {} {}",
+                        req.uri().path(),
+                        header("user-agent"),
+                        header("x-tenant")
+                    );
+                    Ok::<_, Infallible>(hyper::Response::new(Full::new(Bytes::from(body))))
+                });
+                let _ = hyper::server::conn::http1::Builder::new()
+                    .serve_connection(smol_hyper::rt::FuturesIo::new(stream), service)
+                    .await;
+            })
+            .detach();
+        }
+    })
+    .detach();
+}
+
+fn ephemeral_addr() -> SocketAddr {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind");
+    listener
+        .local_addr()
+        .expect("local_addr")
+}
+
+#[test]
+fn paths_resolve_against_the_base_url() {
+    let addr = ephemeral_addr();
+
+    glommio::LocalExecutorBuilder::default()
+        .make()
+        .expect("build glommio executor")
+        .run(async move {
+            serve(addr);
+
+            let mut default_headers = HeaderMap::new();
+            default_headers.insert("x-tenant", HeaderValue::from_static("acme"));
+            let client = Client::builder()
+                .base_url(
+                    format!("http://{addr}/api/v1")
+                        .parse()
+                        .expect("base url"),
+                )
+                .default_headers(default_headers)
+                .user_agent(HeaderValue::from_static("deboa-test/1.0"))
+                .build();
+
+            let response = (&client << "/users")
+                .version(http::Version::HTTP_11)
+                .send_with(&client)
+                .await
+                .expect("request failed");
+            assert_eq!(
+                response
+                    .text()
+                    .await
+                    .expect("body"),
+                "/api/v1/users deboa-test/1.0 acme"
+            );
+        });
+}
+
+#[test]
+fn request_headers_override_the_defaults() {
+    let addr = ephemeral_addr();
+
+    glommio::LocalExecutorBuilder::default()
+        .make()
+        .expect("build glommio executor")
+        .run(async move {
+            serve(addr);
+
+            let client = Client::builder()
+                .base_url(
+                    format!("http://{addr}/")
+                        .parse()
+                        .expect("base url"),
+                )
+                .user_agent(HeaderValue::from_static("deboa-test/1.0"))
+                .build();
+
+            let response = (&client << format!("http://{addr}/absolute").as_str())
+                .version(http::Version::HTTP_11)
+                .header(header::USER_AGENT, "custom/2.0")
+                .send_with(&client)
+                .await
+                .expect("request failed");
+            assert_eq!(
+                response
+                    .text()
+                    .await
+                    .expect("body"),
+                "/absolute custom/2.0 -"
+            );
+        });
+}
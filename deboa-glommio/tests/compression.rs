@@ -0,0 +1,204 @@
+//! End-to-end: responses gzipped by a local hyper server are decoded by the
+//! client, unless the request opts out, and compressed request bodies are
+//! decoded by the server.
+
+use std::convert::Infallible;
+use std::io::{Read, Write};
+use std::net::SocketAddr;
+
+use deboa::{
+    compression::Encoding,
+    request::{get, post},
+};
+use deboa_glommio::Client;
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use http_body_util::{BodyExt, Full};
+use hyper::body::Bytes;
+
+const BODY: &str = "hello from a gzipped glommio server";
+
+fn gzip(data: &[u8]) -> Vec<u8> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder
+        .write_all(data)
+        .expect("compress");
+    encoder
+        .finish()
+        .expect("compress")
+}
+
+/// Gzips the body when the request accepts it, echoes request bodies
+/// decoded.
+fn serve(addr: SocketAddr) {
+    let listener = glommio::net::TcpListener::bind(addr).expect("bind");
+    glommio::spawn_local(async move {
+        while let Ok(stream) = listener
+            .accept()
+            .await
+        {
+            glommio::spawn_local(async move {
+                let service = hyper::service::service_fn(|req: hyper::Request<_>| async move {
+                    if req.method() == http::Method::POST {
+                        return Ok::<_, Infallible>(echo(req).await);
+                    }
+
+                    let accepts_gzip = req
+                        .headers()
+                        .get(http::header::ACCEPT_ENCODING)
+                        .and_then(|value| value.to_str().ok())
+                        .is_some_and(|value| value.contains("gzip"));
+                    let response = if accepts_gzip {
+                        hyper::Response::builder()
+                            .header(http::header::CONTENT_ENCODING, "gzip")
+                            .body(Full::new(Bytes::from(gzip(BODY.as_bytes()))))
+                    } else {
+                        hyper::Response::builder().body(Full::new(Bytes::from(BODY)))
+                    };
+                    Ok::<_, Infallible>(response.expect("response"))
+                });
+                let _ = hyper::server::conn::http1::Builder::new()
+                    .serve_connection(smol_hyper::rt::FuturesIo::new(stream), service)
+                    .await;
+            })
+            .detach();
+        }
+    })
+    .detach();
+}
+
+async fn echo(req: hyper::Request<hyper::body::Incoming>) -> hyper::Response<Full<Bytes>> {
+    let gzipped = req
+        .headers()
+        .get(http::header::CONTENT_ENCODING)
+        .is_some_and(|value| value == "gzip");
+    let body = req
+        .into_body()
+        .collect()
+        .await
+        .expect("read request body")
+        .to_bytes();
+
+    let mut decoded = Vec::new();
+    if gzipped {
+        GzDecoder::new(body.as_ref())
+            .read_to_end(&mut decoded)
+            .expect("decompress");
+    } else {
+        decoded.extend_from_slice(&body);
+    }
+
+    hyper::Response::builder()
+        .header("x-gzipped", gzipped.to_string())
+        .body(Full::new(Bytes::from(decoded)))
+        .expect("response")
+}
+
+fn ephemeral_addr() -> SocketAddr {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind");
+    listener
+        .local_addr()
+        .expect("local_addr")
+}
+
+#[test]
+fn a_gzipped_response_is_decoded() {
+    let addr = ephemeral_addr();
+
+    glommio::LocalExecutorBuilder::default()
+        .make()
+        .expect("build glommio executor")
+        .run(async move {
+            serve(addr);
+
+            let client = Client::default();
+            let response = get(format!("http://{addr}/").as_str())
+                .expect("build request")
+                .version(http::Version::HTTP_11)
+                .send_with(&client)
+                .await
+                .expect("request failed");
+
+            assert!(!response
+                .headers()
+                .contains_key(http::header::CONTENT_ENCODING));
+            let body = response
+                .bytes()
+                .await
+                .expect("read body");
+            assert_eq!(body, BODY.as_bytes());
+        });
+}
+
+#[test]
+fn opting_out_returns_the_raw_bytes() {
+    let addr = ephemeral_addr();
+
+    glommio::LocalExecutorBuilder::default()
+        .make()
+        .expect("build glommio executor")
+        .run(async move {
+            serve(addr);
+
+            let client = Client::default();
+            let response = get(format!("http://{addr}/").as_str())
+                .expect("build request")
+                .version(http::Version::HTTP_11)
+                .header(http::header::ACCEPT_ENCODING, "gzip")
+                .decompress(false)
+                .send_with(&client)
+                .await
+                .expect("request failed");
+
+            assert_eq!(
+                response
+                    .headers()
+                    .get(http::header::CONTENT_ENCODING)
+                    .map(|value| value.as_bytes()),
+                Some(b"gzip".as_ref())
+            );
+            let body = response
+                .bytes()
+                .await
+                .expect("read body");
+            assert_eq!(body, gzip(BODY.as_bytes()));
+        });
+}
+
+#[test]
+fn a_compressed_request_body_is_decoded_by_the_server() {
+    let addr = ephemeral_addr();
+
+    glommio::LocalExecutorBuilder::default()
+        .make()
+        .expect("build glommio executor")
+        .run(async move {
+            serve(addr);
+
+            let client = Client::builder()
+                .request_compression(Encoding::Gzip)
+                .request_compression_threshold(16)
+                .build();
+            for (text, gzipped) in [("tiny", "false"), (BODY, "true")] {
+                let response = post(format!("http://{addr}/").as_str())
+                    .expect("build request")
+                    .version(http::Version::HTTP_11)
+                    .text(text)
+                    .send_with(&client)
+                    .await
+                    .expect("request failed");
+
+                assert_eq!(
+                    response
+                        .headers()
+                        .get("x-gzipped")
+                        .map(|value| value.as_bytes()),
+                    Some(gzipped.as_bytes())
+                );
+                let body = response
+                    .bytes()
+                    .await
+                    .expect("read body");
+                assert_eq!(body, text.as_bytes());
+            }
+        });
+}
@@ -0,0 +1,91 @@
+//! End-to-end: cookies set by a local hyper server, including on a redirect
+//! hop, are kept by the client cookie jar and sent with the next requests.
+
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use deboa::{jar::CookieJar, request::get};
+use deboa_glommio::Client;
+use http_body_util::Full;
+use hyper::body::Bytes;
+
+/// `/login` sets a session cookie and redirects to `/home`, which echoes the
+/// cookies it got.
+fn serve(addr: SocketAddr) {
+    let listener = glommio::net::TcpListener::bind(addr).expect("bind");
+    glommio::spawn_local(async move {
+        while let Ok(stream) = listener
+            .accept()
+            .await
+        {
+            glommio::spawn_local(async move {
+                let service = hyper::service::service_fn(|req: hyper::Request<_>| async move {
+                    let response = if req.uri().path() == "/login" {
+                        hyper::Response::builder()
+                            .status(http::StatusCode::FOUND)
+                            .header(http::header::LOCATION, "/home")
+                            .header(http::header::SET_COOKIE, "session=abc; Path=/; HttpOnly")
+                            .body(Full::new(Bytes::new()))
+                    } else {
+                        let cookies = req
+                            .headers()
+                            .get(http::header::COOKIE)
+                            .map(|value| {
+                                value
+                                    .as_bytes()
+                                    .to_vec()
+                            })
+                            .unwrap_or_default();
+                        hyper::Response::builder().body(Full::new(Bytes::from(cookies)))
+                    };
+                    Ok::<_, Infallible>(response.expect("response"))
+                });
+                let _ = hyper::server::conn::http1::Builder::new()
+                    .serve_connection(smol_hyper::rt::FuturesIo::new(stream), service)
+                    .await;
+            })
+            .detach();
+        }
+    })
+    .detach();
+}
+
+fn ephemeral_addr() -> SocketAddr {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind");
+    listener
+        .local_addr()
+        .expect("local_addr")
+}
+
+#[test]
+fn cookies_set_on_a_redirect_are_sent_back() {
+    let addr = ephemeral_addr();
+
+    glommio::LocalExecutorBuilder::default()
+        .make()
+        .expect("build glommio executor")
+        .run(async move {
+            serve(addr);
+
+            let jar = Arc::new(CookieJar::new());
+            let client = Client::builder()
+                .cookie_store(jar.clone())
+                .build();
+            for path in ["login", "home"] {
+                let response = get(format!("http://{addr}/{path}").as_str())
+                    .expect("build request")
+                    .version(http::Version::HTTP_11)
+                    .send_with(&client)
+                    .await
+                    .expect("request failed");
+
+                let body = response
+                    .bytes()
+                    .await
+                    .expect("read body");
+                assert_eq!(body, b"session=abc");
+            }
+            assert_eq!(jar.len(), 1);
+        });
+}
@@ -0,0 +1,90 @@
+//! End-to-end: failures against local sockets are classified, and keep their
+//! source and the request they belong to.
+
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use deboa::{errors::DeboaError, request::get};
+use deboa_glommio::Client;
+
+/// Accepts connections and never answers.
+fn serve_silently(addr: SocketAddr) {
+    let listener = glommio::net::TcpListener::bind(addr).expect("bind");
+    glommio::spawn_local(async move {
+        let mut streams = Vec::new();
+        while let Ok(stream) = listener
+            .accept()
+            .await
+        {
+            streams.push(stream);
+        }
+    })
+    .detach();
+}
+
+fn ephemeral_addr() -> SocketAddr {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind");
+    listener
+        .local_addr()
+        .expect("local_addr")
+}
+
+#[test]
+fn a_request_without_an_answer_times_out() {
+    let addr = ephemeral_addr();
+
+    glommio::LocalExecutorBuilder::default()
+        .make()
+        .expect("build glommio executor")
+        .run(async move {
+            serve_silently(addr);
+
+            let client = Client::builder()
+                .request_timeout(Duration::from_millis(100))
+                .build();
+            let url = format!("http://{addr}/slow");
+            let error = get(url.as_str())
+                .expect("build request")
+                .version(http::Version::HTTP_11)
+                .send_with(&client)
+                .await
+                .expect_err("request should time out");
+
+            assert!(error.is_timeout(), "{error:?}");
+            assert_eq!(error.method(), Some(&http::Method::GET));
+            assert_eq!(
+                error
+                    .url()
+                    .map(ToString::to_string),
+                Some(url)
+            );
+        });
+}
+
+#[test]
+fn a_refused_connection_keeps_the_io_error() {
+    // Nothing listens on the port once the listener is dropped.
+    let addr = ephemeral_addr();
+
+    glommio::LocalExecutorBuilder::default()
+        .make()
+        .expect("build glommio executor")
+        .run(async move {
+            let client = Client::default();
+            let error: DeboaError = get(format!("http://{addr}/").as_str())
+                .expect("build request")
+                .version(http::Version::HTTP_11)
+                .send_with(&client)
+                .await
+                .expect_err("connection should be refused");
+
+            assert!(error.is_connect(), "{error:?}");
+            assert!(!error.is_timeout());
+            assert_eq!(
+                error
+                    .downcast_source::<std::io::Error>()
+                    .map(std::io::Error::kind),
+                Some(std::io::ErrorKind::ConnectionRefused)
+            );
+        });
+}
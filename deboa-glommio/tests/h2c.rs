@@ -0,0 +1,314 @@
+//! End-to-end: requests in HTTP/2 to an `http://` URL use h2c with prior
+//! knowledge, and a client set to upgrade switches its HTTP/1.1 connections
+//! to HTTP/2 when the server accepts.
+
+use std::convert::Infallible;
+use std::future::Future;
+use std::net::SocketAddr;
+
+use deboa::{protocol::ProtocolPolicy, request::get};
+use deboa_glommio::Client;
+use futures::io::{AsyncReadExt, AsyncWriteExt};
+use http::Version;
+use http_body_util::Full;
+use hyper::body::Bytes;
+
+/// hyper's HTTP/2 server spawns its streams, on this core here.
+#[derive(Clone)]
+struct LocalExecutor;
+
+impl<F> hyper::rt::Executor<F> for LocalExecutor
+where
+    F: Future + 'static,
+{
+    fn execute(&self, fut: F) {
+        glommio::spawn_local(fut).detach();
+    }
+}
+
+/// Answers with the protocol version and the path of the request.
+fn serve(addr: SocketAddr, http2: bool) {
+    let listener = glommio::net::TcpListener::bind(addr).expect("bind");
+    glommio::spawn_local(async move {
+        while let Ok(stream) = listener
+            .accept()
+            .await
+        {
+            glommio::spawn_local(async move {
+                let service = hyper::service::service_fn(|req: hyper::Request<_>| async move {
+                    let body = format!("{:?} {}", req.version(), req.uri().path());
+                    Ok::<_, Infallible>(hyper::Response::new(Full::new(Bytes::from(body))))
+                });
+                let io = smol_hyper::rt::FuturesIo::new(stream);
+                let _ = if http2 {
+                    hyper::server::conn::http2::Builder::new(LocalExecutor)
+                        .serve_connection(io, service)
+                        .await
+                } else {
+                    hyper::server::conn::http1::Builder::new()
+                        .serve_connection(io, service)
+                        .await
+                };
+            })
+            .detach();
+        }
+    })
+    .detach();
+}
+
+/// Accepts `Upgrade: h2c`, answers the upgrade request on stream 1, then
+/// every request with the stream it was sent on.
+fn serve_upgrade(addr: SocketAddr) {
+    let listener = glommio::net::TcpListener::bind(addr).expect("bind");
+    glommio::spawn_local(async move {
+        while let Ok(mut stream) = listener
+            .accept()
+            .await
+        {
+            glommio::spawn_local(async move {
+                let mut head = Vec::new();
+                let mut byte = [0; 1];
+                while !head.ends_with(b"\r\n\r\n") {
+                    stream
+                        .read_exact(&mut byte)
+                        .await
+                        .expect("read head");
+                    head.push(byte[0]);
+                }
+                let head = String::from_utf8_lossy(&head).to_lowercase();
+                assert!(head.contains("upgrade: h2c"), "{head}");
+                assert!(head.contains("http2-settings: "), "{head}");
+
+                stream
+                    .write_all(
+                        b"HTTP/1.1 101 Switching Protocols\r\nConnection: Upgrade\r\nUpgrade: h2c\r\n\r\n",
+                    )
+                    .await
+                    .expect("write 101");
+                // Empty SETTINGS, then `:status 200` on stream 1.
+                stream
+                    .write_all(&[0, 0, 0, 4, 0, 0, 0, 0, 0])
+                    .await
+                    .expect("write settings");
+                stream
+                    .write_all(&[0, 0, 1, 1, 5, 0, 0, 0, 1, 0x88])
+                    .await
+                    .expect("write stream 1");
+
+                let mut preface = [0; 24];
+                stream
+                    .read_exact(&mut preface)
+                    .await
+                    .expect("read preface");
+                assert_eq!(&preface, b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n");
+
+                loop {
+                    let mut header = [0; 9];
+                    if stream
+                        .read_exact(&mut header)
+                        .await
+                        .is_err()
+                    {
+                        break;
+                    }
+                    let len = u32::from_be_bytes([0, header[0], header[1], header[2]]) as usize;
+                    let stream_id =
+                        u32::from_be_bytes([header[5], header[6], header[7], header[8]]) & 0x7fff_ffff;
+                    let mut payload = vec![0; len];
+                    stream
+                        .read_exact(&mut payload)
+                        .await
+                        .expect("read payload");
+
+                    let reply = match header[3] {
+                        // SETTINGS, acknowledged.
+                        4 if header[4] & 1 == 0 => vec![0, 0, 0, 4, 1, 0, 0, 0, 0],
+                        // HEADERS, answered with `:status 200` and the stream.
+                        1 => {
+                            assert_ne!(stream_id, 1, "stream 1 is the upgrade request");
+                            let id = stream_id.to_be_bytes();
+                            let body = format!("stream {stream_id}");
+                            let mut reply = vec![0, 0, 1, 1, 4, id[0], id[1], id[2], id[3], 0x88];
+                            reply.extend_from_slice(&(body.len() as u32).to_be_bytes()[1..]);
+                            reply.extend_from_slice(&[0, 1, id[0], id[1], id[2], id[3]]);
+                            reply.extend_from_slice(body.as_bytes());
+                            reply
+                        }
+                        _ => continue,
+                    };
+                    stream
+                        .write_all(&reply)
+                        .await
+                        .expect("write reply");
+                }
+            })
+            .detach();
+        }
+    })
+    .detach();
+}
+
+fn ephemeral_addr() -> SocketAddr {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind");
+    listener
+        .local_addr()
+        .expect("local_addr")
+}
+
+#[test]
+fn http2_requests_use_prior_knowledge() {
+    let addr = ephemeral_addr();
+
+    glommio::LocalExecutorBuilder::default()
+        .make()
+        .expect("build glommio executor")
+        .run(async move {
+            serve(addr, true);
+
+            let client = Client::default();
+            let response = get(format!("http://{addr}/grpc").as_str())
+                .expect("build request")
+                .version(Version::HTTP_2)
+                .send_with(&client)
+                .await
+                .expect("request failed");
+            assert_eq!(
+                response
+                    .text()
+                    .await
+                    .expect("body"),
+                "HTTP/2.0 /grpc"
+            );
+        });
+}
+
+#[test]
+fn http_requests_default_to_http1() {
+    let addr = ephemeral_addr();
+
+    glommio::LocalExecutorBuilder::default()
+        .make()
+        .expect("build glommio executor")
+        .run(async move {
+            serve(addr, false);
+
+            let client = Client::default();
+            let response = get(format!("http://{addr}/").as_str())
+                .expect("build request")
+                .send_with(&client)
+                .await
+                .expect("request failed");
+            assert_eq!(
+                response
+                    .text()
+                    .await
+                    .expect("body"),
+                "HTTP/1.1 /"
+            );
+        });
+}
+
+#[test]
+fn connections_are_upgraded_to_h2c() {
+    let addr = ephemeral_addr();
+
+    glommio::LocalExecutorBuilder::default()
+        .make()
+        .expect("build glommio executor")
+        .run(async move {
+            serve_upgrade(addr);
+
+            let client = Client::builder()
+                .h2c_upgrade(true)
+                .build();
+            for stream in [3, 5] {
+                let response = get(format!("http://{addr}/").as_str())
+                    .expect("build request")
+                    .send_with(&client)
+                    .await
+                    .expect("request failed");
+                assert_eq!(response.status(), 200);
+                assert_eq!(
+                    response
+                        .text()
+                        .await
+                        .expect("body"),
+                    format!("stream {stream}")
+                );
+            }
+        });
+}
+
+#[test]
+fn a_declined_upgrade_keeps_http1() {
+    let addr = ephemeral_addr();
+
+    glommio::LocalExecutorBuilder::default()
+        .make()
+        .expect("build glommio executor")
+        .run(async move {
+            serve(addr, false);
+
+            let client = Client::builder()
+                .h2c_upgrade(true)
+                .build();
+            let response = get(format!("http://{addr}/status").as_str())
+                .expect("build request")
+                .send_with(&client)
+                .await
+                .expect("request failed");
+            assert_eq!(
+                response
+                    .text()
+                    .await
+                    .expect("body"),
+                "HTTP/1.1 /status"
+            );
+        });
+}
+
+#[test]
+fn the_protocol_policy_picks_the_cleartext_version() {
+    let addr = ephemeral_addr();
+
+    glommio::LocalExecutorBuilder::default()
+        .make()
+        .expect("build glommio executor")
+        .run(async move {
+            serve(addr, true);
+
+            let client = Client::builder()
+                .protocol_policy(ProtocolPolicy::Http2Only)
+                .build();
+            let response = get(format!("http://{addr}/").as_str())
+                .expect("build request")
+                .send_with(&client)
+                .await
+                .expect("request failed");
+            assert_eq!(response.version(), Version::HTTP_2);
+        });
+}
+
+#[test]
+fn http1_only_never_upgrades() {
+    let addr = ephemeral_addr();
+
+    glommio::LocalExecutorBuilder::default()
+        .make()
+        .expect("build glommio executor")
+        .run(async move {
+            serve(addr, false);
+
+            let client = Client::builder()
+                .protocol_policy(ProtocolPolicy::Http1Only)
+                .h2c_upgrade(true)
+                .http2_prior_knowledge(true)
+                .build();
+            let response = get(format!("http://{addr}/").as_str())
+                .expect("build request")
+                .send_with(&client)
+                .await
+                .expect("request failed");
+            assert_eq!(response.version(), Version::HTTP_11);
+        });
+}
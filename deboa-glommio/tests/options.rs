@@ -0,0 +1,152 @@
+//! End-to-end: per request timeouts and policies override the client ones,
+//! and the deadline and read timeout hold while reading the body.
+
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use deboa::{redirect::RedirectPolicy, request::get};
+use deboa_glommio::Client;
+use futures::{AsyncReadExt, AsyncWriteExt};
+
+/// Answers every request with `response` and keeps the connection open.
+fn serve(addr: SocketAddr, response: &'static str) {
+    let listener = glommio::net::TcpListener::bind(addr).expect("bind");
+    glommio::spawn_local(async move {
+        let mut streams = Vec::new();
+        while let Ok(mut stream) = listener
+            .accept()
+            .await
+        {
+            let mut head = [0u8; 1024];
+            let _ = stream
+                .read(&mut head)
+                .await;
+            let _ = stream
+                .write_all(response.as_bytes())
+                .await;
+            let _ = stream.flush().await;
+            streams.push(stream);
+        }
+    })
+    .detach();
+}
+
+/// Sends the head and the start of a body that never ends.
+const STALLED_BODY: &str = "HTTP/1.1 200 OK\r\ncontent-length: 10\r\n\r\nabc";
+
+const REDIRECT: &str = "HTTP/1.1 302 Found\r\nlocation: /next\r\ncontent-length: 0\r\n\r\n";
+
+fn ephemeral_addr() -> SocketAddr {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind");
+    listener
+        .local_addr()
+        .expect("local_addr")
+}
+
+#[test]
+fn a_stalled_body_hits_the_read_timeout() {
+    let addr = ephemeral_addr();
+
+    glommio::LocalExecutorBuilder::default()
+        .make()
+        .expect("build glommio executor")
+        .run(async move {
+            serve(addr, STALLED_BODY);
+
+            let client = Client::default();
+            let response = get(format!("http://{addr}/").as_str())
+                .expect("build request")
+                .version(http::Version::HTTP_11)
+                .read_timeout(Duration::from_millis(100))
+                .send_with(&client)
+                .await
+                .expect("response head");
+
+            let error = response
+                .text()
+                .await
+                .expect_err("body read should time out");
+            assert!(error.is_timeout(), "{error:?}");
+            assert!(error.is_body(), "{error:?}");
+        });
+}
+
+#[test]
+fn the_deadline_covers_the_body() {
+    let addr = ephemeral_addr();
+
+    glommio::LocalExecutorBuilder::default()
+        .make()
+        .expect("build glommio executor")
+        .run(async move {
+            serve(addr, STALLED_BODY);
+
+            let client = Client::default();
+            let response = get(format!("http://{addr}/").as_str())
+                .expect("build request")
+                .version(http::Version::HTTP_11)
+                .timeout(Duration::from_millis(200))
+                .send_with(&client)
+                .await
+                .expect("response head");
+
+            let error = response
+                .bytes()
+                .await
+                .expect_err("body read should pass the deadline");
+            assert!(error.is_timeout(), "{error:?}");
+        });
+}
+
+#[test]
+fn the_response_timeout_overrides_the_client_one() {
+    let addr = ephemeral_addr();
+
+    glommio::LocalExecutorBuilder::default()
+        .make()
+        .expect("build glommio executor")
+        .run(async move {
+            // Reads the request and never answers.
+            serve(addr, "");
+
+            let client = Client::builder()
+                .request_timeout(Duration::from_secs(30))
+                .build();
+            let error = get(format!("http://{addr}/").as_str())
+                .expect("build request")
+                .version(http::Version::HTTP_11)
+                .response_timeout(Duration::from_millis(100))
+                .send_with(&client)
+                .await
+                .expect_err("request should time out");
+
+            assert!(error.is_timeout(), "{error:?}");
+            assert_eq!(error.method(), Some(&http::Method::GET));
+        });
+}
+
+#[test]
+fn the_redirect_policy_overrides_the_client_one() {
+    let addr = ephemeral_addr();
+
+    glommio::LocalExecutorBuilder::default()
+        .make()
+        .expect("build glommio executor")
+        .run(async move {
+            serve(addr, REDIRECT);
+
+            let client = Client::default();
+            let response = get(format!("http://{addr}/").as_str())
+                .expect("build request")
+                .version(http::Version::HTTP_11)
+                .redirect_policy(RedirectPolicy::none())
+                .send_with(&client)
+                .await
+                .expect("response");
+
+            assert_eq!(response.status(), http::StatusCode::FOUND);
+            assert!(response
+                .redirects()
+                .is_empty());
+        });
+}
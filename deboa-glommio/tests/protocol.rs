@@ -0,0 +1,268 @@
+//! End-to-end: the HTTP/2 and HTTP/1 protocol settings of the client builder
+//! reach the wire.
+
+use std::net::SocketAddr;
+
+use deboa::request::get;
+use deboa_glommio::Client;
+use futures::io::{AsyncReadExt, AsyncWriteExt};
+use glommio::net::TcpStream;
+use http::Version;
+use std::time::Duration;
+
+/// Reads a frame, returning its type, flags, stream and payload.
+async fn read_frame(stream: &mut TcpStream) -> Option<(u8, u8, u32, Vec<u8>)> {
+    let mut header = [0; 9];
+    stream
+        .read_exact(&mut header)
+        .await
+        .ok()?;
+    let len = u32::from_be_bytes([0, header[0], header[1], header[2]]) as usize;
+    let stream_id = u32::from_be_bytes([header[5], header[6], header[7], header[8]]) & 0x7fff_ffff;
+    let mut payload = vec![0; len];
+    stream
+        .read_exact(&mut payload)
+        .await
+        .ok()?;
+    Some((header[3], header[4], stream_id, payload))
+}
+
+/// `:status 200` and a body on the stream.
+fn response(stream_id: u32, body: &str) -> Vec<u8> {
+    let id = stream_id.to_be_bytes();
+    let mut reply = vec![0, 0, 1, 1, 4, id[0], id[1], id[2], id[3], 0x88];
+    reply.extend_from_slice(&(body.len() as u32).to_be_bytes()[1..]);
+    reply.extend_from_slice(&[0, 1, id[0], id[1], id[2], id[3]]);
+    reply.extend_from_slice(body.as_bytes());
+    reply
+}
+
+/// Speaks HTTP/2 with prior knowledge. With `ping`, a request is answered
+/// once a keep-alive PING came in, otherwise with the SETTINGS and the
+/// connection window update the client sent, as `id=value` pairs.
+fn serve_http2(addr: SocketAddr, ping: bool) {
+    let listener = glommio::net::TcpListener::bind(addr).expect("bind");
+    glommio::spawn_local(async move {
+        while let Ok(mut stream) = listener
+            .accept()
+            .await
+        {
+            glommio::spawn_local(async move {
+                let mut preface = [0; 24];
+                stream
+                    .read_exact(&mut preface)
+                    .await
+                    .expect("read preface");
+                stream
+                    .write_all(&[0, 0, 0, 4, 0, 0, 0, 0, 0])
+                    .await
+                    .expect("write settings");
+
+                let mut received = Vec::new();
+                let mut pending = None;
+                while let Some((kind, flags, stream_id, payload)) = read_frame(&mut stream).await {
+                    let reply = match kind {
+                        // SETTINGS, recorded and acknowledged.
+                        4 if flags & 1 == 0 => {
+                            for setting in payload.chunks(6) {
+                                let id = u16::from_be_bytes([setting[0], setting[1]]);
+                                let value = u32::from_be_bytes([
+                                    setting[2], setting[3], setting[4], setting[5],
+                                ]);
+                                received.push(format!("{id}={value}"));
+                            }
+                            vec![0, 0, 0, 4, 1, 0, 0, 0, 0]
+                        }
+                        // WINDOW_UPDATE of the connection.
+                        8 if stream_id == 0 => {
+                            let increment = u32::from_be_bytes([
+                                payload[0], payload[1], payload[2], payload[3],
+                            ]);
+                            received.push(format!("window+{increment}"));
+                            continue;
+                        }
+                        1 if ping => {
+                            pending = Some(stream_id);
+                            continue;
+                        }
+                        1 => response(stream_id, &received.join(",")),
+                        // PING, acknowledged, then the request answered.
+                        6 if flags & 1 == 0 => {
+                            let mut reply = vec![0, 0, 8, 6, 1, 0, 0, 0, 0];
+                            reply.extend_from_slice(&payload);
+                            if let Some(stream_id) = pending.take() {
+                                reply.extend(response(stream_id, "pong"));
+                            }
+                            reply
+                        }
+                        _ => continue,
+                    };
+                    stream
+                        .write_all(&reply)
+                        .await
+                        .expect("write reply");
+                }
+            })
+            .detach();
+        }
+    })
+    .detach();
+}
+
+/// Speaks HTTP/1.1, answering with the head of the request and `headers`
+/// extra response headers.
+fn serve_http1(addr: SocketAddr, headers: usize) {
+    let listener = glommio::net::TcpListener::bind(addr).expect("bind");
+    glommio::spawn_local(async move {
+        while let Ok(mut stream) = listener
+            .accept()
+            .await
+        {
+            glommio::spawn_local(async move {
+                let mut head = Vec::new();
+                let mut byte = [0; 1];
+                while !head.ends_with(b"\r\n\r\n") {
+                    stream
+                        .read_exact(&mut byte)
+                        .await
+                        .expect("read head");
+                    head.push(byte[0]);
+                }
+
+                let mut reply = format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\n", head.len());
+                for header in 0..headers {
+                    reply.push_str(&format!("X-Extra-{header}: {header}\r\n"));
+                }
+                reply.push_str("\r\n");
+                let mut reply = reply.into_bytes();
+                reply.extend_from_slice(&head);
+                let _ = stream
+                    .write_all(&reply)
+                    .await;
+            })
+            .detach();
+        }
+    })
+    .detach();
+}
+
+fn ephemeral_addr() -> SocketAddr {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind");
+    listener
+        .local_addr()
+        .expect("local_addr")
+}
+
+#[test]
+fn http2_settings_are_sent() {
+    let addr = ephemeral_addr();
+
+    glommio::LocalExecutorBuilder::default()
+        .make()
+        .expect("build glommio executor")
+        .run(async move {
+            serve_http2(addr, false);
+
+            let client = Client::builder()
+                .http2_initial_stream_window_size(1_048_576)
+                .http2_initial_connection_window_size(4_194_304)
+                .http2_max_frame_size(32_768)
+                .http2_max_concurrent_streams(8)
+                .http2_max_header_list_size(65_536)
+                .build();
+            let response = get(format!("http://{addr}/").as_str())
+                .expect("build request")
+                .version(Version::HTTP_2)
+                .send_with(&client)
+                .await
+                .expect("request failed");
+            let received = response
+                .text()
+                .await
+                .expect("body");
+            let received = received
+                .split(',')
+                .collect::<Vec<_>>();
+            for expected in ["3=8", "4=1048576", "5=32768", "6=65536", "window+4128769"] {
+                assert!(received.contains(&expected), "{expected} in {received:?}");
+            }
+        });
+}
+
+#[test]
+fn http2_keep_alive_pings_are_sent() {
+    let addr = ephemeral_addr();
+
+    glommio::LocalExecutorBuilder::default()
+        .make()
+        .expect("build glommio executor")
+        .run(async move {
+            serve_http2(addr, true);
+
+            let client = Client::builder()
+                .http2_keep_alive_interval(Duration::from_millis(100))
+                .http2_keep_alive_timeout(Duration::from_secs(5))
+                .build();
+            let response = get(format!("http://{addr}/").as_str())
+                .expect("build request")
+                .version(Version::HTTP_2)
+                .send_with(&client)
+                .await
+                .expect("request failed");
+            assert_eq!(
+                response
+                    .text()
+                    .await
+                    .expect("body"),
+                "pong"
+            );
+        });
+}
+
+#[test]
+fn http1_headers_are_title_cased() {
+    let addr = ephemeral_addr();
+
+    glommio::LocalExecutorBuilder::default()
+        .make()
+        .expect("build glommio executor")
+        .run(async move {
+            serve_http1(addr, 0);
+
+            let client = Client::builder()
+                .http1_title_case_headers(true)
+                .build();
+            let response = get(format!("http://{addr}/").as_str())
+                .expect("build request")
+                .header(http::header::HeaderName::from_static("x-tenant"), "acme")
+                .send_with(&client)
+                .await
+                .expect("request failed");
+            let head = response
+                .text()
+                .await
+                .expect("body");
+            assert!(head.contains("\r\nX-Tenant: acme\r\n"), "{head}");
+        });
+}
+
+#[test]
+fn http1_responses_over_max_headers_fail() {
+    let addr = ephemeral_addr();
+
+    glommio::LocalExecutorBuilder::default()
+        .make()
+        .expect("build glommio executor")
+        .run(async move {
+            serve_http1(addr, 16);
+
+            let client = Client::builder()
+                .http1_max_headers(8)
+                .build();
+            let result = get(format!("http://{addr}/").as_str())
+                .expect("build request")
+                .send_with(&client)
+                .await;
+            assert!(result.is_err());
+        });
+}
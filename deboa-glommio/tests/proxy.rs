@@ -0,0 +1,425 @@
+//! End-to-end through hand-rolled proxies: a forwarding HTTP proxy, a
+//! `CONNECT` proxy in front of a TLS server and a SOCKS5 proxy.
+//!
+//! The proxies only speak as much of their protocol as the client needs, so
+//! the assertions are about what the client sent them.
+
+use std::convert::Infallible;
+use std::net::SocketAddr;
+
+use deboa::proxy::Proxy;
+use deboa::request::get;
+use deboa_glommio::Client;
+use futures::io::{AsyncReadExt, AsyncWriteExt};
+use glommio::net::{TcpListener, TcpStream};
+use http_body_util::Full;
+use hyper::body::Bytes;
+
+fn serve(addr: SocketAddr, body: &'static str) {
+    let listener = TcpListener::bind(addr).expect("bind");
+    glommio::spawn_local(async move {
+        while let Ok(stream) = listener
+            .accept()
+            .await
+        {
+            glommio::spawn_local(async move {
+                let service = hyper::service::service_fn(move |_req| async move {
+                    Ok::<_, Infallible>(hyper::Response::new(Full::new(Bytes::from(body))))
+                });
+                let _ = hyper::server::conn::http1::Builder::new()
+                    .serve_connection(smol_hyper::rt::FuturesIo::new(stream), service)
+                    .await;
+            })
+            .detach();
+        }
+    })
+    .detach();
+}
+
+/// Answers every request itself, echoing the request target and the
+/// `Proxy-Authorization` header it received.
+fn serve_forward_proxy(addr: SocketAddr) {
+    let listener = TcpListener::bind(addr).expect("bind");
+    glommio::spawn_local(async move {
+        while let Ok(stream) = listener
+            .accept()
+            .await
+        {
+            glommio::spawn_local(async move {
+                let service = hyper::service::service_fn(|req: hyper::Request<_>| async move {
+                    let authorization = req
+                        .headers()
+                        .get(http::header::PROXY_AUTHORIZATION)
+                        .and_then(|value| value.to_str().ok())
+                        .unwrap_or_default()
+                        .to_string();
+                    let body = format!("{} {}", req.uri(), authorization);
+                    Ok::<_, Infallible>(hyper::Response::new(Full::new(Bytes::from(body))))
+                });
+                let _ = hyper::server::conn::http1::Builder::new()
+                    .serve_connection(smol_hyper::rt::FuturesIo::new(stream), service)
+                    .await;
+            })
+            .detach();
+        }
+    })
+    .detach();
+}
+
+/// Copy bytes both ways until either side closes.
+async fn bridge(client: TcpStream, upstream: TcpStream) {
+    let (mut client_read, mut client_write) = client.split();
+    let (mut upstream_read, mut upstream_write) = upstream.split();
+    let _ = futures::future::select(
+        Box::pin(futures::io::copy(&mut client_read, &mut upstream_write)),
+        Box::pin(futures::io::copy(&mut upstream_read, &mut client_write)),
+    )
+    .await;
+}
+
+/// Opens a tunnel to the `CONNECT` target, rejecting requests without the
+/// expected credentials.
+fn serve_connect_proxy(addr: SocketAddr, authorization: &'static str) {
+    let listener = TcpListener::bind(addr).expect("bind");
+    glommio::spawn_local(async move {
+        while let Ok(mut stream) = listener
+            .accept()
+            .await
+        {
+            glommio::spawn_local(async move {
+                let mut head = Vec::new();
+                let mut byte = [0u8; 1];
+                while !head.ends_with(b"\r\n\r\n") {
+                    if stream
+                        .read_exact(&mut byte)
+                        .await
+                        .is_err()
+                    {
+                        return;
+                    }
+                    head.push(byte[0]);
+                }
+
+                let head = String::from_utf8_lossy(&head).to_string();
+                let target = head
+                    .strip_prefix("CONNECT ")
+                    .and_then(|rest| {
+                        rest.split(' ')
+                            .next()
+                    })
+                    .unwrap_or_default()
+                    .to_string();
+                if !head.contains(&format!("Proxy-Authorization: {authorization}\r\n")) {
+                    let _ = stream
+                        .write_all(b"HTTP/1.1 407 Proxy Authentication Required\r\n\r\n")
+                        .await;
+                    return;
+                }
+
+                let Ok(upstream) = TcpStream::connect(target.as_str()).await else {
+                    let _ = stream
+                        .write_all(b"HTTP/1.1 502 Bad Gateway\r\n\r\n")
+                        .await;
+                    return;
+                };
+                let _ = stream
+                    .write_all(b"HTTP/1.1 200 Connection established\r\n\r\n")
+                    .await;
+                bridge(stream, upstream).await;
+            })
+            .detach();
+        }
+    })
+    .detach();
+}
+
+/// Minimal SOCKS5 proxy without authentication, only accepting domain names.
+fn serve_socks5_proxy(addr: SocketAddr) {
+    let listener = TcpListener::bind(addr).expect("bind");
+    glommio::spawn_local(async move {
+        while let Ok(mut stream) = listener
+            .accept()
+            .await
+        {
+            glommio::spawn_local(async move {
+                let mut greeting = [0u8; 2];
+                stream
+                    .read_exact(&mut greeting)
+                    .await
+                    .expect("greeting");
+                let mut methods = vec![0u8; greeting[1] as usize];
+                stream
+                    .read_exact(&mut methods)
+                    .await
+                    .expect("methods");
+                stream
+                    .write_all(&[0x05, 0x00])
+                    .await
+                    .expect("method selection");
+
+                let mut request = [0u8; 5];
+                stream
+                    .read_exact(&mut request)
+                    .await
+                    .expect("request");
+                assert_eq!(request[3], 0x03, "target should be sent as a domain name");
+                let mut host = vec![0u8; request[4] as usize];
+                stream
+                    .read_exact(&mut host)
+                    .await
+                    .expect("host");
+                let mut port = [0u8; 2];
+                stream
+                    .read_exact(&mut port)
+                    .await
+                    .expect("port");
+
+                let target =
+                    format!("{}:{}", String::from_utf8_lossy(&host), u16::from_be_bytes(port));
+                let upstream = TcpStream::connect(target.as_str())
+                    .await
+                    .expect("connect upstream");
+                stream
+                    .write_all(&[0x05, 0x00, 0x00, 0x01, 0, 0, 0, 0, 0, 0])
+                    .await
+                    .expect("reply");
+                bridge(stream, upstream).await;
+            })
+            .detach();
+        }
+    })
+    .detach();
+}
+
+fn ephemeral_addr() -> SocketAddr {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind");
+    listener
+        .local_addr()
+        .expect("local_addr")
+}
+
+#[test]
+fn plain_requests_are_forwarded_in_absolute_form() {
+    let proxy_addr = ephemeral_addr();
+
+    glommio::LocalExecutorBuilder::default()
+        .make()
+        .expect("build glommio executor")
+        .run(async move {
+            serve_forward_proxy(proxy_addr);
+
+            let proxy =
+                Proxy::http(format!("http://user:pass@{proxy_addr}").as_str()).expect("proxy url");
+            let client = Client::builder()
+                .proxy(proxy)
+                .build();
+            let response = get("http://origin.invalid/posts/1")
+                .expect("build request")
+                .version(http::Version::HTTP_11)
+                .send_with(&client)
+                .await
+                .expect("request failed");
+
+            assert_eq!(response.status(), http::StatusCode::OK);
+            assert_eq!(
+                response
+                    .text()
+                    .await
+                    .expect("body"),
+                "http://origin.invalid/posts/1 Basic dXNlcjpwYXNz"
+            );
+        });
+}
+
+#[test]
+fn no_proxy_hosts_are_reached_directly() {
+    let addr = ephemeral_addr();
+
+    glommio::LocalExecutorBuilder::default()
+        .make()
+        .expect("build glommio executor")
+        .run(async move {
+            serve(addr, "direct");
+
+            // Nothing listens on the proxy port, using it would fail.
+            let proxy = Proxy::all(format!("http://{}", ephemeral_addr()).as_str())
+                .expect("proxy url")
+                .no_proxy(deboa::proxy::NoProxy::new("127.0.0.1"));
+            let client = Client::builder()
+                .proxy(proxy)
+                .build();
+            let response = get(format!("http://{addr}/").as_str())
+                .expect("build request")
+                .version(http::Version::HTTP_11)
+                .send_with(&client)
+                .await
+                .expect("request failed");
+
+            assert_eq!(
+                response
+                    .text()
+                    .await
+                    .expect("body"),
+                "direct"
+            );
+        });
+}
+
+#[cfg(feature = "rust-tls")]
+mod tunnel {
+    use super::*;
+    use futures_rustls::{
+        rustls::{
+            self,
+            pki_types::{CertificateDer, PrivateKeyDer},
+        },
+        TlsAcceptor,
+    };
+    use std::sync::Arc;
+
+    const SERVER_CERT: &[u8] = include_bytes!("../../certs/server.der");
+    const SERVER_KEY: &[u8] = include_bytes!("../../certs/server.key.der");
+
+    fn serve_tls(addr: SocketAddr) {
+        let key = PrivateKeyDer::try_from(SERVER_KEY.to_vec()).expect("server key");
+        let config = rustls::ServerConfig::builder_with_provider(Arc::new(
+            rustls::crypto::ring::default_provider(),
+        ))
+        .with_safe_default_protocol_versions()
+        .expect("protocol versions")
+        .with_no_client_auth()
+        .with_single_cert(vec![CertificateDer::from(SERVER_CERT.to_vec())], key)
+        .expect("server config");
+        let acceptor = TlsAcceptor::from(Arc::new(config));
+
+        let listener = TcpListener::bind(addr).expect("bind");
+        glommio::spawn_local(async move {
+            while let Ok(stream) = listener
+                .accept()
+                .await
+            {
+                let acceptor = acceptor.clone();
+                glommio::spawn_local(async move {
+                    let Ok(stream) = acceptor
+                        .accept(stream)
+                        .await
+                    else {
+                        return;
+                    };
+                    let service = hyper::service::service_fn(|_req| async {
+                        Ok::<_, Infallible>(hyper::Response::new(Full::new(Bytes::from(
+                            "tunnelled",
+                        ))))
+                    });
+                    let _ = hyper::server::conn::http1::Builder::new()
+                        .serve_connection(smol_hyper::rt::FuturesIo::new(stream), service)
+                        .await;
+                })
+                .detach();
+            }
+        })
+        .detach();
+    }
+
+    #[test]
+    fn https_requests_are_tunnelled_with_connect() {
+        let addr = ephemeral_addr();
+        let proxy_addr = ephemeral_addr();
+
+        glommio::LocalExecutorBuilder::default()
+            .make()
+            .expect("build glommio executor")
+            .run(async move {
+                serve_tls(addr);
+                serve_connect_proxy(proxy_addr, "Basic dXNlcjpwYXNz");
+
+                let proxy = Proxy::https(format!("http://{proxy_addr}").as_str())
+                    .expect("proxy url")
+                    .basic_auth("user", "pass");
+                let client = Client::builder()
+                    .skip_cert_verification(true)
+                    .proxy(proxy)
+                    .build();
+                let response = get(format!("https://localhost:{}/", addr.port()).as_str())
+                    .expect("build request")
+                    .version(http::Version::HTTP_11)
+                    .send_with(&client)
+                    .await
+                    .expect("request failed");
+
+                assert_eq!(
+                    response
+                        .text()
+                        .await
+                        .expect("body"),
+                    "tunnelled"
+                );
+            });
+    }
+
+    #[test]
+    fn connect_without_credentials_is_rejected() {
+        let addr = ephemeral_addr();
+        let proxy_addr = ephemeral_addr();
+
+        glommio::LocalExecutorBuilder::default()
+            .make()
+            .expect("build glommio executor")
+            .run(async move {
+                serve_tls(addr);
+                serve_connect_proxy(proxy_addr, "Basic dXNlcjpwYXNz");
+
+                let proxy =
+                    Proxy::https(format!("http://{proxy_addr}").as_str()).expect("proxy url");
+                let client = Client::builder()
+                    .skip_cert_verification(true)
+                    .proxy(proxy)
+                    .build();
+                let response = get(format!("https://localhost:{}/", addr.port()).as_str())
+                    .expect("build request")
+                    .version(http::Version::HTTP_11)
+                    .send_with(&client)
+                    .await;
+
+                assert!(matches!(
+                    response,
+                    Err(deboa::errors::DeboaError::Connection(
+                        deboa::errors::ConnectionError::Proxy { .. }
+                    ))
+                ));
+            });
+    }
+}
+
+#[test]
+fn requests_go_through_a_socks5_proxy() {
+    let addr = ephemeral_addr();
+    let proxy_addr = ephemeral_addr();
+
+    glommio::LocalExecutorBuilder::default()
+        .make()
+        .expect("build glommio executor")
+        .run(async move {
+            serve(addr, "via socks");
+            serve_socks5_proxy(proxy_addr);
+
+            let proxy = Proxy::all(format!("socks5h://{proxy_addr}").as_str()).expect("proxy url");
+            let client = Client::builder()
+                .proxy(proxy)
+                .build();
+            let response = get(format!("http://localhost:{}/", addr.port()).as_str())
+                .expect("build request")
+                .version(http::Version::HTTP_11)
+                .send_with(&client)
+                .await
+                .expect("request failed");
+
+            assert_eq!(
+                response
+                    .text()
+                    .await
+                    .expect("body"),
+                "via socks"
+            );
+        });
+}
@@ -0,0 +1,216 @@
+//! End-to-end over TLS against a server whose certificate the client does not
+//! trust, to check `skip_cert_verification` reaches the rustls connector, and
+//! that the protocol policy drives ALPN.
+#![cfg(feature = "rust-tls")]
+
+use std::convert::Infallible;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use deboa::{protocol::ProtocolPolicy, request::get};
+use deboa_glommio::Client;
+use futures_rustls::{
+    rustls::{
+        self,
+        pki_types::{CertificateDer, PrivateKeyDer},
+    },
+    TlsAcceptor,
+};
+use http_body_util::Full;
+use hyper::body::Bytes;
+
+const SERVER_CERT: &[u8] = include_bytes!("../../certs/server.der");
+const SERVER_KEY: &[u8] = include_bytes!("../../certs/server.key.der");
+
+/// hyper's HTTP/2 server spawns its streams, on this core here.
+#[derive(Clone)]
+struct LocalExecutor;
+
+impl<F> hyper::rt::Executor<F> for LocalExecutor
+where
+    F: Future + 'static,
+{
+    fn execute(&self, fut: F) {
+        glommio::spawn_local(fut).detach();
+    }
+}
+
+fn acceptor(alpn: &[&[u8]]) -> TlsAcceptor {
+    let key = PrivateKeyDer::try_from(SERVER_KEY.to_vec()).expect("server key");
+    let mut config = rustls::ServerConfig::builder_with_provider(Arc::new(
+        rustls::crypto::ring::default_provider(),
+    ))
+    .with_safe_default_protocol_versions()
+    .expect("protocol versions")
+    .with_no_client_auth()
+    .with_single_cert(vec![CertificateDer::from(SERVER_CERT.to_vec())], key)
+    .expect("server config");
+    config.alpn_protocols = alpn
+        .iter()
+        .map(|protocol| protocol.to_vec())
+        .collect();
+    TlsAcceptor::from(Arc::new(config))
+}
+
+fn serve_tls(addr: SocketAddr) {
+    let listener = glommio::net::TcpListener::bind(addr).expect("bind");
+    let acceptor = acceptor(&[]);
+    glommio::spawn_local(async move {
+        while let Ok(stream) = listener
+            .accept()
+            .await
+        {
+            let acceptor = acceptor.clone();
+            glommio::spawn_local(async move {
+                let Ok(stream) = acceptor
+                    .accept(stream)
+                    .await
+                else {
+                    return;
+                };
+                let service = hyper::service::service_fn(|_req| async {
+                    Ok::<_, Infallible>(hyper::Response::new(Full::new(Bytes::from("secure"))))
+                });
+                let _ = hyper::server::conn::http1::Builder::new()
+                    .serve_connection(smol_hyper::rt::FuturesIo::new(stream), service)
+                    .await;
+            })
+            .detach();
+        }
+    })
+    .detach();
+}
+
+/// Offers HTTP/2 and HTTP/1.1 over ALPN, answering with the version of the
+/// request.
+fn serve_alpn(addr: SocketAddr) {
+    let listener = glommio::net::TcpListener::bind(addr).expect("bind");
+    let acceptor = acceptor(&[b"h2", b"http/1.1"]);
+    glommio::spawn_local(async move {
+        while let Ok(stream) = listener
+            .accept()
+            .await
+        {
+            let acceptor = acceptor.clone();
+            glommio::spawn_local(async move {
+                let Ok(stream) = acceptor
+                    .accept(stream)
+                    .await
+                else {
+                    return;
+                };
+                let http2 = stream
+                    .get_ref()
+                    .1
+                    .alpn_protocol()
+                    == Some(b"h2");
+                let service = hyper::service::service_fn(|req: hyper::Request<_>| async move {
+                    let body = format!("{:?}", req.version());
+                    Ok::<_, Infallible>(hyper::Response::new(Full::new(Bytes::from(body))))
+                });
+                let io = smol_hyper::rt::FuturesIo::new(stream);
+                let _ = if http2 {
+                    hyper::server::conn::http2::Builder::new(LocalExecutor)
+                        .serve_connection(io, service)
+                        .await
+                } else {
+                    hyper::server::conn::http1::Builder::new()
+                        .serve_connection(io, service)
+                        .await
+                };
+            })
+            .detach();
+        }
+    })
+    .detach();
+}
+
+fn ephemeral_addr() -> SocketAddr {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind");
+    listener
+        .local_addr()
+        .expect("local_addr")
+}
+
+#[test]
+fn skipping_verification_accepts_an_untrusted_certificate() {
+    let addr = ephemeral_addr();
+
+    glommio::LocalExecutorBuilder::default()
+        .make()
+        .expect("build glommio executor")
+        .run(async move {
+            serve_tls(addr);
+
+            let client = Client::builder()
+                .skip_cert_verification(true)
+                .build();
+            let response = get(format!("https://localhost:{}/", addr.port()).as_str())
+                .expect("build request")
+                .version(http::Version::HTTP_11)
+                .send_with(&client)
+                .await
+                .expect("request failed");
+
+            assert_eq!(response.status(), http::StatusCode::OK);
+        });
+}
+
+#[test]
+fn an_untrusted_certificate_is_rejected_by_default() {
+    let addr = ephemeral_addr();
+
+    glommio::LocalExecutorBuilder::default()
+        .make()
+        .expect("build glommio executor")
+        .run(async move {
+            serve_tls(addr);
+
+            let client = Client::default();
+            let response = get(format!("https://localhost:{}/", addr.port()).as_str())
+                .expect("build request")
+                .version(http::Version::HTTP_11)
+                .send_with(&client)
+                .await;
+
+            assert!(response.is_err());
+        });
+}
+
+#[test]
+fn alpn_follows_the_protocol_policy() {
+    let addr = ephemeral_addr();
+
+    glommio::LocalExecutorBuilder::default()
+        .make()
+        .expect("build glommio executor")
+        .run(async move {
+            serve_alpn(addr);
+
+            for (policy, version) in [
+                (ProtocolPolicy::PreferHttp2, http::Version::HTTP_2),
+                (ProtocolPolicy::Http1Only, http::Version::HTTP_11),
+                (ProtocolPolicy::Http2Only, http::Version::HTTP_2),
+            ] {
+                let client = Client::builder()
+                    .skip_cert_verification(true)
+                    .protocol_policy(policy)
+                    .build();
+                let response = get(format!("https://localhost:{}/", addr.port()).as_str())
+                    .expect("build request")
+                    .send_with(&client)
+                    .await
+                    .expect("request failed");
+
+                assert_eq!(response.version(), version, "{policy:?}");
+                assert_eq!(
+                    response
+                        .text()
+                        .await
+                        .expect("body"),
+                    format!("{version:?}")
+                );
+            }
+        });
+}
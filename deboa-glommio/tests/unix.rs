@@ -0,0 +1,168 @@
+//! End-to-end: a client set with a Unix socket sends every request to it, in
+//! HTTP/1.1 or in h2c with prior knowledge.
+
+use std::convert::Infallible;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+
+use deboa::request::get;
+use deboa_glommio::Client;
+use http_body_util::Full;
+use hyper::body::Bytes;
+
+/// hyper's HTTP/2 server spawns its streams, on this core here.
+#[derive(Clone)]
+struct LocalExecutor;
+
+impl<F> hyper::rt::Executor<F> for LocalExecutor
+where
+    F: Future + 'static,
+{
+    fn execute(&self, fut: F) {
+        glommio::spawn_local(fut).detach();
+    }
+}
+
+/// A socket path unique to the test.
+fn socket_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("deboa-{}-{name}.sock", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+/// Answers with the protocol version and the path of the request.
+fn serve(path: &Path, http2: bool) {
+    let listener = glommio::net::UnixListener::bind(path).expect("bind");
+    glommio::spawn_local(async move {
+        while let Ok(stream) = listener
+            .accept()
+            .await
+        {
+            glommio::spawn_local(async move {
+                let service = hyper::service::service_fn(|req: hyper::Request<_>| async move {
+                    let body = format!("{:?} {}", req.version(), req.uri().path());
+                    Ok::<_, Infallible>(hyper::Response::new(Full::new(Bytes::from(body))))
+                });
+                let io = smol_hyper::rt::FuturesIo::new(stream);
+                let _ = if http2 {
+                    hyper::server::conn::http2::Builder::new(LocalExecutor)
+                        .serve_connection(io, service)
+                        .await
+                } else {
+                    hyper::server::conn::http1::Builder::new()
+                        .serve_connection(io, service)
+                        .await
+                };
+            })
+            .detach();
+        }
+    })
+    .detach();
+}
+
+#[test]
+fn requests_are_sent_over_the_socket() {
+    let path = socket_path("http1");
+
+    glommio::LocalExecutorBuilder::default()
+        .make()
+        .expect("build glommio executor")
+        .run(async move {
+            serve(&path, false);
+
+            let client = Client::builder()
+                .unix_socket(&path)
+                .build();
+            for endpoint in ["/_ping", "/containers/json"] {
+                let response = get(format!("http://docker{endpoint}").as_str())
+                    .expect("build request")
+                    .send_with(&client)
+                    .await
+                    .expect("request failed");
+                assert_eq!(
+                    response
+                        .text()
+                        .await
+                        .expect("body"),
+                    format!("HTTP/1.1 {endpoint}")
+                );
+            }
+
+            let _ = std::fs::remove_file(&path);
+        });
+}
+
+#[test]
+fn h2c_is_used_with_prior_knowledge() {
+    let path = socket_path("h2c");
+
+    glommio::LocalExecutorBuilder::default()
+        .make()
+        .expect("build glommio executor")
+        .run(async move {
+            serve(&path, true);
+
+            let client = Client::builder()
+                .unix_socket(&path)
+                .http2_prior_knowledge(true)
+                .build();
+            let response = get("http://localhost/v1/health")
+                .expect("build request")
+                .send_with(&client)
+                .await
+                .expect("request failed");
+            assert_eq!(
+                response
+                    .text()
+                    .await
+                    .expect("body"),
+                "HTTP/2.0 /v1/health"
+            );
+
+            let _ = std::fs::remove_file(&path);
+        });
+}
+
+#[test]
+fn tls_is_refused_over_the_socket() {
+    let path = socket_path("tls");
+
+    glommio::LocalExecutorBuilder::default()
+        .make()
+        .expect("build glommio executor")
+        .run(async move {
+            serve(&path, false);
+
+            let client = Client::builder()
+                .unix_socket(&path)
+                .build();
+            let error = get("https://localhost/")
+                .expect("build request")
+                .send_with(&client)
+                .await
+                .expect_err("TLS over a Unix socket");
+            assert!(error.is_connect(), "{error:?}");
+
+            let _ = std::fs::remove_file(&path);
+        });
+}
+
+#[test]
+fn a_missing_socket_fails_to_connect() {
+    let path = socket_path("missing");
+
+    glommio::LocalExecutorBuilder::default()
+        .make()
+        .expect("build glommio executor")
+        .run(async move {
+            let client = Client::builder()
+                .unix_socket(&path)
+                .build();
+            let error = get("http://localhost/")
+                .expect("build request")
+                .send_with(&client)
+                .await
+                .expect_err("no server on the socket");
+            assert!(error.is_connect(), "{error:?}");
+        });
+}
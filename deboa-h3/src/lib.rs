@@ -7,7 +7,13 @@ pub mod generic {
     use http::{Request, Response};
     use http_body_util::BodyExt as _;
     use hyper_body_utils::HttpBody;
-    use std::marker::PhantomData;
+    use std::{
+        marker::PhantomData,
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        },
+    };
 
     pub type QuicRequest = h3::client::SendRequest<OpenStreams, Bytes>;
     pub type Http3Request = SendRequest<QuicRequest, HttpBody>;
@@ -15,12 +21,32 @@ pub mod generic {
 
     pub struct SendRequest<Sender, Body> {
         sender: Sender,
+        closed: Arc<AtomicBool>,
         _p: PhantomData<Body>,
     }
 
+    impl<Sender: Clone, Body> Clone for SendRequest<Sender, Body> {
+        fn clone(&self) -> Self {
+            Self { sender: self.sender.clone(), closed: self.closed.clone(), _p: PhantomData }
+        }
+    }
+
+    impl<Sender, Body> SendRequest<Sender, Body> {
+        /// Get the flag the driver of the connection sets once it is closed.
+        pub fn closed(&self) -> Arc<AtomicBool> {
+            self.closed.clone()
+        }
+
+        /// Check if the connection was closed, by the peer or its idle timeout.
+        pub fn is_closed(&self) -> bool {
+            self.closed
+                .load(Ordering::Acquire)
+        }
+    }
+
     impl SendRequest<QuicRequest, HttpBody> {
         pub fn new(sender: QuicRequest) -> Self {
-            Self { sender, _p: PhantomData }
+            Self { sender, closed: Arc::default(), _p: PhantomData }
         }
 
         pub async fn send_request(
@@ -29,7 +55,12 @@ pub mod generic {
         ) -> std::result::Result<http::Response<HttpBody>, StreamError> {
             let mut sender = self.sender.clone();
 
-            let (parts, mut body) = request.into_parts();
+            let (mut parts, mut body) = request.into_parts();
+            // The authority is sent as `:authority`, a `Host` header
+            // disagreeing with it on the port would fail the request.
+            parts
+                .headers
+                .remove(http::header::HOST);
 
             let bodyless_request = Request::from_parts(parts, ());
 
@@ -67,13 +98,23 @@ pub mod generic {
 
 #[cfg(feature = "compio")]
 pub mod compio {
-    use bytes::Bytes;
+    use bytes::{Buf as _, Bytes};
     use compio_quic::{h3::OpenStreams, RecvStream};
+    use futures::{ready, Stream};
     use h3::{client::RequestStream, error::StreamError};
     use http::{Request, Response};
     use http_body_util::BodyExt as _;
     use hyper_body_utils::HttpBody;
-    use std::marker::PhantomData;
+    use send_wrapper::SendWrapper;
+    use std::{
+        marker::PhantomData,
+        pin::Pin,
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        },
+        task::{Context, Poll},
+    };
 
     pub type QuicRequest = h3::client::SendRequest<OpenStreams, Bytes>;
     pub type Http3Request = SendRequest<QuicRequest, HttpBody>;
@@ -81,12 +122,32 @@ pub mod compio {
 
     pub struct SendRequest<Sender, Body> {
         sender: Sender,
+        closed: Arc<AtomicBool>,
         _p: PhantomData<Body>,
     }
 
+    impl<Sender: Clone, Body> Clone for SendRequest<Sender, Body> {
+        fn clone(&self) -> Self {
+            Self { sender: self.sender.clone(), closed: self.closed.clone(), _p: PhantomData }
+        }
+    }
+
+    impl<Sender, Body> SendRequest<Sender, Body> {
+        /// Get the flag the driver of the connection sets once it is closed.
+        pub fn closed(&self) -> Arc<AtomicBool> {
+            self.closed.clone()
+        }
+
+        /// Check if the connection was closed, by the peer or its idle timeout.
+        pub fn is_closed(&self) -> bool {
+            self.closed
+                .load(Ordering::Acquire)
+        }
+    }
+
     impl SendRequest<QuicRequest, HttpBody> {
         pub fn new(sender: QuicRequest) -> Self {
-            Self { sender, _p: PhantomData }
+            Self { sender, closed: Arc::default(), _p: PhantomData }
         }
 
         pub async fn send_request(
@@ -95,7 +156,12 @@ pub mod compio {
         ) -> std::result::Result<http::Response<HttpBody>, StreamError> {
             let mut sender = self.sender.clone();
 
-            let (parts, mut body) = request.into_parts();
+            let (mut parts, mut body) = request.into_parts();
+            // The authority is sent as `:authority`, a `Host` header
+            // disagreeing with it on the port would fail the request.
+            parts
+                .headers
+                .remove(http::header::HOST);
 
             let bodyless_request = Request::from_parts(parts, ());
 
@@ -124,9 +190,30 @@ pub mod compio {
 
             let (parts, _) = response.into_parts();
 
-            let body = HttpBody::from_compio_client(recv_stream);
+            // The streams of compio are bound to the thread of their
+            // runtime, the body is kept there instead of requiring `Send`.
+            let body = HttpBody::from_compio_stream(SendWrapper::new(LocalBody(recv_stream)));
             let response = Response::from_parts(parts, body);
             Ok(response)
         }
     }
+
+    /// Response body read from a QUIC stream.
+    struct LocalBody(HttpStream);
+
+    impl Stream for LocalBody {
+        type Item = std::io::Result<Bytes>;
+
+        fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+            match ready!(self
+                .get_mut()
+                .0
+                .poll_recv_data(cx))
+            {
+                Ok(Some(mut data)) => Poll::Ready(Some(Ok(data.copy_to_bytes(data.remaining())))),
+                Ok(None) => Poll::Ready(None),
+                Err(e) => Poll::Ready(Some(Err(std::io::Error::other(e)))),
+            }
+        }
+    }
 }
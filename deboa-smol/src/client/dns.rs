@@ -1,6 +1,6 @@
 use deboa::{
     dns::DnsResolver,
-    errors::{DeboaError::Dns, DnsError},
+    errors::{DeboaError::Dns, DnsError, ErrorSource},
 };
 use rand::seq::SliceRandom;
 use smol::net::resolve;
@@ -15,7 +15,11 @@ impl DnsResolver for DefaultDnsResolver {
         let hostname = format!("{}:{}", host, port);
         let addrs = resolve(hostname).await;
         if let Err(e) = addrs {
-            return Err(Dns(DnsError::Resolve { host, message: e.to_string() }));
+            return Err(Dns(DnsError::Resolve {
+                host,
+                message: e.to_string(),
+                source: Some(ErrorSource::new(e)),
+            }));
         };
 
         let mut ips: Vec<IpAddr> = addrs
@@ -4,12 +4,13 @@ use crate::{
 };
 use deboa::{
     conn::{HttpConnection, ProtoConnection},
-    errors::{ConnectionError, DeboaError},
+    errors::{ConnectionError, DeboaError, ErrorSource},
+    protocol::ProtocolSettings,
     request::Http1Request,
     Result,
 };
 use http::version::Version;
-use hyper::client::conn::http1::handshake;
+use hyper::rt::{Read, Write};
 use smol_hyper::rt::FuturesIo;
 
 impl HttpConnection for Http1Connection {
@@ -28,26 +29,44 @@ impl ProtoConnection for Http1Connection {
         Version::HTTP_11
     }
 
-    async fn connect(stream: Self::RuntimeStream) -> Result<Self::Connection> {
-        let (sender, conn) = handshake(FuturesIo::new(stream))
-            .await
-            .map_err(|e| {
-                DeboaError::Connection(ConnectionError::Handshake { message: e.to_string() })
-            })?;
+    async fn connect(
+        stream: Self::RuntimeStream,
+        settings: &ProtocolSettings,
+    ) -> Result<Self::Connection> {
+        connect_io(FuturesIo::new(stream), settings).await
+    }
+}
 
-        smol::spawn(async move {
-            match conn
-                .with_upgrades()
-                .await
-            {
-                Ok(_) => (),
-                Err(err) => {
-                    log::error!("Error: {:#}", err)
-                }
-            };
-        })
-        .detach();
+/// Handshake over any connection, a runtime stream or one that declined an
+/// h2c upgrade.
+pub(crate) async fn connect_io<T>(io: T, settings: &ProtocolSettings) -> Result<Http1Connection>
+where
+    T: Read + Write + Unpin + Send + 'static,
+{
+    let (sender, conn) = settings
+        .http1()
+        .builder()
+        .handshake(io)
+        .await
+        .map_err(|e| {
+            DeboaError::Connection(ConnectionError::Handshake {
+                message: e.to_string(),
+                source: Some(ErrorSource::new(e)),
+            })
+        })?;
 
-        Ok(BaseHttpConnection::new(sender))
-    }
+    smol::spawn(async move {
+        match conn
+            .with_upgrades()
+            .await
+        {
+            Ok(_) => (),
+            Err(err) => {
+                log::error!("Error: {:#}", err)
+            }
+        };
+    })
+    .detach();
+
+    Ok(BaseHttpConnection::new(sender))
 }
@@ -7,3 +7,21 @@
 pub mod native;
 #[cfg(feature = "rust-tls")]
 pub mod rustls;
+
+#[cfg(any(feature = "native-tls", feature = "rust-tls"))]
+use deboa::protocol::{ProtocolPolicy, ALPN_HTTP1, ALPN_HTTP2};
+
+/// Get the ALPN protocols offered over TLS under a policy, leaving out the
+/// ones of disabled features.
+#[cfg(any(feature = "native-tls", feature = "rust-tls"))]
+pub(crate) fn alpn_protocols(policy: ProtocolPolicy) -> Vec<&'static str> {
+    policy
+        .alpn()
+        .iter()
+        .copied()
+        .filter(|protocol| {
+            (*protocol == ALPN_HTTP1 && cfg!(feature = "http1"))
+                || (*protocol == ALPN_HTTP2 && cfg!(feature = "http2"))
+        })
+        .collect()
+}
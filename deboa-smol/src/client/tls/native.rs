@@ -4,20 +4,14 @@ use crate::cert::{DeboaCertificate, DeboaIdentity};
 use async_native_tls::{Certificate, Identity, TlsConnector, TlsStream};
 use deboa::{
     errors::{ConnectionError, DeboaError},
+    protocol::ProtocolPolicy,
     Result,
 };
 use smol::net::TcpStream;
 
 #[inline]
-pub(crate) fn alpn() -> &'static [&'static str] {
-    &[
-        #[cfg(feature = "http3")]
-        "h3",
-        #[cfg(feature = "http2")]
-        "h2",
-        #[cfg(feature = "http1")]
-        "http/1.1",
-    ]
+pub(crate) fn alpn(policy: ProtocolPolicy) -> Vec<&'static str> {
+    super::alpn_protocols(policy)
 }
 
 /// Builder for TLS connections using native-tls
@@ -27,7 +21,7 @@ pub struct TlsConnectionBuilder<'a> {
     identity: Option<&'a DeboaIdentity>,
     certificate: Option<&'a DeboaCertificate>,
     skip_server_verification: bool,
-    alpn: &'a [&'a str],
+    alpn: Vec<&'a str>,
 }
 
 impl<'a> TlsConnectionBuilder<'a> {
@@ -39,7 +33,7 @@ impl<'a> TlsConnectionBuilder<'a> {
             identity: None,
             certificate: None,
             skip_server_verification: false,
-            alpn: alpn(),
+            alpn: alpn(ProtocolPolicy::default()),
         }
     }
 
@@ -62,8 +56,8 @@ impl<'a> TlsConnectionBuilder<'a> {
     }
 
     /// Sets the ALPN protocols to use
-    pub fn alpn(mut self, alpn: &'a [&str]) -> Self {
-        self.alpn = alpn;
+    pub fn alpn(mut self, alpn: &[&'a str]) -> Self {
+        self.alpn = alpn.to_vec();
         self
     }
 
@@ -79,7 +73,7 @@ impl<'a> TlsConnectionBuilder<'a> {
             builder
         };
 
-        let builder = builder.request_alpns(self.alpn);
+        let builder = builder.request_alpns(&self.alpn);
 
         let builder = if let Some(ca) = self.certificate {
             let cert: Certificate = ca
@@ -87,6 +81,7 @@ impl<'a> TlsConnectionBuilder<'a> {
                 .map_err(|e| {
                     DeboaError::Connection(ConnectionError::Tls {
                         message: format!("Invalid CA certificate: {}", e),
+                        source: None,
                     })
                 })?;
             builder.add_root_certificate(cert)
@@ -100,6 +95,7 @@ impl<'a> TlsConnectionBuilder<'a> {
                 .map_err(|e| {
                     DeboaError::Connection(ConnectionError::Tls {
                         message: format!("Invalid client identity: {}", e),
+                        source: None,
                     })
                 })?;
             builder.identity(ident)
@@ -117,6 +113,7 @@ impl<'a> TlsConnectionBuilder<'a> {
             .map_err(|e| {
                 DeboaError::Connection(ConnectionError::Tls {
                     message: format!("Could not connect to server: {}", e),
+                    source: None,
                 })
             });
 
@@ -2,6 +2,8 @@
 use async_native_tls::TlsStream;
 #[cfg(feature = "rust-tls")]
 use futures_rustls::client::TlsStream;
+#[cfg(unix)]
+use smol::net::unix::UnixStream;
 use smol::{
     io::{self, AsyncRead, AsyncWrite},
     net::TcpStream,
@@ -11,11 +13,15 @@ use std::{
     task::{Context, Poll},
 };
 
-/// A stream that can be either plain TCP or TLS-secured.
+/// A stream that can be either plain TCP, TLS-secured or a Unix socket.
 pub enum SmolStream {
     /// A plain TCP connection.
     Plain(TcpStream),
 
+    /// A Unix socket connection.
+    #[cfg(unix)]
+    Unix(UnixStream),
+
     /// A TCP connection secured by native TLS.
     #[cfg(feature = "native-tls")]
     Tls(TlsStream<TcpStream>),
@@ -43,6 +49,8 @@ impl AsyncRead for SmolStream {
     ) -> Poll<io::Result<usize>> {
         match &mut *self {
             SmolStream::Plain(stream) => Pin::new(stream).poll_read(cx, buf),
+            #[cfg(unix)]
+            SmolStream::Unix(stream) => Pin::new(stream).poll_read(cx, buf),
             #[cfg(any(feature = "native-tls", feature = "rust-tls"))]
             SmolStream::Tls(stream) => Pin::new(stream).poll_read(cx, buf),
         }
@@ -67,6 +75,8 @@ impl AsyncWrite for SmolStream {
     ) -> Poll<io::Result<usize>> {
         match &mut *self {
             SmolStream::Plain(stream) => Pin::new(stream).poll_write(cx, buf),
+            #[cfg(unix)]
+            SmolStream::Unix(stream) => Pin::new(stream).poll_write(cx, buf),
             #[cfg(any(feature = "native-tls", feature = "rust-tls"))]
             SmolStream::Tls(stream) => Pin::new(stream).poll_write(cx, buf),
         }
@@ -75,6 +85,8 @@ impl AsyncWrite for SmolStream {
     fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
         match &mut *self {
             SmolStream::Plain(stream) => Pin::new(stream).poll_close(cx),
+            #[cfg(unix)]
+            SmolStream::Unix(stream) => Pin::new(stream).poll_close(cx),
             #[cfg(any(feature = "native-tls", feature = "rust-tls"))]
             SmolStream::Tls(stream) => Pin::new(stream).poll_close(cx),
         }
@@ -83,6 +95,8 @@ impl AsyncWrite for SmolStream {
     fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
         match &mut *self {
             SmolStream::Plain(stream) => Pin::new(stream).poll_flush(cx),
+            #[cfg(unix)]
+            SmolStream::Unix(stream) => Pin::new(stream).poll_flush(cx),
             #[cfg(any(feature = "native-tls", feature = "rust-tls"))]
             SmolStream::Tls(stream) => Pin::new(stream).poll_flush(cx),
         }
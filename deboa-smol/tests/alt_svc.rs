@@ -0,0 +1,241 @@
+//! End-to-end: an origin advertising an HTTP/3 alternative with `Alt-Svc` is
+//! reached over QUIC on the next requests, and over TCP again while the
+//! alternative is broken. HTTP/3 tried first on an origin falls back to TCP
+//! the same way.
+#![cfg(all(feature = "http3", feature = "rust-tls"))]
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use bytes::Bytes;
+use deboa::{protocol::ProtocolPolicy, request::get};
+use deboa_smol::{client::http::conn::pool::HttpConnectionPool, Client};
+use futures_rustls::{
+    rustls::{
+        self,
+        pki_types::{CertificateDer, PrivateKeyDer},
+    },
+    TlsAcceptor,
+};
+use futures_util::{AsyncReadExt, AsyncWriteExt};
+use http::Version;
+use quinn::crypto::rustls::QuicServerConfig;
+
+const SERVER_CERT: &[u8] = include_bytes!("../../certs/server.der");
+const SERVER_KEY: &[u8] = include_bytes!("../../certs/server.key.der");
+
+fn server_config(alpn: &[u8]) -> rustls::ServerConfig {
+    let key = PrivateKeyDer::try_from(SERVER_KEY.to_vec()).expect("server key");
+    let mut config = rustls::ServerConfig::builder_with_provider(Arc::new(
+        rustls::crypto::aws_lc_rs::default_provider(),
+    ))
+    .with_safe_default_protocol_versions()
+    .expect("protocol versions")
+    .with_no_client_auth()
+    .with_single_cert(vec![CertificateDer::from(SERVER_CERT.to_vec())], key)
+    .expect("server config");
+    config.alpn_protocols = vec![alpn.to_vec()];
+    config
+}
+
+/// Speaks HTTP/1.1 over TLS, answering every request with `tcp` and the
+/// `Alt-Svc` header.
+async fn serve_tls(alt_svc: String) -> SocketAddr {
+    let listener = smol::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("bind");
+    let addr = listener
+        .local_addr()
+        .expect("local_addr");
+    let acceptor = TlsAcceptor::from(Arc::new(server_config(b"http/1.1")));
+    smol::spawn(async move {
+        while let Ok((stream, _)) = listener
+            .accept()
+            .await
+        {
+            let acceptor = acceptor.clone();
+            let alt_svc = alt_svc.clone();
+            smol::spawn(async move {
+                let Ok(mut stream) = acceptor
+                    .accept(stream)
+                    .await
+                else {
+                    return;
+                };
+                loop {
+                    let mut head = Vec::new();
+                    let mut byte = [0; 1];
+                    while !head.ends_with(b"\r\n\r\n") {
+                        if stream
+                            .read_exact(&mut byte)
+                            .await
+                            .is_err()
+                        {
+                            return;
+                        }
+                        head.push(byte[0]);
+                    }
+                    let reply = format!(
+                        "HTTP/1.1 200 OK\r\nAlt-Svc: {alt_svc}\r\nContent-Length: 3\r\n\r\ntcp"
+                    );
+                    if stream
+                        .write_all(reply.as_bytes())
+                        .await
+                        .is_err()
+                    {
+                        return;
+                    }
+                }
+            })
+            .detach();
+        }
+    })
+    .detach();
+    addr
+}
+
+/// Speaks HTTP/3, answering every request with `h3`.
+fn serve_h3() -> SocketAddr {
+    let crypto = QuicServerConfig::try_from(server_config(b"h3")).expect("quic config");
+    let endpoint = quinn::Endpoint::server(
+        quinn::ServerConfig::with_crypto(Arc::new(crypto)),
+        "127.0.0.1:0"
+            .parse()
+            .expect("addr"),
+    )
+    .expect("endpoint");
+    let addr = endpoint
+        .local_addr()
+        .expect("local_addr");
+    smol::spawn(async move {
+        while let Some(incoming) = endpoint
+            .accept()
+            .await
+        {
+            smol::spawn(async move {
+                let Ok(conn) = incoming.await else {
+                    return;
+                };
+                let Ok(mut conn) =
+                    h3::server::Connection::<_, Bytes>::new(h3_quinn::Connection::new(conn)).await
+                else {
+                    return;
+                };
+                while let Ok(Some(resolver)) = conn.accept().await {
+                    let Ok((_, mut stream)) = resolver
+                        .resolve_request()
+                        .await
+                    else {
+                        continue;
+                    };
+                    let _ = stream
+                        .send_response(http::Response::new(()))
+                        .await;
+                    let _ = stream
+                        .send_data(Bytes::from("h3"))
+                        .await;
+                    let _ = stream
+                        .finish()
+                        .await;
+                }
+            })
+            .detach();
+        }
+    })
+    .detach();
+    addr
+}
+
+async fn fetch(client: &Client, addr: SocketAddr) -> (Version, String) {
+    let response = get(format!("https://{addr}/").as_str())
+        .expect("build request")
+        .send_with(client)
+        .await
+        .expect("request failed");
+    let version = response.version();
+    let body = response
+        .text()
+        .await
+        .expect("body");
+    (version, body)
+}
+
+#[test]
+fn later_requests_use_the_http3_alternative() {
+    smol::block_on(async {
+        let h3_addr = serve_h3();
+        let addr = serve_tls(format!("h3=\":{}\"; ma=60", h3_addr.port())).await;
+
+        let client = Client::builder()
+            .skip_cert_verification(true)
+            .build();
+        let (version, body) = fetch(&client, addr).await;
+        assert_eq!(body, "tcp");
+        assert_eq!(version, Version::HTTP_11);
+
+        for _ in 0..2 {
+            let (version, body) = fetch(&client, addr).await;
+            assert_eq!(body, "h3");
+            assert_eq!(version, Version::HTTP_3);
+        }
+    });
+}
+
+#[test]
+fn a_broken_alternative_falls_back_to_tcp() {
+    smol::block_on(async {
+        // Nothing answers on this port, the QUIC handshake never completes.
+        let silent = std::net::UdpSocket::bind("127.0.0.1:0").expect("bind");
+        let port = silent
+            .local_addr()
+            .expect("local_addr")
+            .port();
+        let addr = serve_tls(format!("h3=\":{port}\"")).await;
+
+        let client = Client::builder()
+            .skip_cert_verification(true)
+            .connection_timeout(Duration::from_secs(2))
+            .build();
+        let (_, body) = fetch(&client, addr).await;
+        assert_eq!(body, "tcp");
+
+        let (_, body) = fetch(&client, addr).await;
+        assert_eq!(body, "tcp");
+
+        // Marked broken, the alternative is not tried again.
+        let start = Instant::now();
+        let (_, body) = fetch(&client, addr).await;
+        assert_eq!(body, "tcp");
+        assert!(start.elapsed() < Duration::from_millis(500));
+    });
+}
+
+#[test]
+fn preferring_http3_falls_back_to_tcp_within_the_timeout() {
+    smol::block_on(async {
+        let addr = serve_tls("clear".to_string()).await;
+        // Nothing answers over UDP on the port of the origin.
+        let _silent = std::net::UdpSocket::bind(addr).expect("bind");
+
+        // Without idle connections, every request opens a new one.
+        let mut pool = HttpConnectionPool::default();
+        pool.set_max_idle_connections(0);
+        let client = Client::builder()
+            .skip_cert_verification(true)
+            .protocol_policy(ProtocolPolicy::PreferHttp3)
+            .connection_timeout(Duration::from_secs(2))
+            .connection_pool(pool)
+            .build();
+        let (version, body) = fetch(&client, addr).await;
+        assert_eq!(body, "tcp");
+        assert_eq!(version, Version::HTTP_11);
+
+        // Marked broken, QUIC is not tried again.
+        let start = Instant::now();
+        let (version, body) = fetch(&client, addr).await;
+        assert_eq!(body, "tcp");
+        assert_eq!(version, Version::HTTP_11);
+        assert!(start.elapsed() < Duration::from_millis(500));
+    });
+}
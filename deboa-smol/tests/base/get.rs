@@ -52,6 +52,25 @@ async fn test_get_http_skip_verification(
     .await
 }
 
+#[rstest]
+#[test_attr(apply(test))]
+async fn test_get_http_skip_verification_without_ca(
+    #[future] create_server: EasyHttpMock<VetisAdapter>,
+    protocol_version: http::Version,
+) -> TestResult<()> {
+    let client = Client::builder()
+        .skip_cert_verification(true)
+        .build();
+
+    deboa_test_utils::base::get::test_skip_cert_verification(
+        &client,
+        &mut create_server.await,
+        protocol_version,
+        true,
+    )
+    .await
+}
+
 #[rstest]
 #[test_attr(apply(test))]
 async fn test_get_http_verify(
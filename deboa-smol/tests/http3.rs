@@ -0,0 +1,181 @@
+//! End-to-end: the QUIC connections of a client share one endpoint, and
+//! follow the HTTP/3 settings of the client. A request asking for HTTP/3 is
+//! sent over QUIC only.
+#![cfg(all(feature = "http3", feature = "rust-tls"))]
+
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use bytes::Bytes;
+use deboa::{protocol::ProtocolPolicy, request::get};
+use deboa_smol::Client;
+use futures_rustls::rustls::{
+    self,
+    pki_types::{CertificateDer, PrivateKeyDer},
+};
+use http::Version;
+use quinn::crypto::rustls::QuicServerConfig;
+
+const SERVER_CERT: &[u8] = include_bytes!("../../certs/server.der");
+const SERVER_KEY: &[u8] = include_bytes!("../../certs/server.key.der");
+
+/// Speaks HTTP/3, answering every request with `h3` and recording the
+/// address of every connection.
+fn serve_h3() -> (SocketAddr, Arc<Mutex<Vec<SocketAddr>>>) {
+    let key = PrivateKeyDer::try_from(SERVER_KEY.to_vec()).expect("server key");
+    let mut config = rustls::ServerConfig::builder_with_provider(Arc::new(
+        rustls::crypto::aws_lc_rs::default_provider(),
+    ))
+    .with_protocol_versions(&[&rustls::version::TLS13])
+    .expect("protocol versions")
+    .with_no_client_auth()
+    .with_single_cert(vec![CertificateDer::from(SERVER_CERT.to_vec())], key)
+    .expect("server config");
+    config.alpn_protocols = vec![b"h3".to_vec()];
+
+    let crypto = QuicServerConfig::try_from(config).expect("quic config");
+    let endpoint = quinn::Endpoint::server(
+        quinn::ServerConfig::with_crypto(Arc::new(crypto)),
+        "127.0.0.1:0"
+            .parse()
+            .expect("addr"),
+    )
+    .expect("endpoint");
+    let addr = endpoint
+        .local_addr()
+        .expect("local_addr");
+    let remotes = Arc::new(Mutex::new(Vec::new()));
+    let connections = remotes.clone();
+    smol::spawn(async move {
+        while let Some(incoming) = endpoint
+            .accept()
+            .await
+        {
+            let connections = connections.clone();
+            smol::spawn(async move {
+                let Ok(conn) = incoming.await else {
+                    return;
+                };
+                connections
+                    .lock()
+                    .unwrap()
+                    .push(conn.remote_address());
+                let Ok(mut conn) =
+                    h3::server::Connection::<_, Bytes>::new(h3_quinn::Connection::new(conn)).await
+                else {
+                    return;
+                };
+                while let Ok(Some(resolver)) = conn.accept().await {
+                    let Ok((_, mut stream)) = resolver
+                        .resolve_request()
+                        .await
+                    else {
+                        continue;
+                    };
+                    let _ = stream
+                        .send_response(http::Response::new(()))
+                        .await;
+                    let _ = stream
+                        .send_data(Bytes::from("h3"))
+                        .await;
+                    let _ = stream
+                        .finish()
+                        .await;
+                }
+            })
+            .detach();
+        }
+    })
+    .detach();
+    (addr, remotes)
+}
+
+async fn fetch(client: &Client, addr: SocketAddr) -> (Version, String) {
+    let response = get(format!("https://{addr}/").as_str())
+        .expect("build request")
+        .send_with(client)
+        .await
+        .expect("request failed");
+    let version = response.version();
+    let body = response
+        .text()
+        .await
+        .expect("body");
+    (version, body)
+}
+
+#[test]
+fn connections_share_the_quic_endpoint() {
+    smol::block_on(async {
+        let (addr, remotes) = serve_h3();
+
+        let client = Client::builder()
+            .skip_cert_verification(true)
+            .protocol_policy(ProtocolPolicy::PreferHttp3)
+            .http3_idle_timeout(Duration::from_millis(200))
+            .build();
+
+        let (version, body) = fetch(&client, addr).await;
+        assert_eq!(body, "h3");
+        assert_eq!(version, Version::HTTP_3);
+
+        // Idle for longer than the timeout, the first connection is closed.
+        smol::Timer::after(Duration::from_millis(600)).await;
+
+        let (version, body) = fetch(&client, addr).await;
+        assert_eq!(body, "h3");
+        assert_eq!(version, Version::HTTP_3);
+
+        let remotes = remotes
+            .lock()
+            .unwrap()
+            .clone();
+        assert_eq!(remotes.len(), 2);
+        assert_eq!(remotes[0], remotes[1]);
+    });
+}
+
+#[test]
+fn a_request_asking_for_http3_goes_over_quic() {
+    smol::block_on(async {
+        let (addr, _) = serve_h3();
+
+        // The policy does not try HTTP/3 first, the request asks for it.
+        let client = Client::builder()
+            .skip_cert_verification(true)
+            .build();
+        let response = get(format!("https://{addr}/").as_str())
+            .expect("build request")
+            .version(Version::HTTP_3)
+            .send_with(&client)
+            .await
+            .expect("request failed");
+
+        assert_eq!(response.version(), Version::HTTP_3);
+        assert_eq!(
+            response
+                .text()
+                .await
+                .expect("body"),
+            "h3"
+        );
+    });
+}
+
+#[test]
+fn a_request_asking_for_http3_over_http_is_refused() {
+    smol::block_on(async {
+        let client = Client::default();
+        let error = get("http://127.0.0.1:9/")
+            .expect("build request")
+            .version(Version::HTTP_3)
+            .send_with(&client)
+            .await
+            .expect_err("HTTP/3 without TLS");
+
+        assert!(error
+            .to_string()
+            .contains("HTTP/3 is not available"));
+    });
+}
@@ -6,7 +6,6 @@ use deboa::{
     cert::{Certificate, Identity},
     conn::HttpConnectionPool,
     dns::DnsResolver,
-    errors::{ConnectionError, DeboaError},
     request::{DeboaRequest, IntoRequest},
     response::DeboaResponse,
     Client, HttpClient, InnerClient, TestResult,
@@ -87,28 +86,16 @@ where
     let request = DeboaRequest::get(server.url("/posts/1"))?
         .version(protocol_version)
         .build()?;
-    let http_version = request.version();
     let response = client
         .execute(request)
         .await;
 
     if skip {
-        match http_version {
-            Version::HTTP_11 => {
-                let response = response?;
-                expect(response.status()).to_be(eq(StatusCode::OK));
-            }
-            Version::HTTP_2 => {
-                let response = response?;
-                expect(response.status()).to_be(eq(StatusCode::OK));
-            }
-            Version::HTTP_3 => {
-                let error = DeboaError::Connection(ConnectionError::Udp {
-                    message: "Could not connect to server: aborted by peer: the cryptographic handshake failed: error 120: peer doesn't support any known protocol".to_string(),
-                });
-                expect(response.unwrap_err()).to_be(eq(error));
-            }
-            _ => unreachable!(),
+        let response = response?;
+        expect(response.status()).to_be(eq(StatusCode::OK));
+        // HTTP/3 is spoken over QUIC, never over TCP instead.
+        if protocol_version == Version::HTTP_3 {
+            expect(response.version()).to_be(eq(Version::HTTP_3));
         }
     } else {
         expect(response).to_be(err());
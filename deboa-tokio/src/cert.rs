@@ -5,11 +5,11 @@
 //!
 //! It also provides the `Certificate` struct for working with CA certificates.
 
-#[cfg(feature = "native-tls")]
-use async_native_tls::{Certificate as NativeCertificate, Identity as NativeIdentity};
 #[cfg(feature = "native-tls")]
 use deboa::cert::IdentityNativeExt;
 use deboa::cert::{Certificate as _, CertificateExt, ContentEncoding, IdentityExt};
+#[cfg(feature = "native-tls")]
+use native_tls::{Certificate as NativeCertificate, Identity as NativeIdentity};
 #[cfg(feature = "rust-tls")]
 use rustls::pki_types::{CertificateDer, PrivateKeyDer};
 
@@ -4,7 +4,7 @@
 
 use deboa::{
     dns::DnsResolver,
-    errors::{DeboaError::Dns, DnsError},
+    errors::{DeboaError::Dns, DnsError, ErrorSource},
 };
 use rand::seq::SliceRandom;
 use std::net::IpAddr;
@@ -19,7 +19,11 @@ impl DnsResolver for DefaultDnsResolver {
         let hostname = format!("{}:{}", host, port);
         let addrs = lookup_host(hostname).await;
         if let Err(e) = addrs {
-            return Err(Dns(DnsError::Resolve { host, message: e.to_string() }));
+            return Err(Dns(DnsError::Resolve {
+                host,
+                message: e.to_string(),
+                source: Some(ErrorSource::new(e)),
+            }));
         }
 
         let mut ips: Vec<IpAddr> = addrs
@@ -16,8 +16,16 @@
 //! - Thread-safe connection handling
 //! ```
 use crate::cert::{DeboaCertificate, DeboaIdentity};
+#[cfg(feature = "http1")]
+use crate::client::http::http1;
+#[cfg(all(feature = "http1", feature = "http2"))]
+use crate::client::http::http2;
 #[cfg(any(feature = "http1", feature = "http2"))]
 use crate::rt::stream::TokioStream;
+#[cfg(feature = "http3")]
+use deboa::alt_svc::{self, AltService};
+#[cfg(all(feature = "http1", feature = "http2"))]
+use deboa::h2c;
 #[cfg(feature = "http1")]
 use deboa::request::Http1Request;
 #[cfg(feature = "http2")]
@@ -25,7 +33,8 @@ use deboa::request::Http2Request;
 use deboa::{
     conn::{ConnectionConfig, HttpConnectionDispatcher, ProtoConnection},
     dns::DnsResolver,
-    errors::{ConnectionError, DeboaError, RequestError},
+    errors::{ConnectionError, DeboaError, DnsError, ErrorSource, RequestError},
+    happy_eyeballs, proxy,
     response::DeboaResponse,
     Result,
 };
@@ -33,8 +42,10 @@ use deboa::{
 use deboa_h3::generic::Http3Request;
 use http::{Request, Version};
 use hyper_body_utils::HttpBody;
+#[cfg(feature = "http1")]
+use hyper_util::rt::TokioIo;
 use log::info;
-use std::{borrow::Cow, marker::PhantomData, time::Duration};
+use std::{marker::PhantomData, path::Path, time::Duration};
 #[cfg(any(feature = "http1", feature = "http2"))]
 use tokio::net::TcpStream;
 
@@ -58,6 +69,14 @@ pub(crate) type Http2Connection = BaseHttpConnection<Http2Request, HttpBody, Htt
 #[cfg(feature = "http3")]
 pub(crate) type Http3Connection = BaseHttpConnection<Http3Request, HttpBody, HttpBody>;
 
+/// Transport shared by the connections of a client. With HTTP/3, it keeps
+/// the QUIC endpoints and client configuration, built once and reused.
+#[derive(Clone, Default)]
+pub struct Transport {
+    #[cfg(feature = "http3")]
+    quic: std::sync::Arc<crate::client::tls::rustls::udp::QuicEndpoints>,
+}
+
 /// Enum that represents the connection type.
 ///
 /// # Variants
@@ -100,13 +119,16 @@ impl DeboaConnection {
         match self {
             #[cfg(feature = "http1")]
             DeboaConnection::Http1(ref mut conn) => {
+                conn.sender
+                    .ready()
+                    .await
+                    .map_err(send_error)?;
+
                 let (parts, body) = conn
                     .sender
                     .send_request(request)
                     .await
-                    .map_err(|e| {
-                        DeboaError::Request(RequestError::Send { message: e.to_string() })
-                    })?
+                    .map_err(send_error)?
                     .into_parts();
 
                 Ok(DeboaResponse::new(http::Response::from_parts(
@@ -116,13 +138,16 @@ impl DeboaConnection {
             }
             #[cfg(feature = "http2")]
             DeboaConnection::Http2(ref mut conn) => {
+                conn.sender
+                    .ready()
+                    .await
+                    .map_err(send_error)?;
+
                 let (parts, body) = conn
                     .sender
                     .send_request(request)
                     .await
-                    .map_err(|e| {
-                        DeboaError::Request(RequestError::Send { message: e.to_string() })
-                    })?
+                    .map_err(send_error)?
                     .into_parts();
 
                 Ok(DeboaResponse::new(http::Response::from_parts(
@@ -137,7 +162,12 @@ impl DeboaConnection {
                     .send_request(request)
                     .await
                     .map_err(|e| {
-                        DeboaError::Request(RequestError::Send { message: e.to_string() })
+                        DeboaError::Request(RequestError::Send {
+                            message: e.to_string(),
+                            method: None,
+                            url: None,
+                            source: Some(ErrorSource::new(e)),
+                        })
                     })?;
 
                 Ok(DeboaResponse::new(response))
@@ -150,6 +180,24 @@ impl DeboaConnection {
     }
 }
 
+/// Map a hyper error, telling apart connections closed by the peer.
+#[cfg(any(feature = "http1", feature = "http2"))]
+fn send_error(e: hyper::Error) -> DeboaError {
+    if e.is_closed() || e.is_canceled() || e.is_incomplete_message() {
+        DeboaError::Connection(ConnectionError::Closed {
+            message: e.to_string(),
+            source: Some(ErrorSource::new(e)),
+        })
+    } else {
+        DeboaError::Request(RequestError::Send {
+            message: e.to_string(),
+            method: None,
+            url: None,
+            source: Some(ErrorSource::new(e)),
+        })
+    }
+}
+
 impl HttpConnectionDispatcher for DeboaConnection {
     /// Send a request over the connection.
     ///
@@ -166,12 +214,99 @@ impl HttpConnectionDispatcher for DeboaConnection {
         request: Request<HttpBody>,
         timeout: Duration,
     ) -> Result<DeboaResponse> {
+        let method = request
+            .method()
+            .clone();
+        let url = request
+            .uri()
+            .clone();
         tokio::time::timeout(timeout, self.send(request))
             .await
             .map_err(|_| {
-                DeboaError::Request(RequestError::Send { message: "Request timed out".to_string() })
+                DeboaError::Request(RequestError::Timeout {
+                    message: format!("Request timed out after {:?}", timeout),
+                    method: Some(method),
+                    url: Some(Box::new(url)),
+                    source: None,
+                })
             })?
     }
+
+    #[inline]
+    fn is_multiplexed(&self) -> bool {
+        match self {
+            #[cfg(feature = "http1")]
+            DeboaConnection::Http1(_) => false,
+            #[allow(unreachable_patterns)]
+            _ => true,
+        }
+    }
+
+    #[inline]
+    fn is_closed(&self) -> bool {
+        match self {
+            #[cfg(feature = "http1")]
+            DeboaConnection::Http1(conn) => conn
+                .sender
+                .is_closed(),
+            #[cfg(feature = "http2")]
+            DeboaConnection::Http2(conn) => conn
+                .sender
+                .is_closed(),
+            #[cfg(feature = "http3")]
+            DeboaConnection::Http3(conn) => conn
+                .sender
+                .is_closed(),
+            #[allow(unreachable_patterns)]
+            _ => false,
+        }
+    }
+
+    #[inline]
+    fn is_ready(&self) -> bool {
+        match self {
+            #[cfg(feature = "http1")]
+            DeboaConnection::Http1(conn) => conn
+                .sender
+                .is_ready(),
+            #[cfg(feature = "http2")]
+            DeboaConnection::Http2(conn) => conn
+                .sender
+                .is_ready(),
+            #[allow(unreachable_patterns)]
+            _ => true,
+        }
+    }
+
+    async fn ready(&mut self) -> Result<()> {
+        match self {
+            #[cfg(feature = "http1")]
+            DeboaConnection::Http1(conn) => conn
+                .sender
+                .ready()
+                .await
+                .map_err(send_error),
+            #[cfg(feature = "http2")]
+            DeboaConnection::Http2(conn) => conn
+                .sender
+                .ready()
+                .await
+                .map_err(send_error),
+            #[allow(unreachable_patterns)]
+            _ => Ok(()),
+        }
+    }
+
+    fn try_clone(&self) -> Option<Self> {
+        match self {
+            #[cfg(feature = "http2")]
+            DeboaConnection::Http2(conn) => Some(DeboaConnection::Http2(conn.clone())),
+            #[cfg(feature = "http3")]
+            DeboaConnection::Http3(conn) => Some(DeboaConnection::Http3(conn.clone())),
+            #[allow(unreachable_patterns)]
+            _ => None,
+        }
+    }
 }
 
 /// Struct that represents the connection.
@@ -191,43 +326,40 @@ impl<Sender, ReqBody, ResBody> BaseHttpConnection<Sender, ReqBody, ResBody> {
     }
 }
 
+impl<Sender, ReqBody, ResBody> Clone for BaseHttpConnection<Sender, ReqBody, ResBody>
+where
+    Sender: Clone,
+{
+    fn clone(&self) -> Self {
+        Self::new(self.sender.clone())
+    }
+}
+
 #[cfg(feature = "rust-tls")]
 async fn connect_with_rustls<'a>(
     tcp_stream: TcpStream,
     config: &ConnectionConfig<'a, DeboaIdentity, DeboaCertificate>,
 ) -> Result<(Version, TokioStream)> {
-    use crate::client::tls::rustls::{tcp::connect, TlsConnectionBuilder};
+    use crate::client::tls::rustls::{alpn, tcp::connect, TlsConnectionBuilder};
     let tls_config = TlsConnectionBuilder::default()
+        .alpn(alpn(config.protocol_policy()))
         .certificate(config.certificate())
         .identity(config.identity())
+        .skip_server_verification(config.skip_cert_verification())
         .build_config()?;
 
     let stream = Box::new(connect(tls_config, tcp_stream, config.host()).await?);
 
-    if let Some(alpn) = stream
+    let alpn = stream
         .get_ref()
         .1
-        .alpn_protocol()
-    {
-        let Cow::Borrowed(alpn_code) = String::from_utf8_lossy(alpn) else {
-            return Err(DeboaError::Connection(ConnectionError::Tcp {
-                message: "Invalid ALPN code".to_string(),
-            }));
-        };
-
-        let version = match alpn_code {
-            "http1.1" => Version::HTTP_11,
-            "h2" => Version::HTTP_2,
-            "h3" => Version::HTTP_3,
-            _ => panic!("Unsupported protocol"),
-        };
+        .alpn_protocol();
+    let version = config
+        .protocol_policy()
+        .negotiated(alpn)?;
 
-        info!("ALPN info found, switching connection to {:?}", version);
-        Ok((version, TokioStream::Tls(stream)))
-    } else {
-        info!("No ALPN info available, falling back to HTTP/1.1");
-        Ok((Version::HTTP_11, TokioStream::Tls(stream)))
-    }
+    info!("Connection negotiated {:?}", version);
+    Ok((version, TokioStream::Tls(stream)))
 }
 
 #[cfg(feature = "native-tls")]
@@ -235,44 +367,183 @@ async fn connect_with_nativetls<'a>(
     tcp_stream: TcpStream,
     config: &ConnectionConfig<'a, DeboaIdentity, DeboaCertificate>,
 ) -> Result<(Version, TokioStream)> {
-    use crate::client::tls::native::TlsConnectionBuilder;
+    use crate::client::tls::native::{alpn, TlsConnectionBuilder};
     let stream = TlsConnectionBuilder::new(tcp_stream, config.host())
+        .alpn(&alpn(config.protocol_policy()))
         .certificate(config.certificate())
         .identity(config.identity())
+        .skip_server_verification(config.skip_cert_verification())
         .connect()
         .await?;
 
-    if let Some(alpn) = stream
+    let alpn = stream
         .get_ref()
-        .alpn_protocol()
-    {
-        let Cow::Borrowed(alpn_code) = String::from_utf8_lossy(alpn) else {
-            return Err(DeboaError::Connection(ConnectionError::Tcp {
-                message: "Invalid ALPN code".to_string(),
-            }));
-        };
+        .negotiated_alpn()
+        .map_err(|e| {
+            DeboaError::Connection(ConnectionError::Tls {
+                message: format!("Could not read negotiated protocol: {}", e),
+                source: None,
+            })
+        })?;
+    let version = config
+        .protocol_policy()
+        .negotiated(alpn.as_deref())?;
+
+    info!("Connection negotiated {:?}", version);
+    Ok((version, TokioStream::Tls(stream)))
+}
 
-        let version = match alpn_code {
-            "http1.1" => Version::HTTP_11,
-            "h2" => Version::HTTP_2,
-            "h3" => Version::HTTP_3,
-            _ => panic!("Unsupported protocol"),
-        };
+/// Open a connection to a Unix socket, in HTTP/1.1 or h2c.
+#[cfg(all(unix, any(feature = "http1", feature = "http2")))]
+async fn connect_unix<'a>(
+    path: &Path,
+    config: &ConnectionConfig<'a, DeboaIdentity, DeboaCertificate>,
+) -> Result<DeboaConnection> {
+    use tokio::net::UnixStream;
 
-        info!("ALPN info found, switching connection to {:?}", version);
-        Ok((version, TokioStream::Tls(stream)))
-    } else {
-        info!("No ALPN info available, falling back to HTTP/1.1");
-        Ok((Version::HTTP_11, TokioStream::Tls(stream)))
+    if config.scheme() == "https" || config.scheme() == "wss" {
+        return Err(DeboaError::Connection(ConnectionError::UnsupportedScheme {
+            message: format!("{} over a Unix socket", config.scheme()),
+        }));
+    }
+
+    let stream = UnixStream::connect(path)
+        .await
+        .map_err(|e| {
+            DeboaError::Connection(ConnectionError::Unix {
+                message: format!("Could not connect to {}: {}", path.display(), e),
+                source: Some(ErrorSource::new(e)),
+            })
+        })?;
+    let stream = TokioStream::Unix(stream);
+
+    match config.cleartext_version() {
+        #[cfg(feature = "http1")]
+        Version::HTTP_11 => Ok(DeboaConnection::http1(
+            Http1Connection::connect(stream, config.protocol_settings()).await?,
+        )),
+        #[cfg(feature = "http2")]
+        Version::HTTP_2 => Ok(DeboaConnection::http2(
+            Http2Connection::connect(stream, config.protocol_settings()).await?,
+        )),
+        _ => Err(DeboaError::UnsupportedProtocol),
     }
 }
 
+/// Unix sockets are only available on Unix platforms.
+#[cfg(all(not(unix), any(feature = "http1", feature = "http2")))]
+async fn connect_unix<'a>(
+    path: &Path,
+    _config: &ConnectionConfig<'a, DeboaIdentity, DeboaCertificate>,
+) -> Result<DeboaConnection> {
+    Err(DeboaError::Connection(ConnectionError::Unix {
+        message: format!("Could not connect to {}: not supported on this platform", path.display()),
+        source: None,
+    }))
+}
+
+/// Open an HTTP/1.1 connection, switched to HTTP/2 if an `Upgrade: h2c` is
+/// asked for and accepted by the server.
+#[cfg(all(feature = "http1", feature = "http2"))]
+async fn upgrade_h2c<'a>(
+    mut io: TokioIo<TokioStream>,
+    config: &ConnectionConfig<'a, DeboaIdentity, DeboaCertificate>,
+) -> Result<DeboaConnection> {
+    if h2c::upgrade(&mut io, config).await? {
+        let mut conn = http2::connect_io(h2c::H2cIo::new(io), config.protocol_settings()).await?;
+        h2c::finish(&mut conn.sender, config).await?;
+        return Ok(DeboaConnection::http2(conn));
+    }
+
+    Ok(DeboaConnection::http1(http1::connect_io(io, config.protocol_settings()).await?))
+}
+
+/// Open an HTTP/3 connection, over QUIC, on the endpoint of the client.
+#[cfg(feature = "http3")]
+async fn connect_http3<'a>(
+    transport: &Transport,
+    addr: std::net::SocketAddr,
+    config: &ConnectionConfig<'a, DeboaIdentity, DeboaCertificate>,
+) -> Result<DeboaConnection> {
+    use crate::client::tls::rustls::{udp, TlsConnectionBuilder};
+    use deboa::protocol::ALPN_HTTP3;
+    use std::net::SocketAddr;
+
+    let settings = config
+        .protocol_settings()
+        .http3();
+    let endpoint = transport
+        .quic
+        .endpoint(SocketAddr::new(
+            happy_eyeballs::bind_addr_for(config.client_bind_addr(), &addr.ip()),
+            0,
+        ))?;
+    let client_config = transport
+        .quic
+        .client_config(|| {
+            let tls_config = TlsConnectionBuilder::default()
+                .alpn(vec![ALPN_HTTP3
+                    .as_bytes()
+                    .to_vec()])
+                .certificate(config.certificate())
+                .identity(config.identity())
+                .skip_server_verification(config.skip_cert_verification())
+                .build_config()?;
+            udp::client_config(tls_config, settings)
+        })?;
+
+    let stream =
+        udp::connect(&endpoint, client_config, addr, config.host(), settings.zero_rtt()).await?;
+
+    let conn = Http3Connection::connect(stream, config.protocol_settings()).await?;
+    Ok(DeboaConnection::http3(conn))
+}
+
+/// Open an HTTP/3 connection to the alternative advertised by the origin,
+/// on the addresses of the origin when it names no host.
+#[cfg(feature = "http3")]
+async fn connect_alternative<'a, D>(
+    transport: &Transport,
+    alternative: &AltService,
+    ips: &[std::net::IpAddr],
+    config: &ConnectionConfig<'a, DeboaIdentity, DeboaCertificate>,
+    dns_resolver: &D,
+) -> Result<DeboaConnection>
+where
+    D: DnsResolver,
+{
+    let ips = match alternative.host() {
+        Some(host) => {
+            let ips = dns_resolver
+                .resolve(host.to_string(), alternative.port())
+                .await?;
+            let ips = happy_eyeballs::sort_addresses(ips, config.client_bind_addr());
+            if ips.is_empty() {
+                return Err(DeboaError::Dns(DnsError::Resolve {
+                    host: host.to_string(),
+                    message: format!("No IP addresses found for hostname: {}", host),
+                    source: None,
+                }));
+            }
+            ips
+        }
+        None => ips.to_vec(),
+    };
+
+    happy_eyeballs::race(&ips, happy_eyeballs::DEFAULT_CONNECTION_ATTEMPT_DELAY, |ip| {
+        connect_http3(transport, std::net::SocketAddr::new(ip, alternative.port()), config)
+    })
+    .await
+}
+
 /// Factory for creating connections.
 pub(crate) struct ConnectionFactory {}
 
 impl ConnectionFactory {
     /// Create a new connection.
+    #[cfg_attr(not(feature = "http3"), allow(unused_variables))]
     pub async fn create_connection<'a, D>(
+        transport: &Transport,
         config: &'a ConnectionConfig<'a, DeboaIdentity, DeboaCertificate>,
         dns_resolver: &D,
     ) -> Result<DeboaConnection>
@@ -281,43 +552,109 @@ impl ConnectionFactory {
     {
         //TODO: consider add support to DNS HTTPS record
 
-        let ips = dns_resolver
-            .resolve(
-                config
-                    .host()
-                    .to_string(),
-                config.port(),
-            )
-            .await?;
-        let ips = if config
-            .client_bind_addr()
-            .is_ipv4()
-        {
-            ips.into_iter()
-                .filter(|ip| ip.is_ipv4())
-                .collect::<Vec<_>>()
-        } else {
-            ips.into_iter()
-                .filter(|ip| ip.is_ipv6())
-                .collect::<Vec<_>>()
+        #[cfg(any(feature = "http1", feature = "http2"))]
+        if let Some(path) = config.unix_socket() {
+            return connect_unix(path, config).await;
+        }
+
+        // Through a proxy, the connection is made to the proxy itself.
+        let (host, port) = match config.proxy() {
+            Some(proxy) => (proxy.host(), proxy.port()),
+            None => (config.host(), config.port()),
         };
 
-        let Some(ip) = ips.first() else {
-            return Err(DeboaError::Request(RequestError::Send {
-                message: format!("No IP addresses found for hostname: {}", config.host()),
+        let ips = dns_resolver
+            .resolve(host.to_string(), port)
+            .await?;
+        let ips = happy_eyeballs::sort_addresses(ips, config.client_bind_addr());
+        if ips.is_empty() {
+            return Err(DeboaError::Dns(DnsError::Resolve {
+                host: host.to_string(),
+                message: format!("No IP addresses found for hostname: {}", host),
+                source: None,
             }));
-        };
+        }
+
+        // HTTP/3 runs over QUIC and never through a proxy, so it is tried
+        // before TCP, which stays the fallback. Either attempt gets half of
+        // the connection timeout, and failing marks HTTP/3 broken for the
+        // origin for a while.
+        #[cfg(feature = "http3")]
+        if *config.protocol_version() == Version::HTTP_3 {
+            // Asked for by the request, HTTP/3 has no fallback.
+            return happy_eyeballs::race(
+                &ips,
+                happy_eyeballs::DEFAULT_CONNECTION_ATTEMPT_DELAY,
+                |ip| connect_http3(transport, std::net::SocketAddr::new(ip, config.port()), config),
+            )
+            .await;
+        } else if let Some(alternative) = config.alternative() {
+            let attempt =
+                Box::pin(connect_alternative(transport, alternative, &ips, config, dns_resolver));
+            match alt_svc::attempt(config.connection_timeout(), attempt).await {
+                Ok(conn) => return Ok(conn),
+                Err(e) => {
+                    info!("Alternative service {} failed, falling back to TCP: {}", alternative, e);
+                    config.alternative_failed();
+                }
+            }
+        } else if config
+            .protocol_policy()
+            .prefers_http3()
+            && config.scheme() == "https"
+            && config
+                .proxy()
+                .is_none()
+            && !config.http3_broken()
+        {
+            let attempt = Box::pin(happy_eyeballs::race(
+                &ips,
+                happy_eyeballs::DEFAULT_CONNECTION_ATTEMPT_DELAY,
+                |ip| connect_http3(transport, std::net::SocketAddr::new(ip, config.port()), config),
+            ));
+            match alt_svc::attempt(config.connection_timeout(), attempt).await {
+                Ok(conn) => return Ok(conn),
+                Err(e) => {
+                    info!("HTTP/3 unavailable, falling back to TCP: {}", e);
+                    config.alternative_failed();
+                }
+            }
+        }
 
         #[cfg(any(feature = "http1", feature = "http2"))]
         let conn_pair = {
-            let tcp_stream = TcpStream::connect(format!("{}:{}", ip, config.port()))
-                .await
-                .map_err(|e| {
-                    DeboaError::Connection(ConnectionError::Tcp { message: e.to_string() })
-                })?;
+            let tcp_stream = happy_eyeballs::race(
+                &ips,
+                happy_eyeballs::DEFAULT_CONNECTION_ATTEMPT_DELAY,
+                |ip| async move {
+                    TcpStream::connect(std::net::SocketAddr::new(ip, port))
+                        .await
+                        .map_err(|e| {
+                            DeboaError::Connection(ConnectionError::Tcp {
+                                message: e.to_string(),
+                                source: Some(ErrorSource::new(e)),
+                            })
+                        })
+                },
+            )
+            .await?;
+
+            // Through a tunnel, the TLS handshake happens with the target.
+            let tcp_stream = match config.proxy() {
+                Some(proxy) if proxy.is_tunnel(config.scheme()) => {
+                    use tokio_util::compat::TokioAsyncReadCompatExt;
+
+                    let mut stream = tcp_stream.compat();
+                    proxy::tunnel(&mut stream, proxy, config.host(), config.port(), dns_resolver)
+                        .await?;
+                    stream.into_inner()
+                }
+                _ => tcp_stream,
+            };
+
             let use_tls = config.scheme() == "https" || config.scheme() == "wss";
             if !use_tls {
-                (Version::HTTP_11, TokioStream::Plain(tcp_stream))
+                (config.cleartext_version(), TokioStream::Plain(tcp_stream))
             } else {
                 #[cfg(feature = "rust-tls")]
                 {
@@ -326,7 +663,7 @@ impl ConnectionFactory {
 
                 #[cfg(feature = "native-tls")]
                 {
-                    connect_with_nativels(tcp_stream, config).await?
+                    connect_with_nativetls(tcp_stream, config).await?
                 }
             }
         };
@@ -334,48 +671,23 @@ impl ConnectionFactory {
         let conn = match conn_pair.0 {
             #[cfg(feature = "http1")]
             Version::HTTP_11 => {
-                let conn = Http1Connection::connect(conn_pair.1).await?;
-                DeboaConnection::http1(conn)
+                let io = TokioIo::new(conn_pair.1);
+
+                #[cfg(feature = "http2")]
+                let conn = Box::pin(upgrade_h2c(io, config)).await?;
+                #[cfg(not(feature = "http2"))]
+                let conn = DeboaConnection::http1(
+                    http1::connect_io(io, config.protocol_settings()).await?,
+                );
+
+                conn
             }
             #[cfg(feature = "http2")]
             Version::HTTP_2 => {
-                let conn = Http2Connection::connect(conn_pair.1).await?;
+                let conn =
+                    Http2Connection::connect(conn_pair.1, config.protocol_settings()).await?;
                 DeboaConnection::http2(conn)
             }
-            #[cfg(feature = "http3")]
-            Version::HTTP_3 => {
-                let stream = {
-                    use crate::client::tls::rustls::udp::connect;
-                    #[cfg(feature = "rust-tls")]
-                    use crate::client::tls::rustls::TlsConnectionBuilder;
-                    use quinn::Endpoint;
-                    use std::net::SocketAddr;
-
-                    let mut client_endpoint = Endpoint::client(SocketAddr::new(
-                        *config.client_bind_addr(),
-                        0,
-                    ))
-                    .map_err(|e| {
-                        DeboaError::Connection(ConnectionError::Udp { message: e.to_string() })
-                    })?;
-
-                    let tls_config = TlsConnectionBuilder::default()
-                        .certificate(config.certificate())
-                        .identity(config.identity())
-                        .build_config()?;
-
-                    connect(
-                        tls_config,
-                        &mut client_endpoint,
-                        SocketAddr::new(*ip, config.port()),
-                        config.host(),
-                    )
-                    .await?
-                };
-
-                let conn = Http3Connection::connect(stream).await?;
-                DeboaConnection::http3(conn)
-            }
             _ => {
                 return Err(DeboaError::UnsupportedProtocol);
             }
@@ -1,13 +1,13 @@
 use crate::{
     cert::{DeboaCertificate, DeboaIdentity},
-    client::http::conn::{ConnectionConfig, ConnectionFactory, DeboaConnection},
+    client::http::conn::{ConnectionConfig, ConnectionFactory, DeboaConnection, Transport},
 };
 use deboa::{
     dns::DnsResolver,
     errors::{ConnectionError, DeboaError},
+    pool::{Checkout, ConnectionCache, HostPermit, PoolKey},
     Result,
 };
-use hashbrown::HashMap;
 use std::time::Duration;
 
 /// Struct that represents the HTTP connection pool.
@@ -15,10 +15,10 @@ use std::time::Duration;
 /// # Fields
 ///
 /// * `connections` - The connections.
+/// * `transport` - The transport shared by the connections.
 pub struct HttpConnectionPool {
-    max_idle_connections: u32,
-    keep_alive_duration: Duration,
-    connections: HashMap<String, DeboaConnection>,
+    connections: ConnectionCache<DeboaConnection>,
+    transport: Transport,
 }
 
 impl AsMut<HttpConnectionPool> for HttpConnectionPool {
@@ -29,11 +29,7 @@ impl AsMut<HttpConnectionPool> for HttpConnectionPool {
 
 impl Default for HttpConnectionPool {
     fn default() -> Self {
-        Self {
-            max_idle_connections: 5,
-            keep_alive_duration: Duration::from_mins(5),
-            connections: HashMap::new(),
-        }
+        Self { connections: ConnectionCache::new(), transport: Transport::default() }
     }
 }
 
@@ -45,7 +41,8 @@ impl HttpConnectionPool {
     /// * `max_idle_connections` - The max idle connections.
     ///
     pub fn set_max_idle_connections(&mut self, max_idle_connections: u32) {
-        self.max_idle_connections = max_idle_connections;
+        self.connections
+            .set_max_idle_connections(max_idle_connections as usize);
     }
 
     /// Allow set keep alive duration
@@ -55,7 +52,33 @@ impl HttpConnectionPool {
     /// * `keep_alive_duration` - The keep alive duration.
     ///
     pub fn set_keep_alive_duration(&mut self, keep_alive_duration: Duration) {
-        self.keep_alive_duration = keep_alive_duration;
+        self.connections
+            .set_keep_alive_duration(keep_alive_duration);
+    }
+
+    /// Allow set max connections per host
+    ///
+    /// Only HTTP/1.1 connections count against the limit, further requests
+    /// wait in line for a connection.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_connections_per_host` - The max connections per host.
+    ///
+    pub fn set_max_connections_per_host(&mut self, max_connections_per_host: usize) {
+        self.connections
+            .set_max_connections_per_host(max_connections_per_host);
+    }
+
+    /// Allow set checkout timeout
+    ///
+    /// # Arguments
+    ///
+    /// * `checkout_timeout` - How long a request waits for a connection.
+    ///
+    pub fn set_checkout_timeout(&mut self, checkout_timeout: Duration) {
+        self.connections
+            .set_checkout_timeout(checkout_timeout);
     }
 }
 
@@ -63,10 +86,16 @@ impl deboa::conn::HttpConnectionPool for HttpConnectionPool {
     type Identity = DeboaIdentity;
     type Certificate = DeboaCertificate;
     type ConnectionDispather = DeboaConnection;
-    type ConnectionCache = HashMap<String, DeboaConnection>;
+    type ConnectionCache = ConnectionCache<DeboaConnection>;
+    type Transport = Transport;
+
+    const HTTP3: bool = cfg!(feature = "http3");
 
     fn new(max_idle_connections: u32, keep_alive_duration: Duration) -> Self {
-        Self { max_idle_connections, keep_alive_duration, connections: HashMap::new() }
+        let mut pool = Self::default();
+        pool.set_max_idle_connections(max_idle_connections);
+        pool.set_keep_alive_duration(keep_alive_duration);
+        pool
     }
 
     #[inline]
@@ -74,36 +103,60 @@ impl deboa::conn::HttpConnectionPool for HttpConnectionPool {
         &self.connections
     }
 
+    fn transport(&self) -> Transport {
+        self.transport
+            .clone()
+    }
+
     #[inline]
     fn connection_count(&self) -> u32 {
         self.connections
             .len() as u32
     }
 
-    async fn create_connection<'a, D>(
+    fn checkout(
         &mut self,
+        config: &ConnectionConfig<'_, Self::Identity, Self::Certificate>,
+        permit: Option<&HostPermit>,
+    ) -> Checkout<DeboaConnection> {
+        self.connections
+            .checkout(&PoolKey::from(config), permit)
+    }
+
+    #[inline]
+    fn checkout_timeout(&self) -> Duration {
+        self.connections
+            .checkout_timeout()
+    }
+
+    #[inline]
+    fn waiting_count(&self) -> usize {
+        self.connections
+            .waiting_count()
+    }
+
+    fn checkin(
+        &mut self,
+        config: &ConnectionConfig<'_, Self::Identity, Self::Certificate>,
+        connection: DeboaConnection,
+    ) {
+        self.connections
+            .checkin(&PoolKey::from(config), connection);
+    }
+
+    async fn connect<'a, D>(
+        transport: &Transport,
         config: &ConnectionConfig<'a, Self::Identity, Self::Certificate>,
         dns_resolver: &D,
-    ) -> Result<&mut DeboaConnection>
+    ) -> Result<DeboaConnection>
     where
         D: DnsResolver,
     {
-        let key = format!("{}:{}", config.host(), config.port());
-        if self
-            .connections
-            .contains_key(&key)
-        {
-            log::debug!("Connection already exists for {}, reusing.", key);
-            return Ok(self
-                .connections
-                .get_mut(&key)
-                .unwrap());
-        }
-
+        let key = PoolKey::from(config);
         log::debug!("Creating new connection for {}", key);
         let connection = tokio::time::timeout(
             config.connection_timeout(),
-            ConnectionFactory::create_connection(config, dns_resolver),
+            ConnectionFactory::create_connection(transport, config, dns_resolver),
         )
         .await
         .map_err(|_| {
@@ -113,15 +166,10 @@ impl deboa::conn::HttpConnectionPool for HttpConnectionPool {
                     key,
                     config.connection_timeout()
                 ),
+                source: None,
             })
         })??;
 
-        self.connections
-            .insert(key.clone(), connection);
-
-        Ok(self
-            .connections
-            .get_mut(&key)
-            .unwrap())
+        Ok(connection)
     }
 }
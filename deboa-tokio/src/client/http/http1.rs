@@ -4,12 +4,13 @@ use crate::{
 };
 use deboa::{
     conn::{HttpConnection, ProtoConnection},
-    errors::{ConnectionError, DeboaError},
+    errors::{ConnectionError, DeboaError, ErrorSource},
+    protocol::ProtocolSettings,
     request::Http1Request,
     Result,
 };
 use http::version::Version;
-use hyper::client::conn::http1::handshake;
+use hyper::rt::{Read, Write};
 use hyper_util::rt::TokioIo;
 
 impl HttpConnection for Http1Connection {
@@ -28,23 +29,41 @@ impl ProtoConnection for Http1Connection {
         Version::HTTP_11
     }
 
-    async fn connect(stream: Self::RuntimeStream) -> Result<Self::Connection> {
-        let (sender, conn) = handshake(TokioIo::new(stream))
-            .await
-            .map_err(|e| {
-                DeboaError::Connection(ConnectionError::Handshake { message: e.to_string() })
-            })?;
+    async fn connect(
+        stream: Self::RuntimeStream,
+        settings: &ProtocolSettings,
+    ) -> Result<Self::Connection> {
+        connect_io(TokioIo::new(stream), settings).await
+    }
+}
 
-        tokio::spawn(async move {
-            match conn
-                .with_upgrades()
-                .await
-            {
-                Ok(_) => (),
-                Err(_err) => {}
-            };
-        });
+/// Handshake over any connection, a runtime stream or one that declined an
+/// h2c upgrade.
+pub(crate) async fn connect_io<T>(io: T, settings: &ProtocolSettings) -> Result<Http1Connection>
+where
+    T: Read + Write + Unpin + Send + 'static,
+{
+    let (sender, conn) = settings
+        .http1()
+        .builder()
+        .handshake(io)
+        .await
+        .map_err(|e| {
+            DeboaError::Connection(ConnectionError::Handshake {
+                message: e.to_string(),
+                source: Some(ErrorSource::new(e)),
+            })
+        })?;
 
-        Ok(BaseHttpConnection::new(sender))
-    }
+    tokio::spawn(async move {
+        match conn
+            .with_upgrades()
+            .await
+        {
+            Ok(_) => (),
+            Err(_err) => {}
+        };
+    });
+
+    Ok(BaseHttpConnection::new(sender))
 }
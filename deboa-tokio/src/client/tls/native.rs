@@ -1,23 +1,18 @@
 //! TLS implementation using native-tls
 //!
 use crate::cert::{DeboaCertificate, DeboaIdentity};
-use async_native_tls::{Certificate, Identity, TlsConnector, TlsStream};
 use deboa::{
     errors::{ConnectionError, DeboaError},
+    protocol::ProtocolPolicy,
     Result,
 };
+use native_tls::{Certificate, Identity, TlsConnector};
 use tokio::net::TcpStream;
+use tokio_native_tls::TlsStream;
 
 #[inline]
-pub(crate) fn alpn() -> &'static [&'static str] {
-    &[
-        #[cfg(feature = "http3")]
-        "h3",
-        #[cfg(feature = "http2")]
-        "h2",
-        #[cfg(feature = "http1")]
-        "http/1.1",
-    ]
+pub(crate) fn alpn(policy: ProtocolPolicy) -> Vec<&'static str> {
+    super::alpn_protocols(policy)
 }
 
 /// Builder for TLS connections using native-tls
@@ -27,7 +22,7 @@ pub struct TlsConnectionBuilder<'a> {
     identity: Option<&'a DeboaIdentity>,
     certificate: Option<&'a DeboaCertificate>,
     skip_server_verification: bool,
-    alpn: &'a [&'a str],
+    alpn: Vec<&'a str>,
 }
 
 impl<'a> TlsConnectionBuilder<'a> {
@@ -39,7 +34,7 @@ impl<'a> TlsConnectionBuilder<'a> {
             identity: None,
             certificate: None,
             skip_server_verification: false,
-            alpn: alpn(),
+            alpn: alpn(ProtocolPolicy::default()),
         }
     }
 
@@ -62,61 +57,63 @@ impl<'a> TlsConnectionBuilder<'a> {
     }
 
     /// Sets the ALPN protocols to use
-    pub fn alpn(mut self, alpn: &'a [&str]) -> Self {
-        self.alpn = alpn;
+    pub fn alpn(mut self, alpn: &[&'a str]) -> Self {
+        self.alpn = alpn.to_vec();
         self
     }
 
     /// Establishes the TLS connection
     pub async fn connect(self) -> Result<TlsStream<TcpStream>> {
-        let builder = TlsConnector::new();
+        let mut builder = TlsConnector::builder();
 
-        let builder = if self.skip_server_verification {
+        if self.skip_server_verification {
             builder
                 .danger_accept_invalid_certs(true)
-                .danger_accept_invalid_hostnames(true)
-        } else {
-            builder
-        };
+                .danger_accept_invalid_hostnames(true);
+        }
 
-        let builder = builder.request_alpns(self.alpn);
+        builder.request_alpns(&self.alpn);
 
-        let builder = if let Some(ca) = self.certificate {
+        if let Some(ca) = self.certificate {
             let cert: Certificate = ca
                 .try_into()
                 .map_err(|e| {
                     DeboaError::Connection(ConnectionError::Tls {
                         message: format!("Invalid CA certificate: {}", e),
+                        source: None,
                     })
                 })?;
-            builder.add_root_certificate(cert)
-        } else {
-            builder
-        };
+            builder.add_root_certificate(cert);
+        }
 
-        let builder = if let Some(identity) = self.identity {
+        if let Some(identity) = self.identity {
             let ident: Identity = identity
                 .try_into()
                 .map_err(|e| {
                     DeboaError::Connection(ConnectionError::Tls {
                         message: format!("Invalid client identity: {}", e),
+                        source: None,
                     })
                 })?;
-            builder.identity(ident)
-        } else {
-            builder
-        };
+            builder.identity(ident);
+        }
+
+        let connector = builder
+            .build()
+            .map_err(|e| {
+                DeboaError::Connection(ConnectionError::Tls {
+                    message: format!("Could not create TLS connector: {}", e),
+                    source: None,
+                })
+            })?;
 
-        let stream = builder
-            .connect(
-                self.host
-                    .to_string(),
-                self.tcp_stream,
-            )
+        let stream = tokio_native_tls::TlsConnector::from(connector)
+            .connect(self.host, self.tcp_stream)
             .await
             .map_err(|e| {
                 DeboaError::Connection(ConnectionError::Tls {
                     message: format!("Could not connect to server: {}", e),
+                    source: None,
                 })
             });
 
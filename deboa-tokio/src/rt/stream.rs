@@ -1,13 +1,15 @@
-#[cfg(feature = "native-tls")]
-use async_native_tls::TlsStream;
 use std::{
     pin::Pin,
     task::{Context, Poll},
 };
+#[cfg(unix)]
+use tokio::net::UnixStream;
 use tokio::{
     io::{self, AsyncRead, AsyncWrite},
     net::TcpStream,
 };
+#[cfg(feature = "native-tls")]
+use tokio_native_tls::TlsStream;
 #[cfg(feature = "rust-tls")]
 use tokio_rustls::client::TlsStream;
 
@@ -16,6 +18,10 @@ pub enum TokioStream {
     /// A plain TCP connection.
     Plain(TcpStream),
 
+    /// A Unix socket connection.
+    #[cfg(unix)]
+    Unix(UnixStream),
+
     /// A TCP connection secured by native TLS.
     #[cfg(feature = "native-tls")]
     Tls(TlsStream<TcpStream>),
@@ -33,6 +39,8 @@ impl AsyncRead for TokioStream {
     ) -> std::task::Poll<std::result::Result<(), std::io::Error>> {
         match &mut *self {
             TokioStream::Plain(stream) => Pin::new(stream).poll_read(cx, buf),
+            #[cfg(unix)]
+            TokioStream::Unix(stream) => Pin::new(stream).poll_read(cx, buf),
             #[cfg(any(feature = "native-tls", feature = "rust-tls"))]
             TokioStream::Tls(stream) => Pin::new(stream).poll_read(cx, buf),
         }
@@ -47,6 +55,8 @@ impl AsyncWrite for TokioStream {
     ) -> Poll<io::Result<usize>> {
         match &mut *self {
             TokioStream::Plain(stream) => Pin::new(stream).poll_write(cx, buf),
+            #[cfg(unix)]
+            TokioStream::Unix(stream) => Pin::new(stream).poll_write(cx, buf),
             #[cfg(any(feature = "native-tls", feature = "rust-tls"))]
             TokioStream::Tls(stream) => Pin::new(stream).poll_write(cx, buf),
         }
@@ -58,6 +68,8 @@ impl AsyncWrite for TokioStream {
     ) -> Poll<Result<(), std::io::Error>> {
         match &mut *self {
             TokioStream::Plain(stream) => Pin::new(stream).poll_shutdown(cx),
+            #[cfg(unix)]
+            TokioStream::Unix(stream) => Pin::new(stream).poll_shutdown(cx),
             #[cfg(any(feature = "native-tls", feature = "rust-tls"))]
             TokioStream::Tls(stream) => Pin::new(stream).poll_shutdown(cx),
         }
@@ -66,6 +78,8 @@ impl AsyncWrite for TokioStream {
     fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
         match &mut *self {
             TokioStream::Plain(stream) => Pin::new(stream).poll_flush(cx),
+            #[cfg(unix)]
+            TokioStream::Unix(stream) => Pin::new(stream).poll_flush(cx),
             #[cfg(any(feature = "native-tls", feature = "rust-tls"))]
             TokioStream::Tls(stream) => Pin::new(stream).poll_flush(cx),
         }
@@ -0,0 +1,22 @@
+//! End-to-end: a request asking for HTTP/3 is refused by a runtime built
+//! without it, instead of being sent over TCP.
+#![cfg(not(feature = "http3"))]
+
+use deboa::request::get;
+use deboa_tokio::Client;
+use http::Version;
+
+#[tokio::test]
+async fn a_request_asking_for_http3_is_refused_without_the_feature() {
+    let client = Client::default();
+    let error = get("https://127.0.0.1:9/")
+        .expect("build request")
+        .version(Version::HTTP_3)
+        .send_with(&client)
+        .await
+        .expect_err("HTTP/3 without the feature");
+
+    assert!(error
+        .to_string()
+        .contains("built without the http3 feature"));
+}
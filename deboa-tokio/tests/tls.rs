@@ -0,0 +1,177 @@
+//! End-to-end over native TLS against a server whose certificate the client
+//! does not trust, to check `skip_cert_verification` reaches the native-tls
+//! connector, and that the negotiated protocol drives the version.
+#![cfg(feature = "native-tls")]
+
+use std::convert::Infallible;
+use std::net::SocketAddr;
+
+use deboa::{protocol::ProtocolPolicy, request::get};
+use deboa_tokio::Client;
+use http_body_util::Full;
+use hyper::body::Bytes;
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use tokio_native_tls::{native_tls, TlsAcceptor};
+
+const SERVER_CERT: &[u8] = include_bytes!("../../certs/server.crt");
+const SERVER_KEY: &[u8] = include_bytes!("../../certs/server.key");
+
+fn acceptor(alpn: &[&str]) -> TlsAcceptor {
+    let identity = native_tls::Identity::from_pkcs8(SERVER_CERT, SERVER_KEY).expect("identity");
+    let acceptor = native_tls::TlsAcceptor::builder(identity)
+        .accept_alpn(alpn)
+        .build()
+        .expect("server config");
+    TlsAcceptor::from(acceptor)
+}
+
+async fn serve_tls() -> SocketAddr {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("bind");
+    let addr = listener
+        .local_addr()
+        .expect("local_addr");
+    let acceptor = acceptor(&[]);
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener
+            .accept()
+            .await
+        {
+            let acceptor = acceptor.clone();
+            tokio::spawn(async move {
+                let Ok(stream) = acceptor
+                    .accept(stream)
+                    .await
+                else {
+                    return;
+                };
+                let service = hyper::service::service_fn(|_req| async {
+                    Ok::<_, Infallible>(hyper::Response::new(Full::new(Bytes::from("secure"))))
+                });
+                let _ = hyper::server::conn::http1::Builder::new()
+                    .serve_connection(TokioIo::new(stream), service)
+                    .await;
+            });
+        }
+    });
+    addr
+}
+
+/// Offers HTTP/2 and HTTP/1.1 over ALPN, answering with the version of the
+/// request.
+async fn serve_alpn() -> SocketAddr {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("bind");
+    let addr = listener
+        .local_addr()
+        .expect("local_addr");
+    let acceptor = acceptor(&["h2", "http/1.1"]);
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener
+            .accept()
+            .await
+        {
+            let acceptor = acceptor.clone();
+            tokio::spawn(async move {
+                let Ok(stream) = acceptor
+                    .accept(stream)
+                    .await
+                else {
+                    return;
+                };
+                let http2 = stream
+                    .get_ref()
+                    .negotiated_alpn()
+                    .ok()
+                    .flatten()
+                    .as_deref()
+                    == Some(b"h2");
+                let service = hyper::service::service_fn(|req: hyper::Request<_>| async move {
+                    let body = format!("{:?}", req.version());
+                    Ok::<_, Infallible>(hyper::Response::new(Full::new(Bytes::from(body))))
+                });
+                let io = TokioIo::new(stream);
+                let _ = if http2 {
+                    hyper::server::conn::http2::Builder::new(TokioExecutor::new())
+                        .serve_connection(io, service)
+                        .await
+                } else {
+                    hyper::server::conn::http1::Builder::new()
+                        .serve_connection(io, service)
+                        .await
+                };
+            });
+        }
+    });
+    addr
+}
+
+#[tokio::test]
+async fn skipping_verification_accepts_an_untrusted_certificate() {
+    let addr = serve_tls().await;
+
+    let client = Client::builder()
+        .skip_cert_verification(true)
+        .build();
+    let response = get(format!("https://localhost:{}/", addr.port()).as_str())
+        .expect("build request")
+        .version(http::Version::HTTP_11)
+        .send_with(&client)
+        .await
+        .expect("request failed");
+
+    assert_eq!(response.status(), http::StatusCode::OK);
+    assert_eq!(
+        response
+            .text()
+            .await
+            .expect("body"),
+        "secure"
+    );
+}
+
+#[tokio::test]
+async fn an_untrusted_certificate_is_rejected_by_default() {
+    let addr = serve_tls().await;
+
+    let client = Client::default();
+    let response = get(format!("https://localhost:{}/", addr.port()).as_str())
+        .expect("build request")
+        .version(http::Version::HTTP_11)
+        .send_with(&client)
+        .await;
+
+    assert!(response.is_err());
+}
+
+#[tokio::test]
+async fn alpn_follows_the_protocol_policy() {
+    let addr = serve_alpn().await;
+
+    for (policy, version) in [
+        (ProtocolPolicy::PreferHttp2, http::Version::HTTP_2),
+        (ProtocolPolicy::Http1Only, http::Version::HTTP_11),
+        (ProtocolPolicy::Http2Only, http::Version::HTTP_2),
+    ] {
+        let client = Client::builder()
+            .skip_cert_verification(true)
+            .protocol_policy(policy)
+            .build();
+        let response = get(format!("https://localhost:{}/", addr.port()).as_str())
+            .expect("build request")
+            .send_with(&client)
+            .await
+            .expect("request failed");
+
+        assert_eq!(response.version(), version, "{policy:?}");
+        assert_eq!(
+            response
+                .text()
+                .await
+                .expect("body"),
+            format!("{version:?}")
+        );
+    }
+}
@@ -3,7 +3,7 @@ use crate::{
     WebSocketWrite,
 };
 use deboa::{
-    errors::{ConnectionError, DeboaError},
+    errors::{ConnectionError, DeboaError, ErrorSource},
     response::DeboaResponse,
 };
 use hyper::{
@@ -25,7 +25,10 @@ impl IntoWebSocket for DeboaResponse {
         let upgraded = on(self.into_inner())
             .await
             .map_err(|e| {
-                DeboaError::Connection(ConnectionError::Upgrade { message: e.to_string() })
+                DeboaError::Connection(ConnectionError::Upgrade {
+                    message: e.to_string(),
+                    source: Some(ErrorSource::new(e)),
+                })
             })?;
         Ok(WebSocket::new(FuturesIo::new(upgraded)))
     }
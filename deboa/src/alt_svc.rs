@@ -0,0 +1,427 @@
+//! Alt-Svc module
+//!
+//! This module provides the alternative services of RFC 7838, advertised by
+//! servers with the `Alt-Svc` header, and the cache the client keeps of them
+//! to send later requests to an origin over HTTP/3.
+//!
+//! # Features
+//!
+//! - `Alt-Svc` header parsing, with quoted authorities and the `ma` parameter
+//! - Alternatives kept per origin until their max-age elapses, or a `clear`
+//! - Alternatives marked broken for a while after a failed connection, as
+//!   are the origins HTTP/3 is tried on first and failed
+//!
+//! # Examples
+//!
+//! ```ignore
+//! use deboa_tokio::Client;
+//!
+//! // Enabled by default, with a runtime built with the `http3` feature.
+//! let client = Client::builder()
+//!     .alt_svc(false)
+//!     .build();
+//! ```
+
+use crate::{
+    errors::{ConnectionError, DeboaError},
+    protocol::ALPN_HTTP3,
+    time, Result,
+};
+use http::{header, HeaderMap};
+use std::{
+    collections::HashMap,
+    fmt::{self, Display},
+    future::Future,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// Lifetime of an alternative advertised without `ma`, 24 hours.
+pub const DEFAULT_MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// How long an alternative is not tried after a failed connection.
+pub const DEFAULT_BROKEN_DURATION: Duration = Duration::from_secs(5 * 60);
+
+/// An alternative service of an origin.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AltService {
+    protocol: String,
+    host: Option<String>,
+    port: u16,
+    max_age: Duration,
+}
+
+impl AltService {
+    /// Get the ALPN protocol of the alternative.
+    pub fn protocol(&self) -> &str {
+        &self.protocol
+    }
+
+    /// Get the host of the alternative, `None` for the host of the origin.
+    pub fn host(&self) -> Option<&str> {
+        self.host.as_deref()
+    }
+
+    /// Get the port of the alternative.
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// Get how long the alternative may be used.
+    pub fn max_age(&self) -> Duration {
+        self.max_age
+    }
+}
+
+impl Display for AltService {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}=\"{}:{}\"",
+            self.protocol,
+            self.host
+                .as_deref()
+                .unwrap_or_default(),
+            self.port
+        )
+    }
+}
+
+/// Value of an `Alt-Svc` header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AltSvc {
+    /// Every alternative of the origin is withdrawn.
+    Clear,
+    /// The alternatives, in order of preference.
+    Services(Vec<AltService>),
+}
+
+/// Split a header value on a separator, outside of quoted strings.
+fn split_unquoted(value: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut quoted = false;
+    let mut escaped = false;
+    let mut start = 0;
+    for (index, c) in value.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            c if c == separator && !quoted => {
+                parts.push(&value[start..index]);
+                start = index + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    parts.push(&value[start..]);
+    parts
+}
+
+/// Remove the quotes of a quoted string, and its escapes.
+fn unquote(value: &str) -> String {
+    let value = value.trim();
+    match value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+    {
+        Some(inner) => {
+            let mut unquoted = String::with_capacity(inner.len());
+            let mut chars = inner.chars();
+            while let Some(c) = chars.next() {
+                match c {
+                    '\\' => unquoted.extend(chars.next()),
+                    c => unquoted.push(c),
+                }
+            }
+            unquoted
+        }
+        None => value.to_string(),
+    }
+}
+
+/// Parse an alternative, `protocol="host:port"` followed by its parameters.
+fn parse_service(value: &str) -> Option<AltService> {
+    let mut parts = split_unquoted(value, ';').into_iter();
+    let (protocol, authority) = parts
+        .next()?
+        .split_once('=')?;
+    let protocol = protocol.trim();
+    if protocol.is_empty() {
+        return None;
+    }
+
+    let authority = unquote(authority);
+    let (host, port) = authority.rsplit_once(':')?;
+    let port = port.parse().ok()?;
+    let host = host
+        .trim_start_matches('[')
+        .trim_end_matches(']');
+    let host = (!host.is_empty()).then(|| host.to_string());
+
+    let mut max_age = DEFAULT_MAX_AGE;
+    for param in parts {
+        if let Some((name, value)) = param.split_once('=') {
+            if name
+                .trim()
+                .eq_ignore_ascii_case("ma")
+            {
+                if let Ok(seconds) = unquote(value).parse() {
+                    max_age = Duration::from_secs(seconds);
+                }
+            }
+        }
+    }
+
+    Some(AltService { protocol: protocol.to_string(), host, port, max_age })
+}
+
+/// Parse the value of an `Alt-Svc` header.
+/// Alternatives that cannot be parsed are skipped.
+///
+/// # Arguments
+///
+/// * `value` - The header value.
+///
+/// # Returns
+///
+/// * `AltSvc` - `Clear` or the alternatives, in order of preference.
+///
+pub fn parse(value: &str) -> AltSvc {
+    if value
+        .trim()
+        .eq_ignore_ascii_case("clear")
+    {
+        return AltSvc::Clear;
+    }
+
+    AltSvc::Services(
+        split_unquoted(value, ',')
+            .into_iter()
+            .filter_map(parse_service)
+            .collect(),
+    )
+}
+
+/// Run a connection attempt to an alternative, giving up after half the
+/// connection timeout so the fallback to TCP still has time.
+///
+/// # Arguments
+///
+/// * `connection_timeout` - The connection timeout.
+/// * `connect` - The connection attempt.
+///
+/// # Returns
+///
+/// * `Result<T>` - The connection, or a timeout error.
+///
+pub async fn attempt<F, T>(connection_timeout: Duration, connect: F) -> Result<T>
+where
+    F: Future<Output = Result<T>>,
+{
+    let limit = connection_timeout / 2;
+    time::timeout(limit, connect)
+        .await
+        .unwrap_or_else(|| {
+            Err(DeboaError::Connection(ConnectionError::Timeout {
+                message: format!("Alternative service timed out after {:?}", limit),
+                source: None,
+            }))
+        })
+}
+
+#[derive(Debug)]
+struct Entry {
+    // `None` for an origin only known for HTTP/3 failing on it.
+    service: Option<AltService>,
+    expires: Instant,
+    broken_until: Option<Instant>,
+}
+
+/// Cache of the HTTP/3 alternatives of the origins a client talked to, and
+/// of the origins HTTP/3 failed on recently.
+#[derive(Debug)]
+pub struct AltSvcCache {
+    entries: Mutex<HashMap<(String, u16), Entry>>,
+    broken_duration: Duration,
+}
+
+impl Default for AltSvcCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_BROKEN_DURATION)
+    }
+}
+
+impl AltSvcCache {
+    /// Allow create a new alternative services cache.
+    ///
+    /// # Arguments
+    ///
+    /// * `broken_duration` - How long an alternative is not tried after a failed connection.
+    ///
+    /// # Returns
+    ///
+    /// * `AltSvcCache` - The new cache.
+    ///
+    pub fn new(broken_duration: Duration) -> Self {
+        Self { entries: Mutex::new(HashMap::new()), broken_duration }
+    }
+
+    fn key(host: &str, port: u16) -> (String, u16) {
+        (host.to_ascii_lowercase(), port)
+    }
+
+    /// Record the alternatives advertised in the headers of a response of an
+    /// origin. Responses without `Alt-Svc` leave the cache as it is.
+    ///
+    /// # Arguments
+    ///
+    /// * `host` - The host of the origin.
+    /// * `port` - The port of the origin.
+    /// * `headers` - The headers of the response.
+    ///
+    pub fn update(&self, host: &str, port: u16, headers: &HeaderMap) {
+        let mut values = headers
+            .get_all(header::ALT_SVC)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .peekable();
+        if values
+            .peek()
+            .is_none()
+        {
+            return;
+        }
+
+        let mut service = None;
+        for value in values {
+            match parse(value) {
+                AltSvc::Clear => {
+                    service = None;
+                    break;
+                }
+                AltSvc::Services(services) => {
+                    service = service.or_else(|| {
+                        services
+                            .into_iter()
+                            .find(|service| service.protocol == ALPN_HTTP3)
+                    });
+                }
+            }
+        }
+
+        let key = Self::key(host, port);
+        let mut entries = self
+            .entries
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        match service {
+            Some(service) => {
+                // A broken alternative stays broken when advertised again,
+                // as does an origin HTTP/3 failed on.
+                let broken_until = entries
+                    .get(&key)
+                    .filter(|entry| {
+                        entry
+                            .service
+                            .as_ref()
+                            .is_none_or(|broken| *broken == service)
+                    })
+                    .and_then(|entry| entry.broken_until);
+                let expires = Instant::now() + service.max_age;
+                entries.insert(key, Entry { service: Some(service), expires, broken_until });
+            }
+            None => {
+                // An origin HTTP/3 failed on stays broken without alternative.
+                let broken_until = entries
+                    .get(&key)
+                    .and_then(|entry| entry.broken_until)
+                    .filter(|until| *until > Instant::now());
+                match broken_until {
+                    Some(until) => {
+                        entries.insert(
+                            key,
+                            Entry { service: None, expires: until, broken_until: Some(until) },
+                        );
+                    }
+                    None => {
+                        entries.remove(&key);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Get the HTTP/3 alternative of an origin, unless it expired or is
+    /// marked broken.
+    ///
+    /// # Arguments
+    ///
+    /// * `host` - The host of the origin.
+    /// * `port` - The port of the origin.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<AltService>` - The alternative, if any.
+    ///
+    pub fn alternative(&self, host: &str, port: u16) -> Option<AltService> {
+        let key = Self::key(host, port);
+        let mut entries = self
+            .entries
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        let entry = entries.get(&key)?;
+        if entry.expires <= now {
+            entries.remove(&key);
+            return None;
+        }
+        if entry
+            .broken_until
+            .is_some_and(|until| until > now)
+        {
+            return None;
+        }
+        entry
+            .service
+            .clone()
+    }
+
+    /// Mark HTTP/3 broken for an origin, neither its alternative nor the
+    /// origin itself is tried over QUIC again for a while.
+    ///
+    /// # Arguments
+    ///
+    /// * `host` - The host of the origin.
+    /// * `port` - The port of the origin.
+    ///
+    pub fn mark_broken(&self, host: &str, port: u16) {
+        let until = Instant::now() + self.broken_duration;
+        self.entries
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(Self::key(host, port))
+            .or_insert_with(|| Entry { service: None, expires: until, broken_until: None })
+            .broken_until = Some(until);
+    }
+
+    /// Check if the alternative of an origin is marked broken.
+    ///
+    /// # Arguments
+    ///
+    /// * `host` - The host of the origin.
+    /// * `port` - The port of the origin.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - `true` if a failed connection to it was recent.
+    ///
+    pub fn is_broken(&self, host: &str, port: u16) -> bool {
+        self.entries
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&Self::key(host, port))
+            .and_then(|entry| entry.broken_until)
+            .is_some_and(|until| until > Instant::now())
+    }
+}
@@ -1,94 +1,990 @@
 //! Caching functionality for the Deboa HTTP client.
 //!
-//! This module provides the `DeboaCache` trait for implementing custom cache
-//! backends. Implement this trait to provide your own caching mechanism for
-//! HTTP responses.
+//! This module provides the [`Cache`] chain, an HTTP cache following
+//! RFC 9111, and the [`DeboaCache`] trait for implementing custom storage
+//! backends. [`MemoryCache`] is an in-memory, least recently used backend.
 //!
 //! # Features
 //!
-//! - Simple key-value interface
-//! - Thread-safe operations
-//! - Optional TTL support (can be implemented by the concrete type)
+//! - `GET` and `HEAD` responses are stored by method and URI, the request
+//!   headers nominated by `Vary` must match for a stored response to be used
+//! - Freshness from `Cache-Control: max-age`, `Expires` or, for responses with
+//!   a `Last-Modified` date, a heuristic of 10% of their age
+//! - `no-store`, `no-cache`, `private` and `must-revalidate` are honoured
+//! - Stale responses are revalidated with `If-None-Match` and
+//!   `If-Modified-Since`, a `304 Not Modified` refreshes the stored response
+//! - `stale-while-revalidate`, see [`CacheHook`] for how it is applied
+//! - Successful `POST`, `PUT`, `PATCH` and `DELETE` requests invalidate the
+//!   stored responses of their URI
 //!
 //! # Examples
 //!
+//! ## Caching responses in memory
+//!
+//! ```ignore
+//! use deboa::cache::{Cache, MemoryCache};
+//! use deboa_tokio::Client;
+//!
+//! let client = Client::default().chain(Cache::new(MemoryCache::new(100)));
+//! ```
+//!
 //! ## Implementing a custom cache
 //!
 //! ```ignore
-//! use deboa::cache::DeboaCache;
+//! use deboa::cache::{CachedResponse, DeboaCache};
 //! use std::collections::HashMap;
-//! use std::sync::{Arc, RwLock};
+//! use std::sync::RwLock;
 //!
 //! #[derive(Default)]
-//! struct MemoryCache {
-//!     store: Arc<RwLock<HashMap<String, String>>>,
+//! struct MapCache {
+//!     store: RwLock<HashMap<String, CachedResponse>>,
 //! }
 //!
-//! impl DeboaCache for MemoryCache {
-//!     fn get(&self, key: &str) -> Option<String> {
+//! impl DeboaCache for MapCache {
+//!     async fn get(&self, key: &str) -> Option<CachedResponse> {
 //!         self.store.read().unwrap().get(key).cloned()
 //!     }
 //!
-//!     fn set(&self, key: &str, value: &str) {
-//!         self.store.write().unwrap().insert(key.to_string(), value.to_string());
+//!     async fn put(&self, key: &str, response: CachedResponse) {
+//!         self.store.write().unwrap().insert(key.to_string(), response);
 //!     }
 //!
-//!     fn delete(&self, key: &str) {
+//!     async fn remove(&self, key: &str) {
 //!         self.store.write().unwrap().remove(key);
 //!     }
 //! }
 //! ```
-//!
-//! ## Using the cache with Deboa
-//!
-//! ```ignore
-//! # use deboa::{Deboa, cache::DeboaCache};
-//! # struct MyCache;
-//! # impl DeboaCache for MyCache {
-//! #   fn get(&self, _: &str) -> Option<String> { None }
-//! #   fn set(&self, _: &str, _: &str) {}
-//! #   fn delete(&self, _: &str) {}
-//! # }
-//! #
-//! let cache = MyCache; // Your cache implementation
-//! // let client = Deboa::builder().cache(Box::new(cache)).build();
-//! ```
+
+use crate::{
+    errors::DeboaError,
+    request::DeboaRequest,
+    response::{read_limited, DeboaResponse},
+    Result,
+};
+use bytes::Bytes;
+use hashbrown::HashSet;
+use http::{header, HeaderMap, HeaderName, HeaderValue, Method, StatusCode, Uri};
+use indexmap::IndexMap;
+use std::{
+    future::Future,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime},
+};
+use tackle::{Chain, Hook};
+
+/// Default number of responses kept by a [`MemoryCache`].
+pub const DEFAULT_CACHE_CAPACITY: usize = 100;
+
+/// Default largest response body stored by the [`Cache`] chain.
+pub const DEFAULT_MAX_BODY_SIZE: u64 = 1024 * 1024;
+
+/// Status codes that can be stored without explicit freshness information,
+/// as listed in RFC 9110, section 15.1.
+const HEURISTICALLY_CACHEABLE: [u16; 10] = [200, 203, 204, 300, 301, 308, 404, 405, 410, 501];
 
 /// A trait defining the interface for cache implementations.
 ///
-/// Implement this trait to provide custom caching behavior for HTTP responses.
-/// The cache is responsible for storing and retrieving responses based on their
-/// cache keys.
+/// Implement this trait to provide custom storage for cached responses.
+/// Keys are built by the [`Cache`] chain from the request method and URI,
+/// the backend only stores and evicts entries.
 ///
 /// # Thread Safety
 ///
-/// Implementations must be thread-safe as they may be accessed concurrently
-/// from multiple threads.
+/// Implementations may be shared by several clients and should be safe to
+/// access concurrently.
 pub trait DeboaCache {
-    /// Get a value from the cache.
+    /// Get a response from the cache.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The key to get the response for.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<CachedResponse>` - The response if it exists, None otherwise.
+    ///
+    fn get(&self, key: &str) -> impl Future<Output = Option<CachedResponse>>;
+
+    /// Store a response in the cache, replacing any previous one.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The key to store the response for.
+    /// * `response` - The response.
+    ///
+    fn put(&self, key: &str, response: CachedResponse) -> impl Future<Output = ()>;
+
+    /// Remove a response from the cache.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The key to remove the response for.
+    ///
+    fn remove(&self, key: &str) -> impl Future<Output = ()>;
+}
+
+impl<T: DeboaCache> DeboaCache for Arc<T> {
+    fn get(&self, key: &str) -> impl Future<Output = Option<CachedResponse>> {
+        self.as_ref()
+            .get(key)
+    }
+
+    fn put(&self, key: &str, response: CachedResponse) -> impl Future<Output = ()> {
+        self.as_ref()
+            .put(key, response)
+    }
+
+    fn remove(&self, key: &str) -> impl Future<Output = ()> {
+        self.as_ref()
+            .remove(key)
+    }
+}
+
+/// A response stored in a cache.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CachedResponse {
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Bytes,
+    vary: Vec<(HeaderName, Option<HeaderValue>)>,
+    request_time: SystemTime,
+    response_time: SystemTime,
+}
+
+impl CachedResponse {
+    /// Create a cached response.
+    ///
+    /// # Arguments
+    ///
+    /// * `status` - The response status.
+    /// * `headers` - The response headers.
+    /// * `body` - The response body.
+    /// * `request_time` - When the request was sent.
+    /// * `response_time` - When the response was received.
+    ///
+    /// # Returns
+    ///
+    /// * `CachedResponse` - The cached response.
+    ///
+    pub fn new(
+        status: StatusCode,
+        headers: HeaderMap,
+        body: Bytes,
+        request_time: SystemTime,
+        response_time: SystemTime,
+    ) -> Self {
+        Self { status, headers, body, vary: Vec::new(), request_time, response_time }
+    }
+
+    /// Allow set the request headers nominated by the `Vary` header.
+    ///
+    /// # Arguments
+    ///
+    /// * `request_headers` - The headers of the request the response is for.
+    ///
+    /// # Returns
+    ///
+    /// * `CachedResponse` - The cached response.
+    ///
+    pub fn vary(mut self, request_headers: &HeaderMap) -> Self {
+        self.vary = vary_names(&self.headers)
+            .into_iter()
+            .map(|name| {
+                let value = request_headers
+                    .get(&name)
+                    .cloned();
+                (name, value)
+            })
+            .collect();
+        self
+    }
+
+    /// Get the response status.
+    #[inline]
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    /// Get the response headers.
+    #[inline]
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    /// Get the response body.
+    #[inline]
+    pub fn body(&self) -> &Bytes {
+        &self.body
+    }
+
+    /// Get the request headers the response varies on, with their values.
+    #[inline]
+    pub fn varying_headers(&self) -> &[(HeaderName, Option<HeaderValue>)] {
+        &self.vary
+    }
+
+    /// Get when the request was sent.
+    #[inline]
+    pub fn request_time(&self) -> SystemTime {
+        self.request_time
+    }
+
+    /// Get when the response was received.
+    #[inline]
+    pub fn response_time(&self) -> SystemTime {
+        self.response_time
+    }
+
+    /// Check if the response can be used for a request, comparing the
+    /// headers nominated by `Vary`.
+    ///
+    /// # Arguments
+    ///
+    /// * `request_headers` - The request headers.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - `true` if the headers match.
+    ///
+    pub fn matches(&self, request_headers: &HeaderMap) -> bool {
+        self.vary
+            .iter()
+            .all(|(name, value)| request_headers.get(name) == value.as_ref())
+    }
+
+    /// Age of the response, as described in RFC 9111, section 4.2.3.
+    ///
+    /// # Arguments
+    ///
+    /// * `now` - The current time.
+    ///
+    /// # Returns
+    ///
+    /// * `Duration` - The age of the response.
+    ///
+    pub fn age(&self, now: SystemTime) -> Duration {
+        let apparent_age = http_date(&self.headers, header::DATE)
+            .and_then(|date| {
+                self.response_time
+                    .duration_since(date)
+                    .ok()
+            })
+            .unwrap_or_default();
+        let response_delay = self
+            .response_time
+            .duration_since(self.request_time)
+            .unwrap_or_default();
+        let age_value = self
+            .headers
+            .get(header::AGE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| {
+                value
+                    .trim()
+                    .parse::<u64>()
+                    .ok()
+            })
+            .map(Duration::from_secs)
+            .unwrap_or_default();
+        let corrected_initial_age = apparent_age.max(age_value + response_delay);
+        let resident_time = now
+            .duration_since(self.response_time)
+            .unwrap_or_default();
+        corrected_initial_age + resident_time
+    }
+
+    /// Freshness lifetime of the response, as described in RFC 9111,
+    /// section 4.2.1.
+    ///
+    /// # Arguments
+    ///
+    /// * `shared` - Whether the cache is shared, `s-maxage` only applies to
+    ///   shared caches.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<Duration>` - The lifetime, `None` if it can't be determined.
+    ///
+    pub fn freshness_lifetime(&self, shared: bool) -> Option<Duration> {
+        let cache_control = CacheControl::from_headers(&self.headers);
+        if shared {
+            if let Some(s_maxage) = cache_control.s_maxage {
+                return Some(s_maxage);
+            }
+        }
+
+        if let Some(max_age) = cache_control.max_age {
+            return Some(max_age);
+        }
+
+        let date = http_date(&self.headers, header::DATE).unwrap_or(self.response_time);
+        if self
+            .headers
+            .contains_key(header::EXPIRES)
+        {
+            // Invalid dates, like `0`, mean the response is already expired.
+            let lifetime = http_date(&self.headers, header::EXPIRES)
+                .and_then(|expires| {
+                    expires
+                        .duration_since(date)
+                        .ok()
+                })
+                .unwrap_or_default();
+            return Some(lifetime);
+        }
+
+        if !HEURISTICALLY_CACHEABLE.contains(&self.status.as_u16()) {
+            return None;
+        }
+
+        let last_modified = http_date(&self.headers, header::LAST_MODIFIED)?;
+        date.duration_since(last_modified)
+            .ok()
+            .map(|age| age / 10)
+    }
+
+    /// Update the stored headers with the ones of a `304 Not Modified`
+    /// response, as described in RFC 9111, section 3.2.
+    ///
+    /// # Arguments
+    ///
+    /// * `headers` - The headers of the `304` response.
+    /// * `request_time` - When the revalidation request was sent.
+    /// * `response_time` - When the `304` response was received.
+    ///
+    pub fn refresh(
+        &mut self,
+        headers: &HeaderMap,
+        request_time: SystemTime,
+        response_time: SystemTime,
+    ) {
+        for name in headers.keys() {
+            if name == header::CONTENT_LENGTH {
+                continue;
+            }
+            self.headers
+                .remove(name);
+            for value in headers.get_all(name) {
+                self.headers
+                    .append(name.clone(), value.clone());
+            }
+        }
+        self.request_time = request_time;
+        self.response_time = response_time;
+    }
+
+    fn to_response(&self, age: Option<Duration>) -> DeboaResponse {
+        let mut headers = self.headers.clone();
+        if let Some(age) = age {
+            headers.insert(header::AGE, HeaderValue::from(age.as_secs()));
+        }
+        DeboaResponse::builder()
+            .status(self.status)
+            .headers(headers)
+            .body(self.body.as_ref())
+            .build()
+    }
+}
+
+/// Cache directives of a request or a response.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct CacheControl {
+    pub(crate) max_age: Option<Duration>,
+    pub(crate) s_maxage: Option<Duration>,
+    pub(crate) stale_while_revalidate: Option<Duration>,
+    pub(crate) no_store: bool,
+    pub(crate) no_cache: bool,
+    pub(crate) private: bool,
+    pub(crate) public: bool,
+    pub(crate) must_revalidate: bool,
+    pub(crate) only_if_cached: bool,
+}
+
+impl CacheControl {
+    pub(crate) fn from_headers(headers: &HeaderMap) -> Self {
+        let mut cache_control = CacheControl::default();
+        let directives = headers
+            .get_all(header::CACHE_CONTROL)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','));
+
+        for directive in directives {
+            let (name, argument) = match directive.split_once('=') {
+                Some((name, argument)) => (name, Some(argument.trim_matches([' ', '"']))),
+                None => (directive, None),
+            };
+            let seconds = argument
+                .and_then(|argument| {
+                    argument
+                        .parse::<u64>()
+                        .ok()
+                })
+                .map(Duration::from_secs);
+
+            match name
+                .trim()
+                .to_ascii_lowercase()
+                .as_str()
+            {
+                "max-age" => cache_control.max_age = seconds,
+                "s-maxage" => cache_control.s_maxage = seconds,
+                "stale-while-revalidate" => cache_control.stale_while_revalidate = seconds,
+                "no-store" => cache_control.no_store = true,
+                "no-cache" => cache_control.no_cache = true,
+                "private" => cache_control.private = true,
+                "public" => cache_control.public = true,
+                "must-revalidate" | "proxy-revalidate" => cache_control.must_revalidate = true,
+                "only-if-cached" => cache_control.only_if_cached = true,
+                _ => {}
+            }
+        }
+
+        // HTTP/1.0 caches only know `Pragma: no-cache`.
+        if !headers.contains_key(header::CACHE_CONTROL)
+            && headers
+                .get(header::PRAGMA)
+                .is_some_and(|value| value.as_bytes() == b"no-cache")
+        {
+            cache_control.no_cache = true;
+        }
+
+        cache_control
+    }
+}
+
+fn http_date(headers: &HeaderMap, name: HeaderName) -> Option<SystemTime> {
+    let value = headers
+        .get(name)?
+        .to_str()
+        .ok()?;
+    httpdate::parse_http_date(value).ok()
+}
+
+fn vary_names(headers: &HeaderMap) -> Vec<HeaderName> {
+    headers
+        .get_all(header::VARY)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .filter_map(|name| {
+            HeaderName::from_bytes(
+                name.trim()
+                    .as_bytes(),
+            )
+            .ok()
+        })
+        .collect()
+}
+
+fn cache_key(method: &Method, uri: &Uri) -> String {
+    format!("{} {}", method, uri)
+}
+
+/// In-memory cache evicting the least recently used responses.
+#[derive(Debug)]
+pub struct MemoryCache {
+    capacity: usize,
+    entries: Mutex<IndexMap<String, CachedResponse>>,
+}
+
+impl MemoryCache {
+    /// Create an in-memory cache.
+    ///
+    /// # Arguments
+    ///
+    /// * `capacity` - The maximum number of responses kept.
+    ///
+    /// # Returns
+    ///
+    /// * `MemoryCache` - The cache.
+    ///
+    pub fn new(capacity: usize) -> Self {
+        Self { capacity, entries: Mutex::new(IndexMap::new()) }
+    }
+
+    /// Get the maximum number of responses kept.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the number of responses kept.
+    pub fn len(&self) -> usize {
+        self.entries
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .len()
+    }
+
+    /// Check if the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Default for MemoryCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_CACHE_CAPACITY)
+    }
+}
+
+impl DeboaCache for MemoryCache {
+    async fn get(&self, key: &str) -> Option<CachedResponse> {
+        let mut entries = self
+            .entries
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        // Move the entry last, the front of the map is the least recently used.
+        let index = entries.get_index_of(key)?;
+        let last = entries.len() - 1;
+        entries.move_index(index, last);
+        entries
+            .get_index(last)
+            .map(|(_, response)| response.clone())
+    }
+
+    async fn put(&self, key: &str, response: CachedResponse) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let mut entries = self
+            .entries
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        entries.shift_remove(key);
+        while entries.len() >= self.capacity {
+            entries.shift_remove_index(0);
+        }
+        entries.insert(key.to_string(), response);
+    }
+
+    async fn remove(&self, key: &str) {
+        self.entries
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .shift_remove(key);
+    }
+}
+
+/// Chain caching responses as described in RFC 9111.
+///
+/// The cache is private by default, as fits a client: responses marked
+/// `private` are stored and `s-maxage` is ignored.
+#[derive(Debug, Clone)]
+pub struct Cache<S> {
+    store: Arc<S>,
+    shared: bool,
+    max_body_size: u64,
+}
+
+impl<S: DeboaCache> Cache<S> {
+    /// Create a new cache chain.
+    ///
+    /// # Arguments
+    ///
+    /// * `store` - The storage backend.
+    ///
+    /// # Returns
+    ///
+    /// * `Cache` - The chain.
+    ///
+    pub fn new(store: S) -> Self {
+        Self::from_arc(Arc::new(store))
+    }
+
+    /// Create a new cache chain from a backend shared with other clients.
     ///
     /// # Arguments
     ///
-    /// * `key` - The key to get the value from.
+    /// * `store` - The storage backend.
     ///
     /// # Returns
     ///
-    /// * `Option<String>` - The value if it exists, None otherwise.
+    /// * `Cache` - The chain.
     ///
-    fn get(&self, key: &str) -> Option<String>;
-    /// Set a value in the cache.
+    pub fn from_arc(store: Arc<S>) -> Self {
+        Self { store, shared: false, max_body_size: DEFAULT_MAX_BODY_SIZE }
+    }
+
+    /// Allow set whether the cache behaves as a shared cache.
     ///
     /// # Arguments
     ///
-    /// * `key` - The key to set the value for.
-    /// * `value` - The value to set.
+    /// * `shared` - `true` to skip `private` responses and honour `s-maxage`.
+    ///
+    /// # Returns
+    ///
+    /// * `Cache` - The chain.
     ///
-    fn set(&self, key: &str, value: &str);
-    /// Delete a value from the cache.
+    pub fn shared(mut self, shared: bool) -> Self {
+        self.shared = shared;
+        self
+    }
+
+    /// Allow set the largest response body stored.
     ///
     /// # Arguments
     ///
-    /// * `key` - The key to delete the value for.
+    /// * `max_body_size` - The size in bytes.
+    ///
+    /// # Returns
+    ///
+    /// * `Cache` - The chain.
     ///
-    fn delete(&self, key: &str);
+    pub fn max_body_size(mut self, max_body_size: u64) -> Self {
+        self.max_body_size = max_body_size;
+        self
+    }
+
+    /// Get the storage backend.
+    #[inline]
+    pub fn store(&self) -> &Arc<S> {
+        &self.store
+    }
+}
+
+impl<H, S> Chain<H, DeboaError, DeboaRequest, DeboaResponse> for Cache<S>
+where
+    H: Hook<DeboaRequest, DeboaResponse, Result = Result<DeboaResponse>>,
+    S: DeboaCache,
+{
+    type Hook = CacheHook<H, S>;
+
+    fn chain(&self, hook: H) -> Self::Hook {
+        CacheHook {
+            inner: hook,
+            store: self.store.clone(),
+            shared: self.shared,
+            max_body_size: self.max_body_size,
+            revalidating: Mutex::new(HashSet::new()),
+        }
+    }
+}
+
+/// Hook created by the [`Cache`] chain.
+///
+/// Hooks can't run work in the background, so within the
+/// `stale-while-revalidate` window a stale response is returned right away
+/// and the next request for the same resource revalidates it.
+pub struct CacheHook<H, S> {
+    inner: H,
+    store: Arc<S>,
+    shared: bool,
+    max_body_size: u64,
+    revalidating: Mutex<HashSet<String>>,
+}
+
+impl<H, S> Hook<DeboaRequest, DeboaResponse> for CacheHook<H, S>
+where
+    H: Hook<DeboaRequest, DeboaResponse, Result = Result<DeboaResponse>>,
+    S: DeboaCache,
+{
+    type Result = Result<DeboaResponse>;
+    type Error = DeboaError;
+
+    async fn call(&self, request: DeboaRequest) -> Self::Result {
+        let method = request
+            .method()
+            .clone();
+        if method != Method::GET && method != Method::HEAD {
+            return self
+                .invalidate(request)
+                .await;
+        }
+
+        let request_cache_control = CacheControl::from_headers(request.headers());
+        let conditional = [
+            header::IF_NONE_MATCH,
+            header::IF_MODIFIED_SINCE,
+            header::IF_MATCH,
+            header::IF_UNMODIFIED_SINCE,
+            header::IF_RANGE,
+            header::RANGE,
+        ]
+        .iter()
+        .any(|name| {
+            request
+                .headers()
+                .contains_key(name)
+        });
+        if request_cache_control.no_store || conditional {
+            return self
+                .inner
+                .call(request)
+                .await;
+        }
+
+        let key = cache_key(&method, request.uri());
+        let stored = self
+            .store
+            .get(&key)
+            .await
+            .filter(|stored| stored.matches(request.headers()));
+
+        let Some(stored) = stored else {
+            if request_cache_control.only_if_cached {
+                return Ok(DeboaResponse::builder()
+                    .status(StatusCode::GATEWAY_TIMEOUT)
+                    .build());
+            }
+            return self
+                .fetch(request, key)
+                .await;
+        };
+
+        let now = SystemTime::now();
+        let age = stored.age(now);
+        let lifetime = stored
+            .freshness_lifetime(self.shared)
+            .unwrap_or_default();
+        let response_cache_control = CacheControl::from_headers(stored.headers());
+        let revalidating = self
+            .revalidating
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .contains(&key);
+        let within_max_age = request_cache_control
+            .max_age
+            .is_none_or(|max_age| age <= max_age);
+        let must_revalidate =
+            revalidating || request_cache_control.no_cache || response_cache_control.no_cache;
+
+        if request_cache_control.only_if_cached
+            || (!must_revalidate && within_max_age && age < lifetime)
+        {
+            return Ok(stored.to_response(Some(age)));
+        }
+
+        if let Some(window) = response_cache_control.stale_while_revalidate {
+            if !must_revalidate
+                && !response_cache_control.must_revalidate
+                && age < lifetime + window
+            {
+                self.revalidating
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .insert(key);
+                return Ok(stored.to_response(Some(age)));
+            }
+        }
+
+        let result = self
+            .revalidate(request, &key, stored)
+            .await;
+        self.revalidating
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&key);
+        result
+    }
+}
+
+impl<H, S> CacheHook<H, S>
+where
+    H: Hook<DeboaRequest, DeboaResponse, Result = Result<DeboaResponse>>,
+    S: DeboaCache,
+{
+    /// Send a request changing a resource, dropping its stored responses.
+    async fn invalidate(&self, request: DeboaRequest) -> Result<DeboaResponse> {
+        let uri = request
+            .uri()
+            .clone();
+        let safe = matches!(*request.method(), Method::OPTIONS | Method::TRACE | Method::CONNECT);
+        let response = self
+            .inner
+            .call(request)
+            .await?;
+
+        let status = response.status();
+        if !safe && (status.is_success() || status.is_redirection()) {
+            self.store
+                .remove(&cache_key(&Method::GET, &uri))
+                .await;
+            self.store
+                .remove(&cache_key(&Method::HEAD, &uri))
+                .await;
+        }
+        Ok(response)
+    }
+
+    /// Send a request and store its response when allowed.
+    async fn fetch(&self, request: DeboaRequest, key: String) -> Result<DeboaResponse> {
+        let request_headers = request
+            .headers()
+            .clone();
+        let request_time = SystemTime::now();
+        let response = self
+            .inner
+            .call(request)
+            .await?;
+        self.store(&key, &request_headers, request_time, response)
+            .await
+    }
+
+    /// Revalidate a stored response with its validators.
+    async fn revalidate(
+        &self,
+        mut request: DeboaRequest,
+        key: &str,
+        mut stored: CachedResponse,
+    ) -> Result<DeboaResponse> {
+        let etag = stored
+            .headers()
+            .get(header::ETAG)
+            .cloned();
+        let last_modified = stored
+            .headers()
+            .get(header::LAST_MODIFIED)
+            .cloned();
+        if etag.is_none() && last_modified.is_none() {
+            return self
+                .fetch(request, key.to_string())
+                .await;
+        }
+
+        let request_headers = request
+            .headers()
+            .clone();
+        if let Some(etag) = etag {
+            request
+                .headers_mut()
+                .insert(header::IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = last_modified {
+            request
+                .headers_mut()
+                .insert(header::IF_MODIFIED_SINCE, last_modified);
+        }
+
+        let request_time = SystemTime::now();
+        let response = self
+            .inner
+            .call(request)
+            .await?;
+
+        if response.status() != StatusCode::NOT_MODIFIED {
+            return self
+                .store(key, &request_headers, request_time, response)
+                .await;
+        }
+
+        stored.refresh(response.headers(), request_time, SystemTime::now());
+        let mut refreshed = stored.to_response(None);
+        refreshed.set_redirects(
+            response
+                .redirects()
+                .to_vec(),
+        );
+        self.store
+            .put(key, stored)
+            .await;
+        Ok(refreshed)
+    }
+
+    /// Store a response when allowed, returning it to the caller.
+    async fn store(
+        &self,
+        key: &str,
+        request_headers: &HeaderMap,
+        request_time: SystemTime,
+        response: DeboaResponse,
+    ) -> Result<DeboaResponse> {
+        if !self.is_storable(request_headers, &response) {
+            return Ok(response);
+        }
+
+        let redirects = response
+            .redirects()
+            .to_vec();
+        let (parts, body) = response.into_parts();
+        // Chunked bodies are only known to be too large while read, they are
+        // then passed through without being stored.
+        let body = match read_limited(body, self.max_body_size).await? {
+            Ok(body) => body,
+            Err(body) => {
+                let mut response = DeboaResponse::new(http::Response::from_parts(parts, body));
+                response.set_redirects(redirects);
+                return Ok(response);
+            }
+        };
+
+        let stored =
+            CachedResponse::new(parts.status, parts.headers, body, request_time, SystemTime::now())
+                .vary(request_headers);
+        let mut response = stored.to_response(None);
+        response.set_redirects(redirects);
+        self.store
+            .put(key, stored)
+            .await;
+        Ok(response)
+    }
+
+    /// Check if a response may be stored, as described in RFC 9111, section 3.
+    fn is_storable(&self, request_headers: &HeaderMap, response: &DeboaResponse) -> bool {
+        let status = response.status();
+        if status.is_informational()
+            || status == StatusCode::PARTIAL_CONTENT
+            || status == StatusCode::NOT_MODIFIED
+        {
+            return false;
+        }
+
+        let headers = response.headers();
+        let cache_control = CacheControl::from_headers(headers);
+        if cache_control.no_store || (self.shared && cache_control.private) {
+            return false;
+        }
+
+        if self.shared
+            && request_headers.contains_key(header::AUTHORIZATION)
+            && !(cache_control.public
+                || cache_control.must_revalidate
+                || cache_control
+                    .s_maxage
+                    .is_some())
+        {
+            return false;
+        }
+
+        let vary_any = headers
+            .get_all(header::VARY)
+            .iter()
+            .any(|value| {
+                value
+                    .as_bytes()
+                    .split(|byte| *byte == b',')
+                    .any(|name| name.trim_ascii() == b"*")
+            });
+        if vary_any {
+            return false;
+        }
+
+        let too_large = headers
+            .get(header::CONTENT_LENGTH)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| {
+                value
+                    .parse::<u64>()
+                    .ok()
+            })
+            .is_some_and(|length| length > self.max_body_size);
+        if too_large {
+            return false;
+        }
+
+        let explicit = cache_control
+            .max_age
+            .is_some()
+            || (self.shared
+                && cache_control
+                    .s_maxage
+                    .is_some())
+            || cache_control.public
+            || headers.contains_key(header::EXPIRES);
+        let validated =
+            headers.contains_key(header::ETAG) || headers.contains_key(header::LAST_MODIFIED);
+        explicit || (HEURISTICALLY_CACHEABLE.contains(&status.as_u16()) && validated)
+    }
 }
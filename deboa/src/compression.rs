@@ -0,0 +1,584 @@
+//! Content codings for HTTP bodies.
+//!
+//! Each coding is behind its own cargo feature: `gzip`, `deflate`, `brotli`
+//! and `zstd`. When any of them is enabled the client advertises them in the
+//! `Accept-Encoding` header and decodes responses on the fly, while their body
+//! is read, removing the `Content-Encoding` and `Content-Length` headers.
+//!
+//! Request bodies can be compressed too, per request with
+//! [`DeboaRequestBuilder::compress`](crate::request::DeboaRequestBuilder::compress)
+//! or for every body above a size threshold with
+//! [`ClientBuilder::request_compression`](crate::ClientBuilder::request_compression).
+//!
+//! # Examples
+//!
+//! ## Getting the raw bytes of a response
+//!
+//! ```ignore
+//! use deboa::request::get;
+//!
+//! let response = get("https://example.com/archive")?
+//!     .decompress(false)
+//!     .send_with(&client)
+//!     .await?;
+//! ```
+//!
+//! Decompression is also skipped for requests with their own
+//! `Accept-Encoding` header.
+//!
+//! ## Compressing a request body
+//!
+//! ```ignore
+//! use deboa::{compression::Encoding, request::post};
+//!
+//! let response = post("https://example.com/ingest")?
+//!     .text(&events)
+//!     .compress(Encoding::Gzip)
+//!     .send_with(&client)
+//!     .await?;
+//! ```
+
+use crate::{
+    errors::{DeboaError, IoError},
+    request::DeboaRequest,
+    response::DeboaResponse,
+    Result,
+};
+use http::{header, HeaderMap, HeaderValue, StatusCode};
+use http_body::Body;
+use hyper_body_utils::HttpBody;
+use std::{fmt::Display, io};
+
+#[cfg(feature = "__compression")]
+use {
+    bytes::{Bytes, BytesMut},
+    futures::FutureExt,
+    futures::{
+        io::{AsyncBufRead, AsyncRead, AsyncReadExt, BufReader},
+        ready, Stream, TryStreamExt,
+    },
+    http_body::Frame,
+    http_body_util::BodyExt,
+    std::{
+        pin::Pin,
+        sync::Mutex,
+        task::{Context, Poll},
+    },
+};
+
+/// Size of the chunks yielded by an encoded or decoded body.
+#[cfg(feature = "__compression")]
+const CHUNK_SIZE: usize = 8 * 1024;
+
+/// Default smallest body compressed by the client wide request compression.
+pub const DEFAULT_COMPRESSION_THRESHOLD: u64 = 1024;
+
+/// A content coding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Encoding {
+    /// `gzip`, requires the `gzip` feature.
+    Gzip,
+    /// `deflate`, zlib wrapped, requires the `deflate` feature.
+    Deflate,
+    /// `br`, requires the `brotli` feature.
+    Brotli,
+    /// `zstd`, requires the `zstd` feature.
+    Zstd,
+}
+
+impl Encoding {
+    /// All the content codings, in order of preference.
+    pub const ALL: [Encoding; 4] =
+        [Encoding::Zstd, Encoding::Brotli, Encoding::Gzip, Encoding::Deflate];
+
+    /// Get the coding name, as used in headers.
+    ///
+    /// # Returns
+    ///
+    /// * `&'static str` - The name.
+    ///
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Encoding::Gzip => "gzip",
+            Encoding::Deflate => "deflate",
+            Encoding::Brotli => "br",
+            Encoding::Zstd => "zstd",
+        }
+    }
+
+    /// Check if the feature of the coding is enabled.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - `true` if the coding is supported.
+    ///
+    pub fn is_enabled(&self) -> bool {
+        match self {
+            Encoding::Gzip => cfg!(feature = "gzip"),
+            Encoding::Deflate => cfg!(feature = "deflate"),
+            Encoding::Brotli => cfg!(feature = "brotli"),
+            Encoding::Zstd => cfg!(feature = "zstd"),
+        }
+    }
+
+    /// Parse a coding name, as found in a `Content-Encoding` header.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<Encoding>` - The coding, `None` if unknown.
+    ///
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name
+            .trim()
+            .to_ascii_lowercase()
+            .as_str()
+        {
+            "gzip" | "x-gzip" => Some(Encoding::Gzip),
+            "deflate" => Some(Encoding::Deflate),
+            "br" => Some(Encoding::Brotli),
+            "zstd" => Some(Encoding::Zstd),
+            _ => None,
+        }
+    }
+}
+
+impl Display for Encoding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Per request decompression setting, kept in the request extensions.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Decompress(pub(crate) bool);
+
+/// Value of the `Accept-Encoding` header sent by the client.
+///
+/// # Returns
+///
+/// * `Option<HeaderValue>` - The value, `None` if no coding is enabled.
+///
+pub fn accept_encoding() -> Option<HeaderValue> {
+    let encodings = Encoding::ALL
+        .iter()
+        .filter(|encoding| encoding.is_enabled())
+        .map(Encoding::as_str)
+        .collect::<Vec<_>>();
+    if encodings.is_empty() {
+        return None;
+    }
+
+    HeaderValue::from_str(&encodings.join(", ")).ok()
+}
+
+/// Add the `Accept-Encoding` header to a request, unless the caller opted out
+/// of decompression or set the header on their own.
+///
+/// Returns whether the response should be decompressed.
+pub(crate) fn prepare(request: &mut DeboaRequest) -> bool {
+    let enabled = request
+        .extensions()
+        .get::<Decompress>()
+        .is_none_or(|decompress| decompress.0);
+    if !enabled
+        || request
+            .headers()
+            .contains_key(header::ACCEPT_ENCODING)
+    {
+        return false;
+    }
+
+    let Some(accept_encoding) = accept_encoding() else {
+        return false;
+    };
+
+    request
+        .headers_mut()
+        .insert(header::ACCEPT_ENCODING, accept_encoding);
+    true
+}
+
+/// Compress a request body, setting the `Content-Encoding` header.
+///
+/// Bodies held in memory are compressed right away, keeping their
+/// `Content-Length`, streaming bodies are compressed while they are sent.
+/// Empty bodies and bodies with a `Content-Encoding` are left untouched.
+///
+/// # Arguments
+///
+/// * `headers` - The request headers.
+/// * `body` - The request body.
+/// * `encoding` - The coding.
+///
+/// # Returns
+///
+/// * `Result<HttpBody>` - The compressed body.
+///
+pub(crate) fn compress(
+    headers: &mut HeaderMap,
+    body: HttpBody,
+    encoding: Encoding,
+) -> Result<HttpBody> {
+    if !encoding.is_enabled() {
+        return Err(DeboaError::Io(IoError::Compress {
+            message: format!("{} support is not enabled", encoding),
+        }));
+    }
+
+    if headers.contains_key(header::CONTENT_ENCODING) || Body::size_hint(&body).exact() == Some(0) {
+        return Ok(body);
+    }
+
+    let body = encode(headers, body, encoding)?;
+    headers.insert(header::CONTENT_ENCODING, HeaderValue::from_static(encoding.as_str()));
+    Ok(body)
+}
+
+/// Compress a request body when it is at least `threshold` bytes long, as
+/// done by the client wide request compression.
+///
+/// Bodies of unknown size, like streams, are not compressed.
+pub(crate) fn compress_request(
+    request: DeboaRequest,
+    encoding: Encoding,
+    threshold: u64,
+) -> Result<DeboaRequest> {
+    let (mut parts, body) = request.into_parts();
+    let large = body_size(&body).is_some_and(|size| size >= threshold);
+    let body = if large { compress(&mut parts.headers, body, encoding)? } else { body };
+    DeboaRequest::from_parts(parts, body)
+}
+
+/// Size of a body, known for bodies held in memory.
+fn body_size(body: &HttpBody) -> Option<u64> {
+    match body {
+        HttpBody::Standard(full) => full
+            .size_hint()
+            .exact(),
+        _ => None,
+    }
+}
+
+#[cfg(not(feature = "__compression"))]
+fn encode(_headers: &mut HeaderMap, body: HttpBody, _encoding: Encoding) -> Result<HttpBody> {
+    Ok(body)
+}
+
+#[cfg(feature = "__compression")]
+fn encode(headers: &mut HeaderMap, body: HttpBody, encoding: Encoding) -> Result<HttpBody> {
+    if let HttpBody::Standard(full) = body {
+        // In memory bodies and readers never wait, the futures are ready.
+        let data = full
+            .collect()
+            .now_or_never()
+            .and_then(|collected| collected.ok())
+            .map(|collected| collected.to_bytes())
+            .unwrap_or_default();
+        let mut encoded = Vec::new();
+        let result = encoder(encoding, Box::new(data.as_ref()))
+            .read_to_end(&mut encoded)
+            .now_or_never();
+        if let Some(Err(e)) = result {
+            return Err(DeboaError::Io(IoError::Compress { message: e.to_string() }));
+        }
+
+        headers.insert(header::CONTENT_LENGTH, HeaderValue::from(encoded.len()));
+        return Ok(HttpBody::from_bytes(&encoded));
+    }
+
+    headers.remove(header::CONTENT_LENGTH);
+    let reader = encoder(encoding, Box::new(DataStream(body).into_async_read()));
+    Ok(HttpBody::from_generic_stream(CodedStream {
+        reader: Mutex::new(reader),
+        buffer: BytesMut::new(),
+        decoding: false,
+    }))
+}
+
+#[cfg(feature = "__compression")]
+fn encoder<'a>(
+    encoding: Encoding,
+    reader: Box<dyn AsyncBufRead + Send + Unpin + 'a>,
+) -> Box<dyn AsyncRead + Send + Unpin + 'a> {
+    match encoding {
+        #[cfg(feature = "gzip")]
+        Encoding::Gzip => Box::new(async_compression::futures::bufread::GzipEncoder::new(reader)),
+        #[cfg(feature = "deflate")]
+        Encoding::Deflate => {
+            Box::new(async_compression::futures::bufread::ZlibEncoder::new(reader))
+        }
+        #[cfg(feature = "brotli")]
+        Encoding::Brotli => {
+            Box::new(async_compression::futures::bufread::BrotliEncoder::new(reader))
+        }
+        #[cfg(feature = "zstd")]
+        Encoding::Zstd => Box::new(async_compression::futures::bufread::ZstdEncoder::new(reader)),
+        // Only enabled codings are encoded.
+        #[allow(unreachable_patterns)]
+        _ => Box::new(reader),
+    }
+}
+
+/// Parse the codings of a response, in the order they were applied.
+///
+/// Returns `None` when the response is not encoded or any of its codings is
+/// not supported, it's then left untouched.
+fn encodings(response: &DeboaResponse) -> Option<Vec<Encoding>> {
+    let mut encodings = Vec::new();
+    for value in response
+        .headers()
+        .get_all(header::CONTENT_ENCODING)
+    {
+        for name in value
+            .to_str()
+            .ok()?
+            .split(',')
+        {
+            if name
+                .trim()
+                .eq_ignore_ascii_case("identity")
+            {
+                continue;
+            }
+            let encoding = Encoding::from_name(name).filter(Encoding::is_enabled)?;
+            encodings.push(encoding);
+        }
+    }
+
+    if encodings.is_empty() {
+        None
+    } else {
+        Some(encodings)
+    }
+}
+
+/// Decompress a response body as it is read.
+///
+/// Responses without a body, not encoded or encoded with an unsupported
+/// coding are returned as they are.
+pub(crate) fn decompress(response: DeboaResponse) -> DeboaResponse {
+    let status = response.status();
+    let empty = status == StatusCode::NO_CONTENT
+        || status == StatusCode::NOT_MODIFIED
+        || response
+            .headers()
+            .get(header::CONTENT_LENGTH)
+            .is_some_and(|length| length.as_bytes() == b"0");
+    if empty {
+        return response;
+    }
+
+    let Some(encodings) = encodings(&response) else {
+        return response;
+    };
+
+    decode(response, encodings)
+}
+
+#[cfg(not(feature = "__compression"))]
+fn decode(response: DeboaResponse, _encodings: Vec<Encoding>) -> DeboaResponse {
+    response
+}
+
+#[cfg(feature = "__compression")]
+fn decode(response: DeboaResponse, encodings: Vec<Encoding>) -> DeboaResponse {
+    let redirects = response
+        .redirects()
+        .to_vec();
+    let (mut parts, body) = response.into_parts();
+    parts
+        .headers
+        .remove(header::CONTENT_ENCODING);
+    parts
+        .headers
+        .remove(header::CONTENT_LENGTH);
+
+    let mut reader: Box<dyn AsyncBufRead + Send + Unpin> =
+        Box::new(DataStream(body).into_async_read());
+    // Codings are listed in the order they were applied, undo them backwards.
+    for encoding in encodings
+        .into_iter()
+        .rev()
+    {
+        reader = Box::new(BufReader::new(decoder(encoding, reader)));
+    }
+
+    let body = HttpBody::from_generic_stream(CodedStream {
+        reader: Mutex::new(reader),
+        buffer: BytesMut::new(),
+        decoding: true,
+    });
+    let mut response = DeboaResponse::new(http::Response::from_parts(parts, body));
+    response.set_redirects(redirects);
+    response
+}
+
+#[cfg(feature = "__compression")]
+fn decoder(
+    encoding: Encoding,
+    reader: Box<dyn AsyncBufRead + Send + Unpin>,
+) -> Box<dyn AsyncRead + Send + Unpin> {
+    match encoding {
+        #[cfg(feature = "gzip")]
+        Encoding::Gzip => {
+            let mut decoder = async_compression::futures::bufread::GzipDecoder::new(reader);
+            decoder.multiple_members(true);
+            Box::new(decoder)
+        }
+        #[cfg(feature = "deflate")]
+        Encoding::Deflate => {
+            Box::new(async_compression::futures::bufread::ZlibDecoder::new(reader))
+        }
+        #[cfg(feature = "brotli")]
+        Encoding::Brotli => {
+            Box::new(async_compression::futures::bufread::BrotliDecoder::new(reader))
+        }
+        #[cfg(feature = "zstd")]
+        Encoding::Zstd => Box::new(async_compression::futures::bufread::ZstdDecoder::new(reader)),
+        // Only enabled codings are decoded.
+        #[allow(unreachable_patterns)]
+        _ => Box::new(reader),
+    }
+}
+
+/// Error raised while decoding a body, told apart from transport errors so
+/// it can be reported as [`IoError::Decompress`](crate::errors::IoError::Decompress).
+#[cfg(feature = "__compression")]
+#[derive(Debug)]
+struct DecompressError(io::Error);
+
+#[cfg(feature = "__compression")]
+impl Display for DecompressError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+#[cfg(feature = "__compression")]
+impl std::error::Error for DecompressError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.0)
+    }
+}
+
+/// Transport error of the encoded body, passed through the decoders.
+#[cfg(feature = "__compression")]
+#[derive(Debug)]
+struct TransportError(io::Error);
+
+#[cfg(feature = "__compression")]
+impl Display for TransportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+#[cfg(feature = "__compression")]
+impl std::error::Error for TransportError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.0)
+    }
+}
+
+/// Check if a body error was raised while decoding it.
+#[cfg(feature = "__compression")]
+pub(crate) fn is_decompress_error(error: &io::Error) -> bool {
+    match error.get_ref() {
+        Some(inner) if inner.is::<DecompressError>() => true,
+        // Body errors may be wrapped by the runtime.
+        Some(inner) => inner
+            .downcast_ref::<io::Error>()
+            .is_some_and(is_decompress_error),
+        None => false,
+    }
+}
+
+#[cfg(not(feature = "__compression"))]
+pub(crate) fn is_decompress_error(_error: &io::Error) -> bool {
+    false
+}
+
+/// Data frames of an encoded body.
+#[cfg(feature = "__compression")]
+struct DataStream(HttpBody);
+
+#[cfg(feature = "__compression")]
+impl Stream for DataStream {
+    type Item = io::Result<Bytes>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let body = &mut self.get_mut().0;
+        loop {
+            let frame = match ready!(Pin::new(&mut *body).poll_frame(cx)) {
+                Some(Ok(frame)) => frame,
+                Some(Err(e)) => {
+                    return Poll::Ready(Some(Err(io::Error::new(e.kind(), TransportError(e)))))
+                }
+                None => return Poll::Ready(None),
+            };
+            // Trailers are dropped.
+            if let Ok(data) = frame.into_data() {
+                return Poll::Ready(Some(Ok(data)));
+            }
+        }
+    }
+}
+
+/// Encoded or decoded body, read in chunks from the coders.
+#[cfg(feature = "__compression")]
+struct CodedStream {
+    // Only accessed through `get_mut`, the lock makes the body `Sync`.
+    reader: Mutex<Box<dyn AsyncRead + Send + Unpin>>,
+    buffer: BytesMut,
+    decoding: bool,
+}
+
+#[cfg(feature = "__compression")]
+impl Stream for CodedStream {
+    type Item = io::Result<Frame<Bytes>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        let reader = this
+            .reader
+            .get_mut()
+            .unwrap_or_else(|e| e.into_inner());
+        this.buffer
+            .resize(CHUNK_SIZE, 0);
+
+        match ready!(Pin::new(reader).poll_read(cx, &mut this.buffer)) {
+            Ok(0) => Poll::Ready(None),
+            Ok(read) => {
+                let chunk = this
+                    .buffer
+                    .split_to(read)
+                    .freeze();
+                Poll::Ready(Some(Ok(Frame::data(chunk))))
+            }
+            Err(e) => {
+                let transport = e
+                    .get_ref()
+                    .is_some_and(|inner| inner.is::<TransportError>());
+                let error = if transport {
+                    e.into_inner()
+                        .and_then(|inner| {
+                            inner
+                                .downcast::<TransportError>()
+                                .ok()
+                        })
+                        .map(|inner| inner.0)
+                        .unwrap_or_else(|| io::Error::other("Failed to read body"))
+                } else if this.decoding {
+                    io::Error::new(e.kind(), DecompressError(e))
+                } else {
+                    e
+                };
+                Poll::Ready(Some(Err(error)))
+            }
+        }
+    }
+}
@@ -2,15 +2,19 @@
 //!
 //! This module provides functionality for managing HTTP connections.
 use crate::{
+    alt_svc::{AltService, AltSvcCache},
     cert::{Certificate, Identity},
     dns::DnsResolver,
+    pool::{Checkout, HostPermit},
+    protocol::{ProtocolPolicy, ProtocolSettings},
+    proxy::Proxy,
     response::DeboaResponse,
     Result,
 };
 use http::{Request, Version};
 use hyper_body_utils::HttpBody;
 use std::time::Duration;
-use std::{future::Future, net::IpAddr};
+use std::{future::Future, net::IpAddr, path::Path};
 
 /// Builder for connection configuration.
 pub struct ConnectionConfigBuilder<'a, I, C> {
@@ -23,6 +27,14 @@ pub struct ConnectionConfigBuilder<'a, I, C> {
     certificate: Option<&'a C>,
     skip_cert_verification: bool,
     client_bind_addr: IpAddr,
+    proxy: Option<&'a Proxy>,
+    unix_socket: Option<&'a Path>,
+    http2_prior_knowledge: bool,
+    h2c_upgrade: bool,
+    protocol_policy: ProtocolPolicy,
+    protocol_settings: ProtocolSettings,
+    alternative: Option<AltService>,
+    alt_svc: Option<&'a AltSvcCache>,
 }
 
 impl<'a, I, C> ConnectionConfigBuilder<'a, I, C>
@@ -45,6 +57,14 @@ where
             client_bind_addr: "0.0.0.0"
                 .parse()
                 .unwrap(),
+            proxy: None,
+            unix_socket: None,
+            http2_prior_knowledge: false,
+            h2c_upgrade: false,
+            protocol_policy: ProtocolPolicy::default(),
+            protocol_settings: ProtocolSettings::default(),
+            alternative: None,
+            alt_svc: None,
         }
     }
 
@@ -102,6 +122,54 @@ where
         self
     }
 
+    /// Set the proxy for the connection.
+    pub fn proxy(mut self, proxy: Option<&'a Proxy>) -> Self {
+        self.proxy = proxy;
+        self
+    }
+
+    /// Set the Unix socket the connection is opened to.
+    pub fn unix_socket(mut self, unix_socket: Option<&'a Path>) -> Self {
+        self.unix_socket = unix_socket;
+        self
+    }
+
+    /// Set whether HTTP/2 is used without TLS, with prior knowledge.
+    pub fn http2_prior_knowledge(mut self, http2_prior_knowledge: bool) -> Self {
+        self.http2_prior_knowledge = http2_prior_knowledge;
+        self
+    }
+
+    /// Set whether HTTP/1.1 connections without TLS are upgraded to HTTP/2.
+    pub fn h2c_upgrade(mut self, h2c_upgrade: bool) -> Self {
+        self.h2c_upgrade = h2c_upgrade;
+        self
+    }
+
+    /// Set the policy choosing the protocol of the connection.
+    pub fn protocol_policy(mut self, protocol_policy: ProtocolPolicy) -> Self {
+        self.protocol_policy = protocol_policy;
+        self
+    }
+
+    /// Set the settings of the HTTP/1 and HTTP/2 connections.
+    pub fn protocol_settings(mut self, protocol_settings: ProtocolSettings) -> Self {
+        self.protocol_settings = protocol_settings;
+        self
+    }
+
+    /// Set the HTTP/3 alternative of the origin, advertised with Alt-Svc.
+    pub fn alternative(mut self, alternative: Option<AltService>) -> Self {
+        self.alternative = alternative;
+        self
+    }
+
+    /// Set the cache the alternative is marked broken in when it fails.
+    pub fn alt_svc(mut self, alt_svc: Option<&'a AltSvcCache>) -> Self {
+        self.alt_svc = alt_svc;
+        self
+    }
+
     /// Build the connection configuration.
     pub fn build(self) -> ConnectionConfig<'a, I, C> {
         ConnectionConfig {
@@ -114,6 +182,14 @@ where
             certificate: self.certificate,
             skip_cert_verification: self.skip_cert_verification,
             client_bind_addr: self.client_bind_addr,
+            proxy: self.proxy,
+            unix_socket: self.unix_socket,
+            http2_prior_knowledge: self.http2_prior_knowledge,
+            h2c_upgrade: self.h2c_upgrade,
+            protocol_policy: self.protocol_policy,
+            protocol_settings: self.protocol_settings,
+            alternative: self.alternative,
+            alt_svc: self.alt_svc,
         }
     }
 }
@@ -129,6 +205,14 @@ pub struct ConnectionConfig<'a, I, C> {
     certificate: Option<&'a C>,
     skip_cert_verification: bool,
     client_bind_addr: IpAddr,
+    proxy: Option<&'a Proxy>,
+    unix_socket: Option<&'a Path>,
+    http2_prior_knowledge: bool,
+    h2c_upgrade: bool,
+    protocol_policy: ProtocolPolicy,
+    protocol_settings: ProtocolSettings,
+    alternative: Option<AltService>,
+    alt_svc: Option<&'a AltSvcCache>,
 }
 
 impl<'a, I, C> ConnectionConfig<'a, I, C>
@@ -185,6 +269,81 @@ where
     pub fn client_bind_addr(&self) -> &IpAddr {
         &self.client_bind_addr
     }
+
+    /// Get the proxy for the connection.
+    pub fn proxy(&self) -> Option<&Proxy> {
+        self.proxy
+    }
+
+    /// Get the Unix socket the connection is opened to.
+    pub fn unix_socket(&self) -> Option<&Path> {
+        self.unix_socket
+    }
+
+    /// Get whether HTTP/2 is used without TLS, with prior knowledge.
+    pub fn http2_prior_knowledge(&self) -> bool {
+        self.http2_prior_knowledge
+    }
+
+    /// Get whether HTTP/1.1 connections without TLS are upgraded to HTTP/2,
+    /// never under an HTTP/1 only policy.
+    pub fn h2c_upgrade(&self) -> bool {
+        self.h2c_upgrade && self.protocol_policy != ProtocolPolicy::Http1Only
+    }
+
+    /// Get the policy choosing the protocol of the connection.
+    pub fn protocol_policy(&self) -> ProtocolPolicy {
+        self.protocol_policy
+    }
+
+    /// Get the settings of the HTTP/1 and HTTP/2 connections.
+    pub fn protocol_settings(&self) -> &ProtocolSettings {
+        &self.protocol_settings
+    }
+
+    /// Get the HTTP/3 alternative of the origin, advertised with Alt-Svc.
+    pub fn alternative(&self) -> Option<&AltService> {
+        self.alternative
+            .as_ref()
+    }
+
+    /// Mark HTTP/3 broken for the origin after a failed connection, to its
+    /// alternative or to the origin itself, so the next requests go over TCP
+    /// for a while.
+    pub fn alternative_failed(&self) {
+        if let Some(alt_svc) = self.alt_svc {
+            alt_svc.mark_broken(self.host, self.port);
+        }
+    }
+
+    /// Check if HTTP/3 failed recently on the origin, so it is not tried
+    /// before TCP.
+    pub fn http3_broken(&self) -> bool {
+        self.alt_svc
+            .is_some_and(|alt_svc| alt_svc.is_broken(self.host, self.port))
+    }
+
+    /// Get the protocol of a connection without TLS.
+    ///
+    /// # Returns
+    ///
+    /// * `Version` - HTTP/2 if it is requested or known to be spoken by
+    ///   the server, HTTP/1.1 otherwise. The policy has the last word when it
+    ///   allows a single protocol.
+    ///
+    pub fn cleartext_version(&self) -> Version {
+        match self.protocol_policy {
+            ProtocolPolicy::Http1Only => return Version::HTTP_11,
+            ProtocolPolicy::Http2Only => return Version::HTTP_2,
+            _ => {}
+        }
+
+        if self.http2_prior_knowledge || self.protocol_version == Version::HTTP_2 {
+            Version::HTTP_2
+        } else {
+            Version::HTTP_11
+        }
+    }
 }
 
 /// Trait that represents an HTTP connection.
@@ -206,6 +365,13 @@ pub trait HttpConnectionPool {
     type ConnectionDispather: HttpConnectionDispatcher;
     /// The connection cache type.
     type ConnectionCache;
+    /// The transport shared by the connections of the pool, such as the QUIC
+    /// endpoints of HTTP/3.
+    type Transport: Clone;
+
+    /// Whether the connections can speak HTTP/3, so the alternatives
+    /// advertised with Alt-Svc are used.
+    const HTTP3: bool = false;
 
     /// Allow create a new connection pool.
     ///
@@ -223,6 +389,14 @@ pub trait HttpConnectionPool {
     ///
     fn connections(&self) -> &Self::ConnectionCache;
 
+    /// Allow get the transport shared by the connections.
+    ///
+    /// # Returns
+    ///
+    /// * `Self::Transport` - A handle to the transport.
+    ///
+    fn transport(&self) -> Self::Transport;
+
     /// Returns the number of connections.
     ///
     /// # Returns
@@ -231,22 +405,77 @@ pub trait HttpConnectionPool {
     ///
     fn connection_count(&self) -> u32;
 
+    /// Check a connection out of the pool.
+    ///
+    /// Multiplexed connections (HTTP/2 and HTTP/3) stay in the pool and a clone
+    /// of their sender is returned, HTTP/1.1 connections are handed out
+    /// exclusively until they are checked back in, and only to requests holding
+    /// a permit of the per host limiter.
+    /// This is expected to be short, the pool is locked while it runs.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The connection configuration.
+    /// * `permit` - The per host permit held by the request, if any.
+    ///
+    /// # Returns
+    ///
+    /// * `Checkout<Self::ConnectionDispather>` - The result of the checkout.
+    ///
+    fn checkout(
+        &mut self,
+        config: &ConnectionConfig<Self::Identity, Self::Certificate>,
+        permit: Option<&HostPermit>,
+    ) -> Checkout<Self::ConnectionDispather>;
+
+    /// Returns how long a request waits for a connection.
+    ///
+    /// # Returns
+    ///
+    /// * `Duration` - The checkout timeout.
+    ///
+    fn checkout_timeout(&self) -> Duration;
+
+    /// Returns the number of requests waiting for a connection.
+    ///
+    /// # Returns
+    ///
+    /// * `usize` - The number of waiting requests.
+    ///
+    fn waiting_count(&self) -> usize;
+
+    /// Return a connection to the pool once the response head was received.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The connection configuration.
+    /// * `connection` - The connection.
+    ///
+    fn checkin(
+        &mut self,
+        config: &ConnectionConfig<Self::Identity, Self::Certificate>,
+        connection: Self::ConnectionDispather,
+    );
+
     /// Allow create a new connection.
+    /// The pool is not locked while connecting, the connection
+    /// is added to the pool when it is checked in.
     ///
     /// # Arguments
     ///
+    /// * `transport` - The transport shared by the connections.
     /// * `config` - The connection configuration.
     /// * `dns_resolver` - The DNS resolver to use.
     ///
     /// # Returns
     ///
-    /// * `Result<&mut Self::ConnectionDispather>` - The connection or error.
+    /// * `Result<Self::ConnectionDispather>` - The connection or error.
     ///
-    fn create_connection<D>(
-        &mut self,
+    fn connect<D>(
+        transport: &Self::Transport,
         config: &ConnectionConfig<Self::Identity, Self::Certificate>,
         dns_resolver: &D,
-    ) -> impl Future<Output = Result<&mut Self::ConnectionDispather>>
+    ) -> impl Future<Output = Result<Self::ConnectionDispather>>
     where
         D: DnsResolver;
 }
@@ -267,6 +496,48 @@ pub trait HttpConnectionDispatcher {
         request: Request<HttpBody>,
         timeout: Duration,
     ) -> impl Future<Output = Result<DeboaResponse>>;
+
+    /// Check if the connection can carry concurrent requests.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - `true` for HTTP/2 and HTTP/3 connections.
+    ///
+    fn is_multiplexed(&self) -> bool;
+
+    /// Check if the connection was closed, by the peer or after an error.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - `true` if the connection cannot be used anymore.
+    ///
+    fn is_closed(&self) -> bool;
+
+    /// Check if the connection can send a request right away.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - `false` while an HTTP/1.1 response body is still being read.
+    ///
+    fn is_ready(&self) -> bool;
+
+    /// Wait until the connection can send a request.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<()>` - An error if the connection was closed.
+    ///
+    fn ready(&mut self) -> impl Future<Output = Result<()>>;
+
+    /// Clone the sender of a multiplexed connection.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<Self>` - The clone, or `None` for HTTP/1.1 connections.
+    ///
+    fn try_clone(&self) -> Option<Self>
+    where
+        Self: Sized;
 }
 
 /// Trait that represents the HTTP connection.
@@ -287,6 +558,7 @@ pub trait ProtoConnection {
     /// # Arguments
     ///
     /// * `stream` - The runtime stream to use.
+    /// * `settings` - The settings of the HTTP/1 and HTTP/2 connections.
     ///
     /// # Errors
     ///
@@ -296,7 +568,10 @@ pub trait ProtoConnection {
     ///
     /// * `Result<Self::Connection>` - The connection or error.
     ///
-    fn connect(stream: Self::RuntimeStream) -> impl Future<Output = Result<Self::Connection>>;
+    fn connect(
+        stream: Self::RuntimeStream,
+        settings: &ProtocolSettings,
+    ) -> impl Future<Output = Result<Self::Connection>>;
 
     /// Get connection protocol.
     ///
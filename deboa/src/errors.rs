@@ -13,6 +13,20 @@
 //! - `ContentError`: Errors related to content serialization/deserialization
 //! - `IoError`: I/O related errors
 //!
+//! Errors caused by a lower level failure, like an `io::Error` or a
+//! `hyper::Error`, keep it as their [`source`](std::error::Error::source), and
+//! errors of a request carry its method and URL where known.
+//!
+//! # Classification
+//!
+//! [`DeboaError::is_timeout`], [`DeboaError::is_connect`],
+//! [`DeboaError::is_tls`], [`DeboaError::is_dns`] and [`DeboaError::is_body`]
+//! tell the kind of failure apart, for retries or alerting.
+//!
+//! A response with an error status is not an error by itself: call
+//! `DeboaResponse::error_for_status`, or chain `ErrorForStatus`, to turn it
+//! into a [`ResponseError::Status`].
+//!
 //! # Examples
 //!
 //! ## Handling Errors
@@ -45,7 +59,15 @@
 //!   Ok(())
 //! }
 //! ``
-use http::StatusCode;
+use bytes::Bytes;
+use http::{HeaderMap, Method, StatusCode, Uri};
+use std::{
+    any::Any,
+    error::Error as StdError,
+    fmt::{self, Debug, Display},
+    io,
+    sync::Arc,
+};
 use thiserror::Error;
 
 /// The main error type for the Deboa HTTP client.
@@ -120,6 +142,319 @@ pub enum DeboaError {
     UnsupportedProtocol,
 }
 
+impl DeboaError {
+    /// Check if the error is a timeout, of a request or of a connection.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - True if the error is a timeout.
+    ///
+    pub fn is_timeout(&self) -> bool {
+        matches!(
+            self,
+            DeboaError::Request(RequestError::Timeout { .. })
+                | DeboaError::Connection(ConnectionError::Timeout { .. })
+        ) || self
+            .downcast_source::<io::Error>()
+            .is_some_and(is_timed_out)
+            || self
+                .downcast_source::<hyper::Error>()
+                .is_some_and(hyper::Error::is_timeout)
+    }
+
+    /// Check if the error happened while connecting, TLS handshakes and
+    /// proxies included.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - True if the error is a connection error.
+    ///
+    pub fn is_connect(&self) -> bool {
+        matches!(
+            self,
+            DeboaError::Connection(
+                ConnectionError::Tcp { .. }
+                    | ConnectionError::Tls { .. }
+                    | ConnectionError::Udp { .. }
+                    | ConnectionError::Unix { .. }
+                    | ConnectionError::Handshake { .. }
+                    | ConnectionError::Timeout { .. }
+                    | ConnectionError::Proxy { .. }
+                    | ConnectionError::UnsupportedScheme { .. }
+            )
+        )
+    }
+
+    /// Check if the error is a TLS error.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - True if the error is a TLS error.
+    ///
+    pub fn is_tls(&self) -> bool {
+        matches!(self, DeboaError::Connection(ConnectionError::Tls { .. }))
+    }
+
+    /// Check if the error happened while resolving a host name.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - True if the error is a DNS error.
+    ///
+    pub fn is_dns(&self) -> bool {
+        matches!(self, DeboaError::Dns(_))
+    }
+
+    /// Check if the error happened while reading or writing a body.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - True if the error is a body error.
+    ///
+    pub fn is_body(&self) -> bool {
+        matches!(
+            self,
+            DeboaError::Io(
+                IoError::Content { .. } | IoError::Decompress { .. } | IoError::Compress { .. }
+            )
+        ) || self
+            .downcast_source::<hyper::Error>()
+            .is_some_and(|e| e.is_body_write_aborted() || e.is_incomplete_message())
+    }
+
+    /// Get the method of the request that failed, if known.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<&Method>` - The method of the request.
+    ///
+    pub fn method(&self) -> Option<&Method> {
+        match self {
+            DeboaError::Request(
+                RequestError::Send { method, .. } | RequestError::Timeout { method, .. },
+            ) => method.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// Get the URL of the request that failed, if known.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<&Uri>` - The URL of the request.
+    ///
+    pub fn url(&self) -> Option<&Uri> {
+        match self {
+            DeboaError::Request(
+                RequestError::Send { url, .. } | RequestError::Timeout { url, .. },
+            ) => url.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Get the status of the response that failed, if any.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<StatusCode>` - The status of the response.
+    ///
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            DeboaError::Response(
+                ResponseError::Status { status, .. }
+                | ResponseError::Receive { status_code: status, .. },
+            ) => Some(*status),
+            _ => None,
+        }
+    }
+
+    /// Get the deserialized body of the response that failed.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<&T>` - The body, if it was deserialized as a `T`.
+    ///
+    pub fn error_body<T: 'static>(&self) -> Option<&T> {
+        match self {
+            DeboaError::Response(ResponseError::Status { details: Some(details), .. }) => {
+                details.downcast_ref()
+            }
+            _ => None,
+        }
+    }
+
+    /// Find an error of the given type in the source chain.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<&E>` - The first error of type `E` in the chain.
+    ///
+    /// # Examples
+    ///
+    /// ```rust, ignore
+    /// if let Some(e) = error.downcast_source::<std::io::Error>() {
+    ///     eprintln!("io error: {:?}", e.kind());
+    /// }
+    /// ```
+    pub fn downcast_source<E: StdError + 'static>(&self) -> Option<&E> {
+        let mut next = StdError::source(self);
+        while let Some(error) = next {
+            let error = match error.downcast_ref::<ErrorSource>() {
+                Some(source) => source.get_ref(),
+                None => error,
+            };
+            if let Some(error) = error.downcast_ref::<E>() {
+                return Some(error);
+            }
+            // An io::Error leaves the error it wraps out of the chain.
+            next = match error
+                .downcast_ref::<io::Error>()
+                .and_then(io::Error::get_ref)
+            {
+                Some(inner) => Some(inner as &(dyn StdError + 'static)),
+                None => error.source(),
+            };
+        }
+        None
+    }
+
+    /// Set the method and URL of requests errors that miss them.
+    pub(crate) fn with_request(mut self, request_method: &Method, request_url: &Uri) -> Self {
+        if let DeboaError::Request(
+            RequestError::Send { method, url, .. } | RequestError::Timeout { method, url, .. },
+        ) = &mut self
+        {
+            method.get_or_insert_with(|| request_method.clone());
+            url.get_or_insert_with(|| Box::new(request_url.clone()));
+        }
+        self
+    }
+}
+
+/// Check if an io error, or the io error it wraps, timed out.
+fn is_timed_out(error: &io::Error) -> bool {
+    error.kind() == io::ErrorKind::TimedOut
+        || error
+            .get_ref()
+            .and_then(|inner| inner.downcast_ref::<io::Error>())
+            .is_some_and(is_timed_out)
+}
+
+/// The underlying error of a Deboa error.
+///
+/// Keeps the original error, like an `io::Error` or a `hyper::Error`, behind
+/// an `Arc` so errors stay cheap to clone. It displays as the original error
+/// and continues its source chain.
+#[derive(Clone)]
+pub struct ErrorSource(Arc<dyn StdError + Send + Sync>);
+
+impl ErrorSource {
+    /// Wrap an error.
+    ///
+    /// # Arguments
+    ///
+    /// * `error` - The original error.
+    ///
+    /// # Returns
+    ///
+    /// * `ErrorSource` - The wrapped error.
+    ///
+    pub fn new<E: StdError + Send + Sync + 'static>(error: E) -> Self {
+        Self(Arc::new(error))
+    }
+
+    /// Get the original error.
+    ///
+    /// # Returns
+    ///
+    /// * `&(dyn StdError + Send + Sync + 'static)` - The original error.
+    ///
+    pub fn get_ref(&self) -> &(dyn StdError + Send + Sync + 'static) {
+        self.0.as_ref()
+    }
+
+    /// Get the original error as a concrete type.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<&E>` - The original error, if it is an `E`.
+    ///
+    pub fn downcast_ref<E: StdError + 'static>(&self) -> Option<&E> {
+        self.0
+            .downcast_ref()
+    }
+}
+
+impl Debug for ErrorSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Debug::fmt(&self.0, f)
+    }
+}
+
+impl Display for ErrorSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Display::fmt(&self.0, f)
+    }
+}
+
+impl StdError for ErrorSource {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        self.0.source()
+    }
+}
+
+impl PartialEq for ErrorSource {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0) || self.to_string() == other.to_string()
+    }
+}
+
+/// The deserialized body of a response with an error status.
+///
+/// Keeps the value behind an `Arc` so errors stay cheap to clone. Two bodies
+/// are equal only if they are the same value.
+#[derive(Clone)]
+pub struct ErrorBody(Arc<dyn Any + Send + Sync>);
+
+impl ErrorBody {
+    /// Wrap a deserialized body.
+    ///
+    /// # Arguments
+    ///
+    /// * `body` - The deserialized body.
+    ///
+    /// # Returns
+    ///
+    /// * `ErrorBody` - The wrapped body.
+    ///
+    pub fn new<T: Any + Send + Sync>(body: T) -> Self {
+        Self(Arc::new(body))
+    }
+
+    /// Get the body as a concrete type.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<&T>` - The body, if it is a `T`.
+    ///
+    pub fn downcast_ref<T: 'static>(&self) -> Option<&T> {
+        self.0
+            .downcast_ref()
+    }
+}
+
+impl Debug for ErrorBody {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ErrorBody(..)")
+    }
+}
+
+impl PartialEq for ErrorBody {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
 /// Request error
 #[derive(Debug, Clone, Error, PartialEq)]
 pub enum RequestError {
@@ -135,6 +470,12 @@ pub enum RequestError {
     Send {
         /// Error message
         message: String,
+        /// Method of the request, if known
+        method: Option<Method>,
+        /// URL of the request, if known, boxed to keep errors small
+        url: Option<Box<Uri>>,
+        /// Underlying error, if any
+        source: Option<ErrorSource>,
     },
 
     /// Failed to prepare request
@@ -163,6 +504,19 @@ pub enum RequestError {
     Timeout {
         /// Error message
         message: String,
+        /// Method of the request, if known
+        method: Option<Method>,
+        /// URL of the request, if known, boxed to keep errors small
+        url: Option<Box<Uri>>,
+        /// Underlying error, if any
+        source: Option<ErrorSource>,
+    },
+
+    /// Failed to follow a redirect
+    #[error("Failed to follow redirect: {message}")]
+    Redirect {
+        /// Error message
+        message: String,
     },
 }
 
@@ -174,6 +528,8 @@ pub enum ConnectionError {
     Tcp {
         /// Error message
         message: String,
+        /// Underlying error, if any
+        source: Option<ErrorSource>,
     },
 
     /// Tls connection error
@@ -181,6 +537,8 @@ pub enum ConnectionError {
     Tls {
         /// Error message
         message: String,
+        /// Underlying error, if any
+        source: Option<ErrorSource>,
     },
 
     /// Udp connection error
@@ -188,6 +546,17 @@ pub enum ConnectionError {
     Udp {
         /// Error message
         message: String,
+        /// Underlying error, if any
+        source: Option<ErrorSource>,
+    },
+
+    /// Unix socket connection error
+    #[error("Unix socket error: {message}")]
+    Unix {
+        /// Error message
+        message: String,
+        /// Underlying error, if any
+        source: Option<ErrorSource>,
     },
 
     /// Connection handshake error
@@ -195,6 +564,8 @@ pub enum ConnectionError {
     Handshake {
         /// Error message
         message: String,
+        /// Underlying error, if any
+        source: Option<ErrorSource>,
     },
 
     /// Connection upgrade error
@@ -202,6 +573,8 @@ pub enum ConnectionError {
     Upgrade {
         /// Error message
         message: String,
+        /// Underlying error, if any
+        source: Option<ErrorSource>,
     },
 
     /// Connection timeout error
@@ -209,6 +582,26 @@ pub enum ConnectionError {
     Timeout {
         /// Error message
         message: String,
+        /// Underlying error, if any
+        source: Option<ErrorSource>,
+    },
+
+    /// Connection closed by the peer error
+    #[error("Connection closed: {message}")]
+    Closed {
+        /// Error message
+        message: String,
+        /// Underlying error, if any
+        source: Option<ErrorSource>,
+    },
+
+    /// Proxy error
+    #[error("Proxy error: {message}")]
+    Proxy {
+        /// Error message
+        message: String,
+        /// Underlying error, if any
+        source: Option<ErrorSource>,
     },
 
     /// Unsupported scheme error
@@ -265,6 +658,8 @@ pub enum DnsError {
         host: String,
         /// Error message
         message: String,
+        /// Underlying error, if any
+        source: Option<ErrorSource>,
     },
 
     /// Failed to initialize resolver
@@ -272,6 +667,8 @@ pub enum DnsError {
     Resolver {
         /// Error message
         message: String,
+        /// Underlying error, if any
+        source: Option<ErrorSource>,
     },
 }
 
@@ -283,6 +680,8 @@ pub enum IoError {
     Content {
         /// Error message
         message: String,
+        /// Underlying error, if any
+        source: Option<ErrorSource>,
     },
 
     /// Failed to write file
@@ -304,6 +703,8 @@ pub enum IoError {
     Decompress {
         /// Error message
         message: String,
+        /// Underlying error, if any
+        source: Option<ErrorSource>,
     },
 
     /// Failed to write to stdout
@@ -346,4 +747,17 @@ pub enum ResponseError {
         /// Error message
         message: String,
     },
+
+    /// Response with an error status
+    #[error("Response with error status: {status}")]
+    Status {
+        /// Status code
+        status: StatusCode,
+        /// Response headers
+        headers: Box<HeaderMap>,
+        /// The start of the response body
+        body: Bytes,
+        /// The deserialized response body, if requested
+        details: Option<ErrorBody>,
+    },
 }
@@ -0,0 +1,565 @@
+//! h2c module
+//!
+//! This module implements the `Upgrade: h2c` negotiation of cleartext HTTP/2
+//! described in RFC 7540, section 3.2, shared by the runtime crates.
+//!
+//! # Features
+//!
+//! - The upgrade is asked with an `OPTIONS /` request, when a new HTTP/1.1
+//!   connection is opened to an `http://` URL without a proxy, before the
+//!   HTTP/1.1 handshake
+//! - A server declining the upgrade keeps the HTTP/1.1 connection, once its
+//!   response has been skipped
+//! - A server accepting it answers the `OPTIONS /` request on stream 1, which
+//!   is read as the response of a replayed request whose frames are never
+//!   sent, so the HTTP/2 connection starts in the state the server expects
+//!
+//! # Examples
+//!
+//! ```ignore
+//! use deboa_tokio::Client;
+//!
+//! let client = Client::builder()
+//!     .h2c_upgrade(true)
+//!     .build();
+//! ```
+
+use crate::{
+    cert::{Certificate, Identity},
+    conn::ConnectionConfig,
+    errors::{ConnectionError, DeboaError, ErrorSource},
+    proxy,
+    request::Http2Request,
+    Result,
+};
+use http::{Method, Request};
+use http_body_util::BodyExt;
+use hyper::rt::{Read, ReadBuf, ReadBufCursor, Write};
+use hyper_body_utils::HttpBody;
+use std::{
+    future::poll_fn,
+    io,
+    pin::Pin,
+    task::{ready, Context, Poll, Waker},
+};
+
+/// Settings sent with the upgrade request, `SETTINGS_ENABLE_PUSH` set to 0,
+/// in base64url.
+const HTTP2_SETTINGS: &str = "AAIAAAAA";
+
+/// Client connection preface of HTTP/2.
+const PREFACE_LEN: usize = 24;
+
+/// Size of a frame header.
+const FRAME_HEADER_LEN: usize = 9;
+
+/// Largest response head or chunk size line accepted from the server.
+const MAX_RESPONSE_HEAD_SIZE: usize = 8192;
+
+/// Buffered output above which writes wait for the connection.
+const MAX_WRITE_BUFFER: usize = 64 * 1024;
+
+const FRAME_DATA: u8 = 0x0;
+const FRAME_HEADERS: u8 = 0x1;
+const FRAME_SETTINGS: u8 = 0x4;
+const FRAME_CONTINUATION: u8 = 0x9;
+const FLAG_ACK: u8 = 0x1;
+
+/// `SETTINGS_HEADER_TABLE_SIZE` set to 0.
+const NO_HEADER_TABLE: [u8; 6] = [0x0, 0x1, 0x0, 0x0, 0x0, 0x0];
+
+/// Ask the server to switch a new HTTP/1.1 connection to HTTP/2.
+///
+/// # Arguments
+///
+/// * `io` - The connection, before the HTTP/1.1 handshake.
+/// * `config` - The connection configuration.
+///
+/// # Returns
+///
+/// * `Result<bool>` - `true` if the server switched to HTTP/2, the connection
+///   is then to be wrapped in [`H2cIo`], `false` if the upgrade is disabled,
+///   not applicable or declined by the server.
+///
+pub async fn upgrade<T, I, C>(io: &mut T, config: &ConnectionConfig<'_, I, C>) -> Result<bool>
+where
+    T: Read + Write + Unpin,
+    I: Identity,
+    C: Certificate,
+{
+    if !config.h2c_upgrade()
+        || config.scheme() != "http"
+        || config
+            .proxy()
+            .is_some()
+    {
+        return Ok(false);
+    }
+
+    let request = format!(
+        "OPTIONS / HTTP/1.1\r\nHost: {}\r\nConnection: Upgrade, HTTP2-Settings\r\nUpgrade: h2c\r\nHTTP2-Settings: {HTTP2_SETTINGS}\r\n\r\n",
+        proxy::authority(config.host(), config.port())
+    );
+    write_all(io, request.as_bytes())
+        .await
+        .map_err(upgrade_error)?;
+
+    // Informational responses come before the final one.
+    let (keep_alive, status, headers) = loop {
+        let head = read_until(io, b"\r\n\r\n", MAX_RESPONSE_HEAD_SIZE).await?;
+        let head = String::from_utf8_lossy(&head);
+        let mut lines = head.lines();
+        let status_line = lines
+            .next()
+            .unwrap_or_default();
+        let mut parts = status_line.splitn(3, ' ');
+        let (Some(version), Some(Ok(status))) = (
+            parts.next(),
+            parts
+                .next()
+                .map(str::parse::<u16>),
+        ) else {
+            return Err(declined(format!("Invalid upgrade response: {status_line}")));
+        };
+
+        match status {
+            101 => return Ok(true),
+            100..=199 => continue,
+            _ => {
+                let headers = lines
+                    .filter_map(|line| line.split_once(':'))
+                    .map(|(name, value)| {
+                        (
+                            name.trim()
+                                .to_ascii_lowercase(),
+                            value
+                                .trim()
+                                .to_ascii_lowercase(),
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                break (version == "HTTP/1.1", status, headers);
+            }
+        }
+    };
+
+    let header = |name: &str| {
+        headers
+            .iter()
+            .find(|(header, _)| header == name)
+            .map(|(_, value)| value.as_str())
+    };
+    if !keep_alive || header("connection").is_some_and(|value| value.contains("close")) {
+        return Err(declined("Server declined the upgrade and closed the connection"));
+    }
+
+    // The response to the upgrade request is skipped, the connection is then
+    // ready for HTTP/1.1 requests.
+    if status == 204 || status == 304 {
+        return Ok(false);
+    }
+    if header("transfer-encoding").is_some_and(|value| value.ends_with("chunked")) {
+        loop {
+            let line = read_until(io, b"\r\n", MAX_RESPONSE_HEAD_SIZE).await?;
+            let line = String::from_utf8_lossy(&line);
+            let size = line
+                .trim_end()
+                .split(';')
+                .next()
+                .and_then(|size| usize::from_str_radix(size.trim(), 16).ok())
+                .ok_or_else(|| declined(format!("Invalid chunk size: {}", line.trim_end())))?;
+            if size == 0 {
+                // Trailers, up to an empty line.
+                while read_until(io, b"\r\n", MAX_RESPONSE_HEAD_SIZE)
+                    .await?
+                    .len()
+                    > 2
+                {}
+                return Ok(false);
+            }
+            skip(io, size + 2).await?;
+        }
+    }
+    match header("content-length").map(str::parse::<usize>) {
+        Some(Ok(len)) => {
+            skip(io, len).await?;
+            Ok(false)
+        }
+        _ => Err(declined("Server declined the upgrade with an unbounded response")),
+    }
+}
+
+async fn write_all<T>(io: &mut T, mut bytes: &[u8]) -> io::Result<()>
+where
+    T: Write + Unpin,
+{
+    while !bytes.is_empty() {
+        let n = poll_fn(|cx| Pin::new(&mut *io).poll_write(cx, bytes)).await?;
+        if n == 0 {
+            return Err(io::ErrorKind::WriteZero.into());
+        }
+        bytes = &bytes[n..];
+    }
+    poll_fn(|cx| Pin::new(&mut *io).poll_flush(cx)).await
+}
+
+async fn read<T>(io: &mut T, buf: &mut [u8]) -> Result<usize>
+where
+    T: Read + Unpin,
+{
+    let mut read_buf = ReadBuf::new(buf);
+    poll_fn(|cx| Pin::new(&mut *io).poll_read(cx, read_buf.unfilled()))
+        .await
+        .map_err(upgrade_error)?;
+    match read_buf
+        .filled()
+        .len()
+    {
+        0 => Err(declined("Connection closed during the upgrade")),
+        n => Ok(n),
+    }
+}
+
+/// Read byte by byte, the bytes following `end` belong to the next response.
+async fn read_until<T>(io: &mut T, end: &[u8], limit: usize) -> Result<Vec<u8>>
+where
+    T: Read + Unpin,
+{
+    let mut bytes = Vec::with_capacity(256);
+    let mut byte = [0u8; 1];
+    while !bytes.ends_with(end) {
+        if bytes.len() >= limit {
+            return Err(declined("Upgrade response too large"));
+        }
+        read(io, &mut byte).await?;
+        bytes.push(byte[0]);
+    }
+    Ok(bytes)
+}
+
+async fn skip<T>(io: &mut T, mut len: usize) -> Result<()>
+where
+    T: Read + Unpin,
+{
+    let mut chunk = vec![0u8; len.min(4096)];
+    while len > 0 {
+        let max = len.min(chunk.len());
+        len -= read(io, &mut chunk[..max]).await?;
+    }
+    Ok(())
+}
+
+/// Read the response of the server to the upgrade request.
+///
+/// Must be called on the HTTP/2 connection made over the upgraded connection,
+/// before any other request.
+///
+/// # Arguments
+///
+/// * `sender` - The sender of the HTTP/2 connection.
+/// * `config` - The connection configuration.
+///
+/// # Returns
+///
+/// * `Result<()>` - Ok once the connection is ready for requests.
+///
+pub async fn finish<I, C>(
+    sender: &mut Http2Request,
+    config: &ConnectionConfig<'_, I, C>,
+) -> Result<()>
+where
+    I: Identity,
+    C: Certificate,
+{
+    let request = Request::builder()
+        .method(Method::OPTIONS)
+        .uri(format!("http://{}/", proxy::authority(config.host(), config.port())))
+        .body(HttpBody::empty())
+        .map_err(upgrade_error)?;
+
+    sender
+        .ready()
+        .await
+        .map_err(upgrade_error)?;
+    sender
+        .send_request(request)
+        .await
+        .map_err(upgrade_error)?
+        .into_body()
+        .collect()
+        .await
+        .map_err(upgrade_error)?;
+
+    Ok(())
+}
+
+fn declined(message: impl Into<String>) -> DeboaError {
+    DeboaError::Connection(ConnectionError::Upgrade { message: message.into(), source: None })
+}
+
+fn upgrade_error<E>(e: E) -> DeboaError
+where
+    E: std::error::Error + Send + Sync + 'static,
+{
+    DeboaError::Connection(ConnectionError::Upgrade {
+        message: e.to_string(),
+        source: Some(ErrorSource::new(e)),
+    })
+}
+
+/// Progress of the output through the frames of the HTTP/2 connection.
+enum Filter {
+    /// Bytes of the connection preface left.
+    Preface(usize),
+    /// Frame header being read, and its length so far.
+    Header([u8; FRAME_HEADER_LEN], usize),
+    /// Bytes of the frame payload left, and whether they are sent.
+    Payload { remaining: usize, forward: bool },
+}
+
+impl Filter {
+    /// Append the bytes of `input` to send to `output`.
+    ///
+    /// Returns `true` once the headers of stream 1 have been dropped.
+    fn feed(&mut self, mut input: &[u8], output: &mut Vec<u8>) -> bool {
+        let mut replayed = false;
+        while !input.is_empty() {
+            match self {
+                Filter::Preface(remaining) => {
+                    let n = (*remaining).min(input.len());
+                    output.extend_from_slice(&input[..n]);
+                    input = &input[n..];
+                    *remaining -= n;
+                    if *remaining == 0 {
+                        *self = Filter::Header([0; FRAME_HEADER_LEN], 0);
+                    }
+                }
+                Filter::Header(header, filled) => {
+                    let n = (FRAME_HEADER_LEN - *filled).min(input.len());
+                    header[*filled..*filled + n].copy_from_slice(&input[..n]);
+                    input = &input[n..];
+                    *filled += n;
+                    if *filled == FRAME_HEADER_LEN {
+                        let stream_id =
+                            u32::from_be_bytes([header[5], header[6], header[7], header[8]])
+                                & 0x7fff_ffff;
+                        let forward = !(stream_id == 1
+                            && matches!(
+                                header[3],
+                                FRAME_DATA | FRAME_HEADERS | FRAME_CONTINUATION
+                            ));
+                        if forward {
+                            output.extend_from_slice(header);
+                        } else if header[3] == FRAME_HEADERS {
+                            replayed = true;
+                        }
+                        *self = Filter::Payload { remaining: frame_len(header), forward };
+                    }
+                }
+                Filter::Payload { remaining, forward } => {
+                    let n = (*remaining).min(input.len());
+                    if *forward {
+                        output.extend_from_slice(&input[..n]);
+                    }
+                    input = &input[n..];
+                    *remaining -= n;
+                    if *remaining == 0 {
+                        *self = Filter::Header([0; FRAME_HEADER_LEN], 0);
+                    }
+                }
+            }
+        }
+        replayed
+    }
+}
+
+fn frame_len(header: &[u8]) -> usize {
+    u32::from_be_bytes([0, header[0], header[1], header[2]]) as usize
+}
+
+/// Connection upgraded to HTTP/2 with `Upgrade: h2c`.
+///
+/// The server answers the upgrade request on stream 1, so the replayed request
+/// opening stream 1 on the client side is never sent, and the frames coming
+/// from the server are held back until it has been, telling apart the answer
+/// to the upgrade request from an unexpected stream. The first `SETTINGS`
+/// frame of the server also disables the header table of the client, which
+/// would otherwise index the headers of the replayed request.
+pub struct H2cIo<T> {
+    inner: T,
+    first_frame: Option<Vec<u8>>,
+    read_buf: Vec<u8>,
+    read_pos: usize,
+    read_waker: Option<Waker>,
+    held: Vec<u8>,
+    replayed: bool,
+    filter: Filter,
+    write_buf: Vec<u8>,
+    write_pos: usize,
+}
+
+impl<T> H2cIo<T> {
+    /// Wrap an upgraded connection.
+    pub fn new(inner: T) -> Self {
+        Self {
+            inner,
+            first_frame: Some(Vec::new()),
+            read_buf: Vec::new(),
+            read_pos: 0,
+            read_waker: None,
+            held: Vec::new(),
+            replayed: false,
+            filter: Filter::Preface(PREFACE_LEN),
+            write_buf: Vec::new(),
+            write_pos: 0,
+        }
+    }
+}
+
+impl<T> H2cIo<T>
+where
+    T: Write + Unpin,
+{
+    fn poll_drain(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        while self.write_pos < self.write_buf.len() {
+            let n = ready!(
+                Pin::new(&mut self.inner).poll_write(cx, &self.write_buf[self.write_pos..])
+            )?;
+            if n == 0 {
+                return Poll::Ready(Err(io::ErrorKind::WriteZero.into()));
+            }
+            self.write_pos += n;
+        }
+        self.write_buf
+            .clear();
+        self.write_pos = 0;
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<T> Read for H2cIo<T>
+where
+    T: Read + Unpin,
+{
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        mut buf: ReadBufCursor<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        loop {
+            if this.read_pos < this.read_buf.len() {
+                let n = buf
+                    .remaining()
+                    .min(this.read_buf.len() - this.read_pos);
+                buf.put_slice(&this.read_buf[this.read_pos..this.read_pos + n]);
+                this.read_pos += n;
+                if this.read_pos == this.read_buf.len() {
+                    this.read_buf = Vec::new();
+                    this.read_pos = 0;
+                }
+                return Poll::Ready(Ok(()));
+            }
+
+            let Some(frame) = this
+                .first_frame
+                .as_mut()
+            else {
+                if !this.replayed {
+                    this.read_waker = Some(cx.waker().clone());
+                    return Poll::Pending;
+                }
+                if !this.held.is_empty() {
+                    this.read_buf = std::mem::take(&mut this.held);
+                    continue;
+                }
+                return Pin::new(&mut this.inner).poll_read(cx, buf);
+            };
+
+            let mut chunk = [0; 4096];
+            let mut chunk_buf = ReadBuf::new(&mut chunk);
+            ready!(Pin::new(&mut this.inner).poll_read(cx, chunk_buf.unfilled()))?;
+            let read = chunk_buf.filled();
+            frame.extend_from_slice(read);
+
+            if read.is_empty() {
+                this.read_buf = this
+                    .first_frame
+                    .take()
+                    .unwrap_or_default();
+                if this
+                    .read_buf
+                    .is_empty()
+                {
+                    return Poll::Ready(Ok(()));
+                }
+                continue;
+            }
+
+            if frame.len() < FRAME_HEADER_LEN {
+                continue;
+            }
+            let len = frame_len(frame);
+            if frame.len() < FRAME_HEADER_LEN + len {
+                continue;
+            }
+
+            this.held = frame.split_off(FRAME_HEADER_LEN + len);
+            if frame[3] == FRAME_SETTINGS && frame[4] & FLAG_ACK == 0 {
+                let len = (len + NO_HEADER_TABLE.len()) as u32;
+                frame[..3].copy_from_slice(&len.to_be_bytes()[1..]);
+                frame.extend_from_slice(&NO_HEADER_TABLE);
+            }
+            this.read_buf = this
+                .first_frame
+                .take()
+                .unwrap_or_default();
+        }
+    }
+}
+
+impl<T> Write for H2cIo<T>
+where
+    T: Write + Unpin,
+{
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        if this.write_buf.len() >= MAX_WRITE_BUFFER {
+            ready!(this.poll_drain(cx))?;
+        }
+
+        if this
+            .filter
+            .feed(buf, &mut this.write_buf)
+        {
+            this.replayed = true;
+            if let Some(waker) = this
+                .read_waker
+                .take()
+            {
+                waker.wake();
+            }
+        }
+
+        if let Poll::Ready(Err(e)) = this.poll_drain(cx) {
+            return Poll::Ready(Err(e));
+        }
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        ready!(this.poll_drain(cx))?;
+        Pin::new(&mut this.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        ready!(this.poll_drain(cx))?;
+        Pin::new(&mut this.inner).poll_shutdown(cx)
+    }
+}
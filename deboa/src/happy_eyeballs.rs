@@ -0,0 +1,170 @@
+//! Happy Eyeballs module
+//!
+//! This module implements connection racing as described in RFC 8305, shared
+//! by the runtime crates.
+//!
+//! # Features
+//!
+//! - Resolved addresses are interleaved by family, starting with the family of
+//!   the first address returned by the resolver
+//! - Attempts are started `DEFAULT_CONNECTION_ATTEMPT_DELAY` apart, or as soon as
+//!   the previous one fails, the first connection established wins
+//! - An unspecified bind address does not restrict the address family
+use crate::{
+    errors::{DeboaError, RequestError},
+    Result,
+};
+use futures::{
+    future::{select, Either},
+    stream::{FuturesUnordered, StreamExt},
+};
+use futures_timer::Delay;
+use std::{
+    future::Future,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    time::Duration,
+};
+
+/// Default delay between two connection attempts, as recommended by RFC 8305.
+pub const DEFAULT_CONNECTION_ATTEMPT_DELAY: Duration = Duration::from_millis(250);
+
+/// Sort resolved addresses for connection racing.
+///
+/// Addresses of the other family than the bind address are dropped, unless
+/// the bind address is unspecified. The remaining addresses are interleaved
+/// by family, keeping the order of the resolver within each family.
+///
+/// # Arguments
+///
+/// * `addrs` - The resolved addresses.
+/// * `bind_addr` - The local address the client binds to.
+///
+/// # Returns
+///
+/// * `Vec<IpAddr>` - The addresses, in the order they should be tried.
+///
+pub fn sort_addresses(addrs: Vec<IpAddr>, bind_addr: &IpAddr) -> Vec<IpAddr> {
+    let addrs = addrs
+        .into_iter()
+        .filter(|addr| bind_addr.is_unspecified() || addr.is_ipv4() == bind_addr.is_ipv4())
+        .collect::<Vec<_>>();
+
+    let Some(first) = addrs.first() else {
+        return addrs;
+    };
+
+    let prefer_ipv6 = first.is_ipv6();
+    let (preferred, other): (Vec<IpAddr>, Vec<IpAddr>) = addrs
+        .iter()
+        .partition(|addr| addr.is_ipv6() == prefer_ipv6);
+
+    let mut sorted = Vec::with_capacity(addrs.len());
+    let mut preferred = preferred.into_iter();
+    let mut other = other.into_iter();
+    loop {
+        match (preferred.next(), other.next()) {
+            (None, None) => break,
+            (first, second) => {
+                sorted.extend(first);
+                sorted.extend(second);
+            }
+        }
+    }
+    sorted
+}
+
+/// Local address to bind to when connecting to `remote`.
+///
+/// An unspecified bind address is replaced by the unspecified address of the
+/// family of `remote`, so IPv6 destinations stay reachable.
+///
+/// # Arguments
+///
+/// * `bind_addr` - The local address the client binds to.
+/// * `remote` - The address to connect to.
+///
+/// # Returns
+///
+/// * `IpAddr` - The local address to bind to.
+///
+pub fn bind_addr_for(bind_addr: &IpAddr, remote: &IpAddr) -> IpAddr {
+    if !bind_addr.is_unspecified() {
+        return *bind_addr;
+    }
+
+    match remote {
+        IpAddr::V4(_) => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+        IpAddr::V6(_) => IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+    }
+}
+
+/// Race connection attempts to a list of addresses.
+///
+/// A new attempt is started every `attempt_delay`, or right away when an
+/// attempt fails. The first successful attempt is returned and the others
+/// are dropped.
+///
+/// # Arguments
+///
+/// * `addrs` - The addresses, in the order they should be tried.
+/// * `attempt_delay` - The delay between two attempts.
+/// * `connect` - Function connecting to a single address.
+///
+/// # Returns
+///
+/// * `Result<T>` - The first connection established, or the last error.
+///
+pub async fn race<T, F, Fut>(addrs: &[IpAddr], attempt_delay: Duration, connect: F) -> Result<T>
+where
+    F: Fn(IpAddr) -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let mut pending = addrs.iter();
+    let mut attempts = FuturesUnordered::new();
+    let mut last_error = None;
+
+    loop {
+        if attempts.is_empty() {
+            match pending.next() {
+                Some(addr) => attempts.push(connect(*addr)),
+                None => {
+                    return Err(last_error.unwrap_or_else(|| {
+                        DeboaError::Request(RequestError::Send {
+                            message: "No IP addresses to connect to".to_string(),
+                            method: None,
+                            url: None,
+                            source: None,
+                        })
+                    }));
+                }
+            }
+        }
+
+        let result = if pending.len() > 0 {
+            match select(attempts.next(), Delay::new(attempt_delay)).await {
+                Either::Left((result, _)) => result,
+                Either::Right(_) => None,
+            }
+        } else {
+            attempts
+                .next()
+                .await
+        };
+
+        match result {
+            Some(Ok(connection)) => return Ok(connection),
+            Some(Err(error)) => {
+                last_error = Some(error);
+                if let Some(addr) = pending.next() {
+                    attempts.push(connect(*addr));
+                }
+            }
+            // The attempt delay elapsed, start the next attempt.
+            None => {
+                if let Some(addr) = pending.next() {
+                    attempts.push(connect(*addr));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,762 @@
+//! Cookie storage for the Deboa HTTP client.
+//!
+//! This module provides the [`CookieStore`] trait, attached to a client with
+//! [`ClientBuilder::cookie_store`](crate::ClientBuilder::cookie_store), and
+//! [`CookieJar`], an in-memory store following RFC 6265.
+//!
+//! # Features
+//!
+//! - Cookies set by every response are captured, including redirect hops
+//! - Matching cookies are sent with the following requests
+//! - Domain, path, secure and expiry matching
+//! - Cookies scoped to a public suffix, like `com` or `co.uk`, are rejected
+//! - Jars can be saved and loaded in the Netscape `cookies.txt` format used by
+//!   curl and wget, or as JSON with the `cookie-json` feature
+//!
+//! # Examples
+//!
+//! ## Keeping cookies between requests
+//!
+//! ```ignore
+//! use deboa::jar::CookieJar;
+//! use deboa_tokio::Client;
+//! use std::sync::Arc;
+//!
+//! let jar = Arc::new(CookieJar::default());
+//! let client = Client::builder()
+//!     .cookie_store(jar.clone())
+//!     .build();
+//! ```
+//!
+//! ## Carrying a session across restarts
+//!
+//! ```ignore
+//! use deboa::jar::CookieJar;
+//! use std::{fs::File, io::BufReader};
+//!
+//! let jar = CookieJar::new();
+//! jar.load_netscape(BufReader::new(File::open("cookies.txt")?))?;
+//! // ...
+//! jar.save_netscape(File::create("cookies.txt")?)?;
+//! ```
+
+use crate::{
+    cookie::DeboaCookie,
+    errors::{DeboaError, ErrorSource, IoError},
+    request::DeboaRequest,
+    Result,
+};
+use cookie::{time::OffsetDateTime, Cookie, Expiration};
+use http::{header, HeaderMap, HeaderValue, Uri};
+use indexmap::IndexMap;
+use std::{
+    fmt::Debug,
+    io::{BufRead, Write},
+    net::IpAddr,
+    sync::Mutex,
+};
+
+#[cfg(feature = "cookie-json")]
+use {
+    crate::errors::ContentError,
+    serde::{Deserialize, Serialize},
+    std::io::Read,
+};
+
+/// Header line of Netscape cookie files.
+const NETSCAPE_HEADER: &str = "# Netscape HTTP Cookie File";
+
+/// Prefix curl adds to the domain of http only cookies in Netscape cookie
+/// files.
+const HTTP_ONLY_PREFIX: &str = "#HttpOnly_";
+
+/// A trait defining the interface for cookie stores.
+///
+/// Implement this trait to keep cookies in a custom storage.
+///
+/// # Thread Safety
+///
+/// Stores are shared by the requests of a client and should be safe to access
+/// concurrently.
+pub trait CookieStore {
+    /// Store the cookies set by a response.
+    ///
+    /// # Arguments
+    ///
+    /// * `uri` - The URI of the request the response is for.
+    /// * `cookies` - The cookies, with `Max-Age` converted to an expiry date.
+    ///
+    fn set_cookies(&self, uri: &Uri, cookies: Vec<DeboaCookie>);
+
+    /// Get the cookies to send with a request.
+    ///
+    /// # Arguments
+    ///
+    /// * `uri` - The URI of the request.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<DeboaCookie>` - The cookies, in the order they should be sent.
+    ///
+    fn cookies(&self, uri: &Uri) -> Vec<DeboaCookie>;
+}
+
+/// A cookie kept by a [`CookieJar`].
+#[derive(Clone, Debug)]
+struct StoredCookie {
+    cookie: DeboaCookie,
+    host_only: bool,
+    expires: Option<OffsetDateTime>,
+}
+
+impl StoredCookie {
+    fn new(cookie: DeboaCookie, host_only: bool) -> Self {
+        let expires = match cookie.expires() {
+            Some(Expiration::DateTime(expires)) => Some(expires),
+            _ => None,
+        };
+        Self { cookie, host_only, expires }
+    }
+
+    fn key(&self) -> (String, String, String) {
+        let cookie = &self.cookie;
+        (
+            cookie
+                .name()
+                .to_string(),
+            cookie
+                .domain()
+                .cloned()
+                .unwrap_or_default(),
+            cookie
+                .path()
+                .cloned()
+                .unwrap_or_else(|| "/".to_string()),
+        )
+    }
+
+    /// Session cookies and expired ones are not persisted.
+    fn is_persistent(&self, now: OffsetDateTime) -> bool {
+        self.expires
+            .is_some_and(|expires| expires > now)
+    }
+
+    fn is_expired(&self, now: OffsetDateTime) -> bool {
+        self.expires
+            .is_some_and(|expires| expires <= now)
+    }
+
+    fn matches(&self, host: &str, path: &str, secure: bool) -> bool {
+        let domain = self
+            .cookie
+            .domain()
+            .map(String::as_str)
+            .unwrap_or_default();
+        let cookie_path = self
+            .cookie
+            .path()
+            .map(String::as_str)
+            .unwrap_or("/");
+        let host_matches =
+            if self.host_only { host == domain } else { domain_matches(host, domain) };
+        host_matches
+            && path_matches(path, cookie_path)
+            && (secure
+                || !self
+                    .cookie
+                    .secure()
+                    .unwrap_or_default())
+    }
+}
+
+/// In-memory cookie store following RFC 6265.
+///
+/// Cookies are identified by their name, domain and path, a new cookie
+/// replaces the one with the same identity and expired cookies are dropped.
+#[derive(Default)]
+pub struct CookieJar {
+    cookies: Mutex<IndexMap<(String, String, String), StoredCookie>>,
+}
+
+impl Debug for CookieJar {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CookieJar")
+            .field("cookies", &self.len())
+            .finish()
+    }
+}
+
+impl CookieJar {
+    /// Create an empty cookie jar.
+    ///
+    /// # Returns
+    ///
+    /// * `CookieJar` - The cookie jar.
+    ///
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of cookies kept, expired ones included until they
+    /// are dropped.
+    pub fn len(&self) -> usize {
+        self.cookies
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .len()
+    }
+
+    /// Check if the jar is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Remove all the cookies.
+    pub fn clear(&self) {
+        self.cookies
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+    }
+
+    /// Load cookies from a Netscape `cookies.txt` file, as written by curl
+    /// and wget. Loaded cookies replace the ones with the same name, domain
+    /// and path, expired ones are skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - The reader of the file.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<()>` - An error if the file can't be read or has an invalid line.
+    ///
+    pub fn load_netscape<R: BufRead>(&self, reader: R) -> Result<()> {
+        let mut cookies = Vec::new();
+        for (index, line) in reader
+            .lines()
+            .enumerate()
+        {
+            let line = line.map_err(|e| {
+                DeboaError::Io(IoError::Content {
+                    message: e.to_string(),
+                    source: Some(ErrorSource::new(e)),
+                })
+            })?;
+            let (line, http_only) = match line.strip_prefix(HTTP_ONLY_PREFIX) {
+                Some(line) => (line, true),
+                None => (line.as_str(), false),
+            };
+            if line
+                .trim()
+                .is_empty()
+                || line.starts_with('#')
+            {
+                continue;
+            }
+
+            let cookie = parse_netscape_line(line, http_only).ok_or_else(|| {
+                DeboaError::Cookie { message: format!("invalid cookie file line {}", index + 1) }
+            })?;
+            cookies.push(cookie);
+        }
+        self.insert(cookies);
+        Ok(())
+    }
+
+    /// Save cookies to a Netscape `cookies.txt` file, readable by curl and
+    /// wget. Session cookies and expired ones are left out.
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - The writer of the file.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<()>` - An error if the file can't be written.
+    ///
+    pub fn save_netscape<W: Write>(&self, mut writer: W) -> Result<()> {
+        let mut contents = format!("{NETSCAPE_HEADER}\n\n");
+        for StoredCookie { cookie, host_only, expires } in self.persistent() {
+            let prefix = if cookie
+                .http_only()
+                .unwrap_or_default()
+            {
+                HTTP_ONLY_PREFIX
+            } else {
+                ""
+            };
+            let domain = cookie
+                .domain()
+                .map(String::as_str)
+                .unwrap_or_default();
+            let domain = if host_only { domain.to_string() } else { format!(".{domain}") };
+            contents.push_str(&format!(
+                "{prefix}{domain}\t{}\t{}\t{}\t{}\t{}\t{}\n",
+                netscape_flag(!host_only),
+                cookie
+                    .path()
+                    .map(String::as_str)
+                    .unwrap_or("/"),
+                netscape_flag(
+                    cookie
+                        .secure()
+                        .unwrap_or_default()
+                ),
+                expires
+                    .map(OffsetDateTime::unix_timestamp)
+                    .unwrap_or_default(),
+                cookie.name(),
+                cookie.value(),
+            ));
+        }
+
+        writer
+            .write_all(contents.as_bytes())
+            .and_then(|_| writer.flush())
+            .map_err(|e| DeboaError::Io(IoError::File { message: e.to_string() }))
+    }
+
+    /// Load cookies from JSON, as written by [`CookieJar::save_json`].
+    /// Loaded cookies replace the ones with the same name, domain and path,
+    /// expired ones are skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - The reader of the JSON.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<()>` - An error if the JSON can't be read or is invalid.
+    ///
+    #[cfg(feature = "cookie-json")]
+    pub fn load_json<R: Read>(&self, reader: R) -> Result<()> {
+        let cookies = serde_json::from_reader::<_, Vec<PersistedCookie>>(reader).map_err(|e| {
+            DeboaError::Content(ContentError::Deserialization { message: e.to_string() })
+        })?;
+        let cookies = cookies
+            .into_iter()
+            .map(StoredCookie::try_from)
+            .collect::<Result<Vec<_>>>()?;
+        self.insert(cookies);
+        Ok(())
+    }
+
+    /// Save cookies as JSON. Session cookies and expired ones are left out.
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - The writer of the JSON.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<()>` - An error if the JSON can't be written.
+    ///
+    #[cfg(feature = "cookie-json")]
+    pub fn save_json<W: Write>(&self, writer: W) -> Result<()> {
+        let cookies = self
+            .persistent()
+            .iter()
+            .map(PersistedCookie::from)
+            .collect::<Vec<_>>();
+        serde_json::to_writer_pretty(writer, &cookies).map_err(|e| {
+            DeboaError::Content(ContentError::Serialization { message: e.to_string() })
+        })
+    }
+
+    /// Store cookies as they are, skipping the expired ones.
+    fn insert(&self, cookies: Vec<StoredCookie>) {
+        let now = OffsetDateTime::now_utc();
+        let mut stored = self
+            .cookies
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        for cookie in cookies {
+            if !cookie.is_expired(now) {
+                stored.insert(cookie.key(), cookie);
+            }
+        }
+    }
+
+    /// Returns the cookies to persist.
+    fn persistent(&self) -> Vec<StoredCookie> {
+        let now = OffsetDateTime::now_utc();
+        self.cookies
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .values()
+            .filter(|cookie| cookie.is_persistent(now))
+            .cloned()
+            .collect()
+    }
+}
+
+/// A cookie as saved by [`CookieJar::save_json`], `expires` is a unix
+/// timestamp.
+#[cfg(feature = "cookie-json")]
+#[derive(Serialize, Deserialize)]
+struct PersistedCookie {
+    name: String,
+    value: String,
+    domain: String,
+    path: String,
+    host_only: bool,
+    secure: bool,
+    http_only: bool,
+    expires: i64,
+}
+
+#[cfg(feature = "cookie-json")]
+impl From<&StoredCookie> for PersistedCookie {
+    fn from(stored: &StoredCookie) -> Self {
+        let (name, domain, path) = stored.key();
+        let cookie = &stored.cookie;
+        Self {
+            name,
+            value: cookie
+                .value()
+                .to_string(),
+            domain,
+            path,
+            host_only: stored.host_only,
+            secure: cookie
+                .secure()
+                .unwrap_or_default(),
+            http_only: cookie
+                .http_only()
+                .unwrap_or_default(),
+            expires: stored
+                .expires
+                .map(OffsetDateTime::unix_timestamp)
+                .unwrap_or_default(),
+        }
+    }
+}
+
+#[cfg(feature = "cookie-json")]
+impl TryFrom<PersistedCookie> for StoredCookie {
+    type Error = DeboaError;
+
+    fn try_from(persisted: PersistedCookie) -> Result<Self> {
+        persisted_cookie(
+            &persisted.name,
+            &persisted.value,
+            &persisted.domain,
+            &persisted.path,
+            persisted.host_only,
+            persisted.secure,
+            persisted.http_only,
+            persisted.expires,
+        )
+        .ok_or_else(|| DeboaError::Cookie {
+            message: format!("invalid persisted cookie {}", persisted.name),
+        })
+    }
+}
+
+impl CookieStore for CookieJar {
+    fn set_cookies(&self, uri: &Uri, cookies: Vec<DeboaCookie>) {
+        let Some(host) = request_host(uri) else {
+            return;
+        };
+        let secure = is_secure(uri);
+        let now = OffsetDateTime::now_utc();
+
+        let mut stored = self
+            .cookies
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        for mut cookie in cookies {
+            // Only secure origins may set secure cookies.
+            if !secure
+                && cookie
+                    .secure()
+                    .unwrap_or_default()
+            {
+                continue;
+            }
+
+            let domain = cookie
+                .domain()
+                .map(|domain| {
+                    domain
+                        .trim_start_matches('.')
+                        .to_ascii_lowercase()
+                })
+                .filter(|domain| !domain.is_empty());
+            let (domain, host_only) = match domain {
+                Some(domain) if is_public_suffix(&domain) => {
+                    if domain != host {
+                        continue;
+                    }
+                    (domain, true)
+                }
+                Some(domain) => {
+                    if !domain_matches(&host, &domain) {
+                        continue;
+                    }
+                    (domain, false)
+                }
+                None => (host.clone(), true),
+            };
+
+            let path = match cookie.path() {
+                Some(path) if path.starts_with('/') => path.clone(),
+                _ => default_path(uri.path()),
+            };
+
+            cookie
+                .set_domain(&domain)
+                .set_path(&path);
+
+            let cookie = StoredCookie::new(cookie, host_only);
+            let key = cookie.key();
+            if cookie.is_expired(now) {
+                stored.shift_remove(&key);
+                continue;
+            }
+
+            // Insecure origins may not overwrite secure cookies.
+            let overwrites_secure = stored
+                .get(&key)
+                .is_some_and(|existing| {
+                    existing
+                        .cookie
+                        .secure()
+                        .unwrap_or_default()
+                });
+            if !secure && overwrites_secure {
+                continue;
+            }
+
+            stored.insert(key, cookie);
+        }
+    }
+
+    fn cookies(&self, uri: &Uri) -> Vec<DeboaCookie> {
+        let Some(host) = request_host(uri) else {
+            return Vec::new();
+        };
+        let secure = is_secure(uri);
+        let path = match uri.path() {
+            "" => "/",
+            path => path,
+        };
+        let now = OffsetDateTime::now_utc();
+
+        let mut stored = self
+            .cookies
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        stored.retain(|_, cookie| !cookie.is_expired(now));
+
+        let mut cookies = stored
+            .values()
+            .filter(|cookie| cookie.matches(&host, path, secure))
+            .map(|cookie| {
+                cookie
+                    .cookie
+                    .clone()
+            })
+            .collect::<Vec<_>>();
+        // Longer paths first, the sort is stable so older cookies come first
+        // among cookies with paths of the same length.
+        cookies.sort_by_key(|cookie| {
+            std::cmp::Reverse(
+                cookie
+                    .path()
+                    .map(String::len)
+                    .unwrap_or_default(),
+            )
+        });
+        cookies
+    }
+}
+
+/// Parse the `Set-Cookie` headers of a response, converting `Max-Age` to an
+/// expiry date. Invalid cookies are skipped.
+pub(crate) fn parse_set_cookies(headers: &HeaderMap) -> Vec<DeboaCookie> {
+    let now = OffsetDateTime::now_utc();
+    headers
+        .get_all(header::SET_COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .filter_map(|value| Cookie::parse(value.to_string()).ok())
+        .map(|cookie| {
+            // Max-Age has precedence over Expires.
+            let max_age = cookie.max_age();
+            let mut cookie = DeboaCookie::from(cookie);
+            if let Some(max_age) = max_age {
+                let expires = if max_age.is_positive() {
+                    now.checked_add(max_age)
+                        .unwrap_or(OffsetDateTime::UNIX_EPOCH)
+                } else {
+                    OffsetDateTime::UNIX_EPOCH
+                };
+                cookie.set_expires(Expiration::DateTime(expires));
+            }
+            cookie
+        })
+        .collect()
+}
+
+/// Add the cookies of a store to a request, after the ones set on the request
+/// itself, which take precedence.
+pub(crate) fn add_cookies<S: CookieStore + ?Sized>(store: &S, request: &mut DeboaRequest) {
+    let cookies = store.cookies(request.uri());
+    if cookies.is_empty() {
+        return;
+    }
+
+    let existing = request
+        .headers()
+        .get(header::COOKIE)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+    let names = existing
+        .iter()
+        .flat_map(|value| value.split(';'))
+        .filter_map(|pair| {
+            pair.split_once('=')
+                .map(|(name, _)| name.trim())
+        })
+        .collect::<Vec<_>>();
+
+    let mut pairs = existing
+        .iter()
+        .map(String::to_string)
+        .collect::<Vec<_>>();
+    pairs.extend(
+        cookies
+            .iter()
+            .filter(|cookie| !names.contains(&cookie.name()))
+            .map(DeboaCookie::to_string),
+    );
+
+    if let Ok(value) = HeaderValue::from_str(&pairs.join("; ")) {
+        request
+            .headers_mut()
+            .insert(header::COOKIE, value);
+    }
+}
+
+/// Parse a line of a Netscape cookie file: domain, whether subdomains are
+/// included, path, secure, expiry as a unix timestamp, 0 for session cookies,
+/// name and value, separated by tabs.
+fn parse_netscape_line(line: &str, http_only: bool) -> Option<StoredCookie> {
+    let mut fields = line.splitn(7, '\t');
+    let domain = fields.next()?;
+    let include_subdomains = parse_netscape_flag(fields.next()?)?;
+    let path = fields.next()?;
+    let secure = parse_netscape_flag(fields.next()?)?;
+    let expires = fields
+        .next()?
+        .trim()
+        .parse::<i64>()
+        .ok()?;
+    let name = fields.next()?;
+    let value = fields.next()?;
+    persisted_cookie(name, value, domain, path, !include_subdomains, secure, http_only, expires)
+}
+
+/// Build a stored cookie from the fields of a persisted one.
+#[allow(clippy::too_many_arguments)]
+fn persisted_cookie(
+    name: &str,
+    value: &str,
+    domain: &str,
+    path: &str,
+    host_only: bool,
+    secure: bool,
+    http_only: bool,
+    expires: i64,
+) -> Option<StoredCookie> {
+    let domain = domain
+        .trim_start_matches('.')
+        .to_ascii_lowercase();
+    if name.is_empty() || domain.is_empty() {
+        return None;
+    }
+
+    let mut cookie = DeboaCookie::new(name, value);
+    cookie
+        .set_domain(&domain)
+        .set_path(if path.starts_with('/') { path } else { "/" })
+        .set_secure(secure)
+        .set_http_only(http_only);
+    if expires > 0 {
+        let expires = OffsetDateTime::from_unix_timestamp(expires).ok()?;
+        cookie.set_expires(Expiration::DateTime(expires));
+    }
+    Some(StoredCookie::new(cookie, host_only))
+}
+
+fn parse_netscape_flag(flag: &str) -> Option<bool> {
+    match flag.trim() {
+        flag if flag.eq_ignore_ascii_case("TRUE") => Some(true),
+        flag if flag.eq_ignore_ascii_case("FALSE") => Some(false),
+        _ => None,
+    }
+}
+
+fn netscape_flag(flag: bool) -> &'static str {
+    if flag {
+        "TRUE"
+    } else {
+        "FALSE"
+    }
+}
+
+fn request_host(uri: &Uri) -> Option<String> {
+    let host = uri.host()?;
+    let host = host
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .trim_end_matches('.');
+    Some(host.to_ascii_lowercase())
+}
+
+fn is_secure(uri: &Uri) -> bool {
+    matches!(uri.scheme_str(), Some("https") | Some("wss"))
+}
+
+fn is_public_suffix(domain: &str) -> bool {
+    psl::suffix(domain.as_bytes()).is_some_and(|suffix| {
+        suffix.is_known()
+            && suffix
+                .trim()
+                .as_bytes()
+                == domain.as_bytes()
+    })
+}
+
+/// Domain matching, as described in RFC 6265, section 5.1.3.
+fn domain_matches(host: &str, domain: &str) -> bool {
+    if host == domain {
+        return true;
+    }
+
+    host.parse::<IpAddr>()
+        .is_err()
+        && host
+            .strip_suffix(domain)
+            .is_some_and(|prefix| prefix.ends_with('.'))
+}
+
+/// Path matching, as described in RFC 6265, section 5.1.4.
+fn path_matches(path: &str, cookie_path: &str) -> bool {
+    if path == cookie_path {
+        return true;
+    }
+
+    path.strip_prefix(cookie_path)
+        .is_some_and(|rest| cookie_path.ends_with('/') || rest.starts_with('/'))
+}
+
+/// Default path of a cookie, as described in RFC 6265, section 5.1.4.
+fn default_path(path: &str) -> String {
+    match path.rfind('/') {
+        Some(0) | None => "/".to_string(),
+        Some(index) => path[..index].to_string(),
+    }
+}
@@ -1,35 +1,60 @@
 #![doc = include_str!("../README.md")]
 #![deny(missing_docs)]
 use crate::{
+    alt_svc::AltSvcCache,
     cert::{Certificate, Identity},
+    compression::{Encoding, DEFAULT_COMPRESSION_THRESHOLD},
     conn::{ConnectionConfig, HttpConnectionDispatcher, HttpConnectionPool},
     dns::DnsResolver,
-    errors::{DeboaError, RequestError},
+    errors::{ConnectionError, DeboaError, RequestError},
+    jar::CookieStore,
+    pool::Pooled,
+    protocol::{CongestionController, ProtocolPolicy, ProtocolSettings},
+    proxy::{Proxy, ProxyScheme},
+    redirect::{RedirectPolicy, Redirector},
     request::{DeboaRequest, DeboaRequestBuilder, IntoRequest},
     response::DeboaResponse,
+    retry::{is_idempotent, RetryPolicy},
 };
+use ::url::Url;
 use async_lock::RwLock;
+use http::{header, HeaderMap, HeaderValue};
 use log::info;
 use std::{
     future::Future,
     net::{IpAddr, Ipv4Addr},
     ops::Shl,
+    path::{Path, PathBuf},
+    sync::Arc,
     time::Duration,
 };
 use tackle::{Chain, Hook, HookFn};
 
+pub mod alt_svc;
 pub mod cache;
 pub mod cert;
+pub mod compression;
 pub mod conn;
 pub mod cookie;
 pub mod dns;
 pub mod errors;
 pub mod form;
+pub mod h2c;
+pub mod happy_eyeballs;
+pub mod jar;
+pub mod options;
+pub mod pool;
+pub mod protocol;
+pub mod proxy;
+pub mod redirect;
 pub mod request;
 pub mod response;
+pub mod retry;
 pub mod serde;
+pub mod status;
 #[cfg(test)]
 pub mod tests;
+mod time;
 pub mod url;
 
 /// Type alias for Result<T, DeboaError>
@@ -92,6 +117,8 @@ pub trait HttpClient {
 /// Client parameters struct
 pub struct ClientBuilder<I, C, P, R> {
     inner: InnerClient<I, C, P, R>,
+    base_url: Option<Url>,
+    default_headers: HeaderMap,
 }
 
 impl<I, C, P, R> ClientBuilder<I, C, P, R>
@@ -154,15 +181,309 @@ where
         self
     }
 
+    /// Add a proxy, proxies are tried in the order they were added
+    pub fn proxy(mut self, proxy: Proxy) -> Self {
+        self.inner
+            .proxies
+            .push(proxy);
+        self
+    }
+
+    /// Add the proxies set by the HTTP_PROXY, HTTPS_PROXY and NO_PROXY
+    /// environment variables
+    pub fn proxy_from_env(mut self) -> Self {
+        self.inner
+            .proxies
+            .extend(Proxy::from_env());
+        self
+    }
+
+    /// Set unix socket, every connection is opened to it whatever the host
+    /// of the request, proxies are not used
+    pub fn unix_socket<T: Into<PathBuf>>(mut self, path: T) -> Self {
+        self.inner
+            .unix_socket = Some(path.into());
+        self
+    }
+
+    /// Set whether every request without TLS is sent in HTTP/2, assuming the
+    /// server speaks it (h2c with prior knowledge), whatever its version
+    pub fn http2_prior_knowledge(mut self, http2_prior_knowledge: bool) -> Self {
+        self.inner
+            .http2_prior_knowledge = http2_prior_knowledge;
+        self
+    }
+
+    /// Set whether new HTTP/1.1 connections without TLS ask the server to
+    /// switch to HTTP/2, with an `Upgrade: h2c` request, staying in HTTP/1.1
+    /// if it does not
+    pub fn h2c_upgrade(mut self, h2c_upgrade: bool) -> Self {
+        self.inner
+            .h2c_upgrade = h2c_upgrade;
+        self
+    }
+
+    /// Set the policy choosing the protocol of new connections, and the ALPN
+    /// protocols offered over TLS
+    pub fn protocol_policy(mut self, protocol_policy: ProtocolPolicy) -> Self {
+        self.inner
+            .protocol_policy = protocol_policy;
+        self
+    }
+
+    /// Set whether HTTP/3 alternatives advertised with `Alt-Svc` are used for
+    /// later requests to an origin, falling back to TCP when they fail. Only
+    /// applies to runtimes built with the `http3` feature
+    pub fn alt_svc(mut self, enabled: bool) -> Self {
+        self.inner.alt_svc = enabled.then(AltSvcCache::default);
+        self
+    }
+
+    /// Set the initial flow control window of HTTP/2 streams, in bytes
+    pub fn http2_initial_stream_window_size(mut self, size: u32) -> Self {
+        self.inner
+            .protocol_settings
+            .http2
+            .initial_stream_window_size = Some(size);
+        self
+    }
+
+    /// Set the initial flow control window of HTTP/2 connections, in bytes
+    pub fn http2_initial_connection_window_size(mut self, size: u32) -> Self {
+        self.inner
+            .protocol_settings
+            .http2
+            .initial_connection_window_size = Some(size);
+        self
+    }
+
+    /// Set whether the HTTP/2 flow control windows adapt to the bandwidth-delay
+    /// product of the connection, taking over the initial window sizes
+    pub fn http2_adaptive_window(mut self, enabled: bool) -> Self {
+        self.inner
+            .protocol_settings
+            .http2
+            .adaptive_window = enabled;
+        self
+    }
+
+    /// Set the largest HTTP/2 frame accepted from the server, in bytes
+    pub fn http2_max_frame_size(mut self, size: u32) -> Self {
+        self.inner
+            .protocol_settings
+            .http2
+            .max_frame_size = Some(size);
+        self
+    }
+
+    /// Set the maximum number of HTTP/2 streams the server may open
+    pub fn http2_max_concurrent_streams(mut self, max: u32) -> Self {
+        self.inner
+            .protocol_settings
+            .http2
+            .max_concurrent_streams = Some(max);
+        self
+    }
+
+    /// Set the largest HTTP/2 header list accepted from the server, in bytes
+    pub fn http2_max_header_list_size(mut self, size: u32) -> Self {
+        self.inner
+            .protocol_settings
+            .http2
+            .max_header_list_size = Some(size);
+        self
+    }
+
+    /// Set the interval of HTTP/2 keep-alive PINGs, sent while requests are in
+    /// flight
+    pub fn http2_keep_alive_interval(mut self, interval: Duration) -> Self {
+        self.inner
+            .protocol_settings
+            .http2
+            .keep_alive_interval = Some(interval);
+        self
+    }
+
+    /// Set how long an HTTP/2 keep-alive PING waits for its answer before the
+    /// connection is closed
+    pub fn http2_keep_alive_timeout(mut self, timeout: Duration) -> Self {
+        self.inner
+            .protocol_settings
+            .http2
+            .keep_alive_timeout = Some(timeout);
+        self
+    }
+
+    /// Set whether HTTP/1 header names are sent in title case
+    pub fn http1_title_case_headers(mut self, enabled: bool) -> Self {
+        self.inner
+            .protocol_settings
+            .http1
+            .title_case_headers = enabled;
+        self
+    }
+
+    /// Set whether the case of HTTP/1 response header names is kept
+    pub fn http1_preserve_header_case(mut self, enabled: bool) -> Self {
+        self.inner
+            .protocol_settings
+            .http1
+            .preserve_header_case = enabled;
+        self
+    }
+
+    /// Set the maximum number of headers of an HTTP/1 response
+    pub fn http1_max_headers(mut self, max: usize) -> Self {
+        self.inner
+            .protocol_settings
+            .http1
+            .max_headers = Some(max);
+        self
+    }
+
+    /// Set the size of the HTTP/1 read buffer, in bytes
+    pub fn http1_read_buffer_size(mut self, size: usize) -> Self {
+        self.inner
+            .protocol_settings
+            .http1
+            .read_buffer_size = Some(size);
+        self
+    }
+
+    /// Set how long an HTTP/3 connection stays open without traffic
+    pub fn http3_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.inner
+            .protocol_settings
+            .http3
+            .idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Set the interval of the QUIC keep-alive packets of HTTP/3 connections, keeping
+    /// them open while idle
+    pub fn http3_keep_alive_interval(mut self, interval: Duration) -> Self {
+        self.inner
+            .protocol_settings
+            .http3
+            .keep_alive_interval = Some(interval);
+        self
+    }
+
+    /// Set the maximum number of bidirectional QUIC streams the server may open
+    pub fn http3_max_concurrent_bidi_streams(mut self, max: u32) -> Self {
+        self.inner
+            .protocol_settings
+            .http3
+            .max_concurrent_bidi_streams = Some(max);
+        self
+    }
+
+    /// Set the maximum number of unidirectional QUIC streams the server may open
+    pub fn http3_max_concurrent_uni_streams(mut self, max: u32) -> Self {
+        self.inner
+            .protocol_settings
+            .http3
+            .max_concurrent_uni_streams = Some(max);
+        self
+    }
+
+    /// Set the round-trip time HTTP/3 connections assume before measuring it
+    pub fn http3_initial_rtt(mut self, rtt: Duration) -> Self {
+        self.inner
+            .protocol_settings
+            .http3
+            .initial_rtt = Some(rtt);
+        self
+    }
+
+    /// Set the congestion controller of HTTP/3 connections
+    pub fn http3_congestion_controller(mut self, controller: CongestionController) -> Self {
+        self.inner
+            .protocol_settings
+            .http3
+            .congestion_controller = controller;
+        self
+    }
+
+    /// Set whether resumed HTTP/3 connections send their first requests as 0-RTT
+    /// data, before the handshake completes. 0-RTT data can be replayed by an
+    /// attacker, only enable it for requests safe to repeat
+    pub fn http3_zero_rtt(mut self, enabled: bool) -> Self {
+        self.inner
+            .protocol_settings
+            .http3
+            .zero_rtt = enabled;
+        self
+    }
+
+    /// Compress request bodies above the compression threshold, bodies of
+    /// unknown size are sent as they are
+    pub fn request_compression(mut self, encoding: Encoding) -> Self {
+        self.inner
+            .request_compression = Some(encoding);
+        self
+    }
+
+    /// Set the smallest request body compressed, in bytes
+    pub fn request_compression_threshold(mut self, threshold: u64) -> Self {
+        self.inner
+            .request_compression_threshold = threshold;
+        self
+    }
+
+    /// Set cookie store, cookies set by responses are kept in it and sent
+    /// with the matching requests
+    pub fn cookie_store<S: CookieStore + Send + Sync + 'static>(mut self, store: Arc<S>) -> Self {
+        self.inner
+            .cookie_store = Some(store);
+        self
+    }
+
+    /// Set redirect policy
+    pub fn redirect_policy(mut self, redirect_policy: RedirectPolicy) -> Self {
+        self.inner
+            .redirect_policy = redirect_policy;
+        self
+    }
+
+    /// Set retry policy
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.inner
+            .retry_policy = Some(retry_policy);
+        self
+    }
+
+    /// Set base url, paths given to the client resolve against it
+    pub fn base_url(mut self, base_url: Url) -> Self {
+        self.base_url = Some(base_url);
+        self
+    }
+
+    /// Set default headers, sent with every request that does not set them
+    pub fn default_headers(mut self, default_headers: HeaderMap) -> Self {
+        self.default_headers
+            .extend(default_headers);
+        self
+    }
+
+    /// Set user agent, sent with every request that does not set one
+    pub fn user_agent(mut self, user_agent: HeaderValue) -> Self {
+        self.default_headers
+            .insert(header::USER_AGENT, user_agent);
+        self
+    }
+
     /// Build the client
     pub fn build(self) -> Client<InnerClient<I, C, P, R>> {
-        Client::from_inner(self.inner)
+        Client { hook: self.inner, base_url: self.base_url, default_headers: self.default_headers }
     }
 }
 
 /// Client struct
 pub struct Client<H> {
     hook: H,
+    base_url: Option<Url>,
+    default_headers: HeaderMap,
 }
 
 impl<H> Client<H>
@@ -171,7 +492,7 @@ where
 {
     /// Initialize a client from hook
     pub fn new(inner: H) -> Self {
-        Self { hook: inner }
+        Self { hook: inner, base_url: None, default_headers: HeaderMap::new() }
     }
 
     /// Add a new hook to the chain
@@ -180,7 +501,11 @@ where
         C: Chain<H, DeboaError, DeboaRequest, DeboaResponse, Hook = Hout>,
         Hout: Hook<DeboaRequest, DeboaResponse, Result = Result<DeboaResponse>> + 'static,
     {
-        Client::new(chain.chain(self.hook))
+        Client {
+            hook: chain.chain(self.hook),
+            base_url: self.base_url,
+            default_headers: self.default_headers,
+        }
     }
 
     /// Add a hook from a function
@@ -189,14 +514,41 @@ where
         F: Fn(DeboaRequest, std::rc::Rc<H>) -> Fut + Send,
         Fut: Future<Output = Result<DeboaResponse>>,
     {
-        Client::from_fn(HookFn::new(self.hook, f))
+        Client {
+            hook: HookFn::new(self.hook, f),
+            base_url: self.base_url,
+            default_headers: self.default_headers,
+        }
+    }
+}
+
+impl<H> Client<H> {
+    /// Allow get base url at any time.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<&Url>` - The base url.
+    ///
+    pub fn base_url(&self) -> Option<&Url> {
+        self.base_url
+            .as_ref()
+    }
+
+    /// Allow get default headers at any time.
+    ///
+    /// # Returns
+    ///
+    /// * `&HeaderMap` - The default headers.
+    ///
+    pub fn default_headers(&self) -> &HeaderMap {
+        &self.default_headers
     }
 }
 
 impl<F, H> Client<HookFn<F, H>> {
     /// Initialize a client from hook
     pub fn from_fn(inner: HookFn<F, H>) -> Self {
-        Self { hook: inner }
+        Self { hook: inner, base_url: None, default_headers: HeaderMap::new() }
     }
 }
 
@@ -209,19 +561,24 @@ where
 {
     /// Create a client from inner client
     pub fn from_inner(inner: InnerClient<I, C, P, R>) -> Self {
-        Self { hook: inner }
+        Self { hook: inner, base_url: None, default_headers: HeaderMap::new() }
     }
 
     /// Returns a new builer
     pub fn builder() -> ClientBuilder<I, C, P, R> {
-        ClientBuilder { inner: InnerClient::<I, C, P, R>::default() }
+        ClientBuilder {
+            inner: InnerClient::<I, C, P, R>::default(),
+            base_url: None,
+            default_headers: HeaderMap::new(),
+        }
     }
 }
 
 ///
 /// Extension trait for Client to enable the `<<` operator for URL construction.
 /// This allows for a more ergonomic way to create requests using the `<<` operator.
-/// The operator creates a GET request with the provided URL.
+/// The operator creates a GET request with the provided URL, a path resolves
+/// against the base url of the client.
 ///
 /// # Examples
 ///
@@ -246,7 +603,13 @@ impl<H> Shl<&str> for &Client<H> {
     type Output = DeboaRequestBuilder;
 
     fn shl(self, other: &str) -> Self::Output {
-        DeboaRequest::get(other).expect("Invalid URL!")
+        match &self.base_url {
+            Some(base_url) if url::is_relative(other) => {
+                DeboaRequest::get(url::resolve(base_url, other).expect("Invalid URL!"))
+                    .expect("Invalid URL!")
+            }
+            _ => DeboaRequest::get(other).expect("Invalid URL!"),
+        }
     }
 }
 
@@ -255,7 +618,7 @@ where
     H: Hook<DeboaRequest, DeboaResponse> + Default,
 {
     fn default() -> Self {
-        Self { hook: H::default() }
+        Self { hook: H::default(), base_url: None, default_headers: HeaderMap::new() }
     }
 }
 
@@ -267,8 +630,34 @@ where
     where
         Req: IntoRequest,
     {
+        let mut request = request.into_request_from(
+            self.base_url
+                .as_ref(),
+        )?;
+
+        // Headers set on the request win over the client defaults.
+        for name in self
+            .default_headers
+            .keys()
+        {
+            if request
+                .headers()
+                .contains_key(name)
+            {
+                continue;
+            }
+            for value in self
+                .default_headers
+                .get_all(name)
+            {
+                request
+                    .headers_mut()
+                    .append(name.clone(), value.clone());
+            }
+        }
+
         self.hook
-            .call(request.into_request()?)
+            .call(request)
             .await
     }
 }
@@ -327,6 +716,18 @@ pub struct InnerClient<I, C, P, R> {
     pool: RwLock<P>,
     dns_resolver: R,
     bind_addr: IpAddr,
+    proxies: Vec<Proxy>,
+    unix_socket: Option<PathBuf>,
+    http2_prior_knowledge: bool,
+    h2c_upgrade: bool,
+    protocol_policy: ProtocolPolicy,
+    protocol_settings: ProtocolSettings,
+    alt_svc: Option<AltSvcCache>,
+    request_compression: Option<Encoding>,
+    request_compression_threshold: u64,
+    cookie_store: Option<Arc<dyn CookieStore + Send + Sync>>,
+    redirect_policy: RedirectPolicy,
+    retry_policy: Option<RetryPolicy>,
 }
 
 impl<I, C, P, R> InnerClient<I, C, P, R> {
@@ -415,6 +816,141 @@ impl<I, C, P, R> InnerClient<I, C, P, R> {
     pub fn identity(&self) -> &Option<I> {
         &self.identity
     }
+
+    /// Allow get proxies at any time.
+    ///
+    /// # Returns
+    ///
+    /// * `&[Proxy]` - The proxies.
+    ///
+    #[inline]
+    pub fn proxies(&self) -> &[Proxy] {
+        &self.proxies
+    }
+
+    /// Allow get unix socket at any time.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<&Path>` - The unix socket.
+    ///
+    #[inline]
+    pub fn unix_socket(&self) -> Option<&Path> {
+        self.unix_socket
+            .as_deref()
+    }
+
+    /// Allow get whether HTTP/2 is used without TLS at any time.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - `true` if HTTP/2 is used with prior knowledge.
+    ///
+    #[inline]
+    pub fn http2_prior_knowledge(&self) -> bool {
+        self.http2_prior_knowledge
+    }
+
+    /// Allow get whether cleartext connections are upgraded to HTTP/2 at any time.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - `true` if HTTP/1.1 connections ask to switch to HTTP/2.
+    ///
+    #[inline]
+    pub fn h2c_upgrade(&self) -> bool {
+        self.h2c_upgrade
+    }
+
+    /// Allow get protocol policy at any time.
+    ///
+    /// # Returns
+    ///
+    /// * `ProtocolPolicy` - The policy choosing the protocol of new connections.
+    ///
+    #[inline]
+    pub fn protocol_policy(&self) -> ProtocolPolicy {
+        self.protocol_policy
+    }
+
+    /// Allow get protocol settings at any time.
+    ///
+    /// # Returns
+    ///
+    /// * `&ProtocolSettings` - The settings of the HTTP/1 and HTTP/2 connections.
+    ///
+    #[inline]
+    pub fn protocol_settings(&self) -> &ProtocolSettings {
+        &self.protocol_settings
+    }
+
+    /// Allow get alternative services cache at any time.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<&AltSvcCache>` - The HTTP/3 alternatives advertised by the origins, if enabled.
+    ///
+    #[inline]
+    pub fn alt_svc(&self) -> Option<&AltSvcCache> {
+        self.alt_svc
+            .as_ref()
+    }
+
+    /// Allow get request compression at any time.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<Encoding>` - The coding of request bodies.
+    ///
+    #[inline]
+    pub fn request_compression(&self) -> Option<Encoding> {
+        self.request_compression
+    }
+
+    /// Allow get request compression threshold at any time.
+    ///
+    /// # Returns
+    ///
+    /// * `u64` - The smallest request body compressed.
+    ///
+    #[inline]
+    pub fn request_compression_threshold(&self) -> u64 {
+        self.request_compression_threshold
+    }
+
+    /// Allow get cookie store at any time.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<&Arc<dyn CookieStore + Send + Sync>>` - The cookie store.
+    ///
+    #[inline]
+    pub fn cookie_store(&self) -> Option<&Arc<dyn CookieStore + Send + Sync>> {
+        self.cookie_store
+            .as_ref()
+    }
+
+    /// Allow get redirect policy at any time.
+    ///
+    /// # Returns
+    ///
+    /// * `&RedirectPolicy` - The redirect policy.
+    ///
+    #[inline]
+    pub fn redirect_policy(&self) -> &RedirectPolicy {
+        &self.redirect_policy
+    }
+
+    /// Allow get retry policy at any time.
+    ///
+    /// # Returns
+    ///
+    /// * `&Option<RetryPolicy>` - The retry policy.
+    ///
+    #[inline]
+    pub fn retry_policy(&self) -> &Option<RetryPolicy> {
+        &self.retry_policy
+    }
 }
 
 impl<I, C, P, R> Default for InnerClient<I, C, P, R>
@@ -434,6 +970,18 @@ where
             skip_cert_verification: false,
             pool: RwLock::new(P::default()),
             dns_resolver: R::default(),
+            proxies: Vec::new(),
+            unix_socket: None,
+            http2_prior_knowledge: false,
+            h2c_upgrade: false,
+            protocol_policy: ProtocolPolicy::default(),
+            protocol_settings: ProtocolSettings::default(),
+            alt_svc: Some(AltSvcCache::default()),
+            request_compression: None,
+            request_compression_threshold: DEFAULT_COMPRESSION_THRESHOLD,
+            cookie_store: None,
+            redirect_policy: RedirectPolicy::default(),
+            retry_policy: None,
         }
     }
 }
@@ -448,7 +996,95 @@ where
     type Result = Result<DeboaResponse>;
     type Error = DeboaError;
 
-    async fn call(&self, request: DeboaRequest) -> Result<DeboaResponse> {
+    async fn call(&self, mut request: DeboaRequest) -> Result<DeboaResponse> {
+        let deadline = options::start(&mut request);
+        let method = request
+            .method()
+            .clone();
+        let uri = request
+            .uri()
+            .clone();
+        let (retry_policy, read_timeout) = match request.options() {
+            Some(options) => (
+                options
+                    .retry_policy
+                    .clone()
+                    .or_else(|| {
+                        self.retry_policy
+                            .clone()
+                    }),
+                options.read_timeout,
+            ),
+            None => (
+                self.retry_policy
+                    .clone(),
+                None,
+            ),
+        };
+
+        let response = options::with_deadline(deadline, &method, &uri, async {
+            match &retry_policy {
+                Some(retry_policy) => {
+                    retry_policy
+                        .execute(request, |request| self.follow(request))
+                        .await
+                }
+                None => {
+                    self.follow(request)
+                        .await
+                }
+            }
+        })
+        .await?;
+
+        Ok(options::limit_body(response, deadline, read_timeout))
+    }
+}
+
+impl<I, C, P, R> InnerClient<I, C, P, R>
+where
+    I: Identity + Send + Clone,
+    C: Certificate + Send + Clone,
+    P: HttpConnectionPool<Identity = I, Certificate = C> + Send,
+    R: DnsResolver + Send,
+{
+    /// Send a request, following redirects according to the redirect policy.
+    async fn follow(&self, request: DeboaRequest) -> Result<DeboaResponse> {
+        let redirect_policy = request
+            .options()
+            .and_then(|options| {
+                options
+                    .redirect_policy
+                    .clone()
+            })
+            .unwrap_or_else(|| {
+                self.redirect_policy
+                    .clone()
+            });
+        if let RedirectPolicy::None = redirect_policy {
+            return self
+                .send(request)
+                .await;
+        }
+
+        let (mut redirector, mut request) = Redirector::new(&redirect_policy, request)?;
+        loop {
+            let response = self
+                .send(request)
+                .await?;
+
+            match redirector.next(&response)? {
+                Some(next) => {
+                    info!("Following redirect ({}) to {}", response.status(), next.uri());
+                    request = next;
+                }
+                None => return Ok(redirector.finish(response)),
+            }
+        }
+    }
+
+    /// Send a single request, without following redirects.
+    async fn send(&self, mut request: DeboaRequest) -> Result<DeboaResponse> {
         info!("Building request: {} {}", request.method(), request.uri());
 
         let uri = request
@@ -458,12 +1094,26 @@ where
         let Some(scheme) = uri.scheme_str() else {
             return Err(DeboaError::Request(RequestError::Send {
                 message: "Missing scheme".to_string(),
+                method: Some(
+                    request
+                        .method()
+                        .clone(),
+                ),
+                url: Some(Box::new(uri.clone())),
+                source: None,
             }));
         };
 
         let Some(host) = uri.host() else {
             return Err(DeboaError::Request(RequestError::Send {
                 message: "Missing host".to_string(),
+                method: Some(
+                    request
+                        .method()
+                        .clone(),
+                ),
+                url: Some(Box::new(uri.clone())),
+                source: None,
             }));
         };
 
@@ -477,11 +1127,116 @@ where
                 }
             });
 
+        // Connections to a Unix socket never go through a proxy.
+        let proxy = match self.unix_socket {
+            Some(_) => None,
+            None => self
+                .proxies
+                .iter()
+                .find(|proxy| proxy.intercepts(scheme, host)),
+        };
+
+        // HTTP/3 asked for by the request is spoken over QUIC only, it is
+        // never sent over TCP instead.
+        if request.version() == http::Version::HTTP_3 {
+            let reason = if !P::HTTP3 {
+                Some("the runtime is built without the http3 feature")
+            } else if scheme != "https" {
+                Some("it requires https")
+            } else if proxy.is_some()
+                || self
+                    .unix_socket
+                    .is_some()
+            {
+                Some("it does not run through a proxy or a Unix socket")
+            } else if !self
+                .protocol_policy
+                .allows_http3()
+            {
+                Some("the protocol policy does not allow it")
+            } else {
+                None
+            };
+            if let Some(reason) = reason {
+                return Err(DeboaError::Request(RequestError::Send {
+                    message: format!("HTTP/3 is not available: {}", reason),
+                    method: Some(
+                        request
+                            .method()
+                            .clone(),
+                    ),
+                    url: Some(Box::new(uri.clone())),
+                    source: None,
+                }));
+            }
+        }
+
+        // Plain HTTP requests are forwarded by HTTP proxies, in absolute-form.
+        if let Some(proxy) =
+            proxy.filter(|proxy| proxy.scheme() == ProxyScheme::Http && !proxy.is_tunnel(scheme))
+        {
+            if let Some(authorization) = proxy.authorization() {
+                request
+                    .headers_mut()
+                    .insert(http::header::PROXY_AUTHORIZATION, authorization);
+            }
+        }
+
+        if let Some(store) = &self.cookie_store {
+            jar::add_cookies(store.as_ref(), &mut request);
+        }
+
+        if let Some(encoding) = self.request_compression {
+            request = compression::compress_request(
+                request,
+                encoding,
+                self.request_compression_threshold,
+            )?;
+        }
+
+        let decompress = compression::prepare(&mut request);
+        let has_body = request.method() != http::Method::HEAD;
+
+        let options = request
+            .options()
+            .cloned()
+            .unwrap_or_default();
+        let mut connection_timeout = options
+            .connect_timeout
+            .unwrap_or(self.connection_timeout);
+        let mut request_timeout = options
+            .response_timeout
+            .unwrap_or(self.request_timeout);
+        // Nothing is allowed to wait past the deadline.
+        if let Some(deadline) = options.deadline {
+            connection_timeout = connection_timeout.min(options::remaining(deadline));
+            request_timeout = request_timeout.min(options::remaining(deadline));
+        }
+
+        // Alternatives are only used by runtimes speaking HTTP/3, for the
+        // origin itself when the policy allows it.
+        let alt_svc = self
+            .alt_svc
+            .as_ref()
+            .filter(|_| P::HTTP3 && scheme == "https");
+        let alternative = alt_svc
+            .filter(|_| {
+                proxy.is_none()
+                    && self
+                        .unix_socket
+                        .is_none()
+                    && self
+                        .protocol_policy
+                        .allows_http3()
+            })
+            .and_then(|alt_svc| alt_svc.alternative(host, port));
+
         let config = ConnectionConfig::builder()
             .scheme(scheme)
             .host(host)
             .port(port)
             .protocol_version(request.version())
+            .connection_timeout(connection_timeout)
             .identity(
                 self.identity
                     .as_ref(),
@@ -492,22 +1247,88 @@ where
             )
             .skip_cert_verification(self.skip_cert_verification)
             .client_bind_addr(self.bind_addr)
+            .proxy(proxy)
+            .unix_socket(
+                self.unix_socket
+                    .as_deref(),
+            )
+            .http2_prior_knowledge(self.http2_prior_knowledge)
+            .h2c_upgrade(self.h2c_upgrade)
+            .protocol_policy(self.protocol_policy)
+            .protocol_settings(self.protocol_settings)
+            .alternative(alternative)
+            .alt_svc(alt_svc)
             .build();
 
-        let mut pool = self
-            .pool
-            .write()
-            .await;
+        // Keep the pool locked only while checking the connection out,
+        // the exchange itself runs without holding the lock.
+        let Pooled { mut connection, permit, reused } =
+            pool::acquire(&self.pool, &config, &self.dns_resolver).await?;
+
+        // Streams of a shared connection do not count against the limit.
+        let permit = if connection.is_multiplexed() { None } else { permit };
+
+        // A pooled connection may have been closed by the server while idle,
+        // keep a copy of idempotent requests to send them again.
+        let replay = if reused && is_idempotent(request.method()) {
+            request
+                .try_clone()
+                .ok()
+        } else {
+            None
+        };
+
+        let method = request
+            .method()
+            .clone();
+        let result = connection
+            .send_request(request.body(), request_timeout)
+            .await
+            .map_err(|e| e.with_request(&method, &uri));
 
-        let conn = pool
-            .create_connection(&config, &self.dns_resolver)
-            .await?;
+        let response = match (result, replay) {
+            (
+                Err(DeboaError::Connection(ConnectionError::Closed { message, .. })),
+                Some(replay),
+            ) => {
+                info!(
+                    "Pooled connection to {}:{} was closed ({}), reconnecting",
+                    host, port, message
+                );
+                let transport = self
+                    .pool
+                    .read()
+                    .await
+                    .transport();
+                connection = P::connect(&transport, &config, &self.dns_resolver).await?;
+                connection
+                    .send_request(replay.body(), request_timeout)
+                    .await
+                    .map_err(|e| e.with_request(&method, &uri))?
+            }
+            (result, _) => result?,
+        };
 
-        let request = request.body();
+        if let Some(store) = &self.cookie_store {
+            store.set_cookies(&uri, jar::parse_set_cookies(response.headers()));
+        }
 
-        let response = conn
-            .send_request(request, self.request_timeout)
-            .await?;
+        if let Some(alt_svc) = alt_svc {
+            alt_svc.update(host, port, response.headers());
+        }
+
+        self.pool
+            .write()
+            .await
+            .checkin(&config, connection);
+
+        // The slot is released once the connection is back in the pool,
+        // so the next waiting request finds it there.
+        drop(permit);
+
+        if decompress && has_body {
+            return Ok(compression::decompress(response));
+        }
 
         Ok(response)
     }
@@ -0,0 +1,260 @@
+//! Per request settings for the Deboa HTTP client.
+//!
+//! Client settings apply to every request sent. This module provides
+//! [`RequestOptions`], kept in the request extensions, to override some of
+//! them for a single request: timeouts, a total deadline, and the retry and
+//! redirect policies.
+//!
+//! # Timeouts
+//!
+//! - `timeout` and `deadline` bound the whole exchange, across retries and
+//!   redirects, including reading the response body
+//! - `connect_timeout` bounds opening a new connection
+//! - `response_timeout` bounds waiting for the response head
+//! - `read_timeout` bounds the time between two chunks of the response body
+//!
+//! A request past its deadline fails with a
+//! [`RequestError::Timeout`](crate::errors::RequestError::Timeout), a body read
+//! past it fails with an `io::Error` of kind `TimedOut`. Both are told apart
+//! by [`DeboaError::is_timeout`].
+//!
+//! # Examples
+//!
+//! ```ignore
+//! use deboa::{redirect::RedirectPolicy, request::get};
+//! use std::time::Duration;
+//!
+//! let response = get("https://api.example.com/report")?
+//!     .timeout(Duration::from_secs(60))
+//!     .read_timeout(Duration::from_secs(5))
+//!     .redirect_policy(RedirectPolicy::none())
+//!     .send_with(&client)
+//!     .await?;
+//! ```
+
+use crate::{
+    errors::{DeboaError, RequestError},
+    redirect::RedirectPolicy,
+    request::DeboaRequest,
+    response::DeboaResponse,
+    retry::RetryPolicy,
+    Result,
+};
+use bytes::Bytes;
+use futures::{
+    future::{select, Either},
+    FutureExt, Stream,
+};
+use futures_timer::Delay;
+use http::{Method, Uri};
+use http_body::{Body, Frame};
+use hyper_body_utils::HttpBody;
+use std::{
+    future::Future,
+    io,
+    pin::Pin,
+    sync::Mutex,
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+/// Settings of a single request, overriding the client ones.
+///
+/// Unset fields fall back to the client settings.
+#[derive(Debug, Clone, Default)]
+pub struct RequestOptions {
+    /// Time allowed for the whole exchange, counted from when it is sent.
+    pub timeout: Option<Duration>,
+    /// Instant the whole exchange must be done by.
+    pub deadline: Option<Instant>,
+    /// Time allowed to open a new connection.
+    pub connect_timeout: Option<Duration>,
+    /// Time allowed to wait for the response head.
+    pub response_timeout: Option<Duration>,
+    /// Time allowed between two chunks of the response body.
+    pub read_timeout: Option<Duration>,
+    /// Retry policy of the request.
+    pub retry_policy: Option<RetryPolicy>,
+    /// Redirect policy of the request.
+    pub redirect_policy: Option<RedirectPolicy>,
+}
+
+/// Turn the timeout of a request into a deadline, starting the clock.
+///
+/// The deadline is kept in the request options, so retries and redirects
+/// of the request share it.
+///
+/// # Arguments
+///
+/// * `request` - The request about to be sent.
+///
+/// # Returns
+///
+/// * `Option<Instant>` - The deadline of the request, if any.
+///
+pub(crate) fn start(request: &mut DeboaRequest) -> Option<Instant> {
+    let options = request
+        .extensions_mut()
+        .get_mut::<RequestOptions>()?;
+    if let Some(timeout) = options
+        .timeout
+        .take()
+    {
+        let deadline = Instant::now() + timeout;
+        options.deadline = Some(
+            options
+                .deadline
+                .map_or(deadline, |current| current.min(deadline)),
+        );
+    }
+    options.deadline
+}
+
+/// Time left before a deadline.
+pub(crate) fn remaining(deadline: Instant) -> Duration {
+    deadline.saturating_duration_since(Instant::now())
+}
+
+/// Run a future, failing with a timeout once the deadline is past.
+///
+/// # Arguments
+///
+/// * `deadline` - The deadline, `None` to run the future as is.
+/// * `method` - The method of the request.
+/// * `url` - The url of the request.
+/// * `future` - The future to run.
+///
+/// # Returns
+///
+/// * `Result<T>` - The output of the future, or a timeout error.
+///
+pub(crate) fn with_deadline<F, T>(
+    deadline: Option<Instant>,
+    method: &Method,
+    url: &Uri,
+    future: F,
+) -> impl Future<Output = Result<T>>
+where
+    F: Future<Output = Result<T>>,
+{
+    // Built from combinators rather than an async block, so the exchange, a
+    // large future, is not kept twice on the stack.
+    let Some(deadline) = deadline else {
+        return Either::Left(future);
+    };
+
+    let method = method.clone();
+    let url = url.clone();
+    Either::Right(select(Box::pin(future), Delay::new(remaining(deadline))).map(move |output| {
+        match output {
+            Either::Left((result, _)) => result,
+            Either::Right(_) => Err(DeboaError::Request(RequestError::Timeout {
+                message: "Request deadline exceeded".to_string(),
+                method: Some(method),
+                url: Some(Box::new(url)),
+                source: None,
+            })),
+        }
+    }))
+}
+
+/// Enforce the deadline and read timeout of a request on the response body.
+///
+/// # Arguments
+///
+/// * `response` - The response.
+/// * `deadline` - The deadline of the request.
+/// * `read_timeout` - The time allowed between two chunks.
+///
+/// # Returns
+///
+/// * `DeboaResponse` - The response, with a body failing once a limit is hit.
+///
+pub(crate) fn limit_body(
+    response: DeboaResponse,
+    deadline: Option<Instant>,
+    read_timeout: Option<Duration>,
+) -> DeboaResponse {
+    if deadline.is_none() && read_timeout.is_none() {
+        return response;
+    }
+
+    let redirects = response
+        .redirects()
+        .to_vec();
+    let (parts, body) = response.into_parts();
+    let body = HttpBody::from_generic_stream(TimedBody {
+        body: Mutex::new(body),
+        deadline: deadline.map(|deadline| Delay::new(remaining(deadline))),
+        read_timeout,
+        idle: read_timeout.map(Delay::new),
+        done: false,
+    });
+    let mut response = DeboaResponse::new(http::Response::from_parts(parts, body));
+    response.set_redirects(redirects);
+    response
+}
+
+/// Response body failing once the deadline is past or no data comes in time.
+struct TimedBody {
+    // Only accessed through `get_mut`, the lock makes the body `Sync`.
+    body: Mutex<HttpBody>,
+    deadline: Option<Delay>,
+    read_timeout: Option<Duration>,
+    idle: Option<Delay>,
+    done: bool,
+}
+
+impl Stream for TimedBody {
+    type Item = io::Result<Frame<Bytes>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if this.done {
+            return Poll::Ready(None);
+        }
+
+        // The deadline is checked first, a body always ready would outrun it.
+        let message = if this
+            .deadline
+            .as_mut()
+            .is_some_and(|deadline| {
+                deadline
+                    .poll_unpin(cx)
+                    .is_ready()
+            }) {
+            "Request deadline exceeded while reading the body".to_string()
+        } else {
+            let body = this
+                .body
+                .get_mut()
+                .unwrap_or_else(|e| e.into_inner());
+            if let Poll::Ready(frame) = Pin::new(body).poll_frame(cx) {
+                if let (Some(idle), Some(read_timeout)) = (&mut this.idle, this.read_timeout) {
+                    idle.reset(read_timeout);
+                }
+                return Poll::Ready(frame);
+            }
+
+            if !this
+                .idle
+                .as_mut()
+                .is_some_and(|idle| {
+                    idle.poll_unpin(cx)
+                        .is_ready()
+                })
+            {
+                return Poll::Pending;
+            }
+
+            format!(
+                "No body data received for {:?}",
+                this.read_timeout
+                    .unwrap_or_default()
+            )
+        };
+
+        this.done = true;
+        Poll::Ready(Some(Err(io::Error::new(io::ErrorKind::TimedOut, message))))
+    }
+}
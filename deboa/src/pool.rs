@@ -0,0 +1,909 @@
+//! Connection cache module
+//!
+//! This module provides the bookkeeping shared by the runtime connection pools.
+//! Runtime crates own the connections, this module decides which one is
+//! handed out for a request.
+//!
+//! # Features
+//!
+//! - Multiplexed connections (HTTP/2, HTTP/3) are shared by cloning their sender
+//! - HTTP/1.1 connections are checked out exclusively
+//! - At most `max_connections_per_host` HTTP/1.1 connections are used per origin,
+//!   further requests wait in a first-in first-out queue
+//! - Idle connections are closed after `keep_alive_duration`, and at most
+//!   `max_idle_connections` are kept, least recently used first out
+use crate::{
+    cert::{Certificate, Identity},
+    conn::{ConnectionConfig, HttpConnectionDispatcher, HttpConnectionPool},
+    dns::DnsResolver,
+    errors::{ConnectionError, DeboaError},
+    proxy::Proxy,
+    time::timeout,
+    Result,
+};
+use async_lock::RwLock;
+use hashbrown::HashMap;
+use http::Version;
+use std::{
+    collections::VecDeque,
+    fmt::Display,
+    future::Future,
+    hash::{DefaultHasher, Hash, Hasher},
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::{Arc, Mutex, MutexGuard},
+    task::{Context, Poll, Waker},
+    time::{Duration, Instant},
+};
+
+/// Default maximum number of HTTP/1.1 connections per origin.
+pub const DEFAULT_MAX_CONNECTIONS_PER_HOST: usize = 10;
+
+/// Default maximum number of idle connections kept by a pool.
+pub const DEFAULT_MAX_IDLE_CONNECTIONS: usize = 5;
+
+/// Default time an idle connection is kept open.
+pub const DEFAULT_KEEP_ALIVE_DURATION: Duration = Duration::from_secs(300);
+
+/// Default time a request waits for a pooled connection.
+pub const DEFAULT_CHECKOUT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Key identifying the connections that can be shared by requests.
+///
+/// Two requests only share a connection if they agree on the origin, the
+/// requested protocol version, the TLS settings, the proxy and the Unix
+/// socket.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PoolKey {
+    scheme: String,
+    host: String,
+    port: u16,
+    version: Version,
+    identity: Option<u64>,
+    certificate: Option<u64>,
+    skip_cert_verification: bool,
+    proxy: Option<Proxy>,
+    unix_socket: Option<PathBuf>,
+}
+
+impl PoolKey {
+    /// Allow create a new pool key, without TLS settings.
+    ///
+    /// # Arguments
+    ///
+    /// * `scheme` - The scheme.
+    /// * `host` - The host.
+    /// * `port` - The port.
+    /// * `version` - The requested protocol version.
+    ///
+    /// # Returns
+    ///
+    /// * `PoolKey` - The new key.
+    ///
+    pub fn new(scheme: &str, host: &str, port: u16, version: Version) -> Self {
+        Self {
+            scheme: scheme.to_ascii_lowercase(),
+            host: host.to_ascii_lowercase(),
+            port,
+            version,
+            identity: None,
+            certificate: None,
+            skip_cert_verification: false,
+            proxy: None,
+            unix_socket: None,
+        }
+    }
+
+    /// Get the scheme of the key.
+    pub fn scheme(&self) -> &str {
+        &self.scheme
+    }
+
+    /// Get the host of the key.
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+
+    /// Get the port of the key.
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// Get the requested protocol version of the key.
+    pub fn version(&self) -> Version {
+        self.version
+    }
+
+    /// Get the Unix socket of the key.
+    pub fn unix_socket(&self) -> Option<&Path> {
+        self.unix_socket
+            .as_deref()
+    }
+}
+
+impl<I, C> From<&ConnectionConfig<'_, I, C>> for PoolKey
+where
+    I: Identity,
+    C: Certificate,
+{
+    fn from(config: &ConnectionConfig<'_, I, C>) -> Self {
+        // Requests to an origin with an HTTP/3 alternative share its QUIC
+        // connection, not the TCP one the alternative was learnt from.
+        let version = match config.alternative() {
+            Some(_) => Version::HTTP_3,
+            None => *config.protocol_version(),
+        };
+        let mut key = PoolKey::new(config.scheme(), config.host(), config.port(), version);
+        key.identity = config
+            .identity()
+            .map(|identity| {
+                fingerprint(&[
+                    identity.cert(),
+                    identity
+                        .key()
+                        .as_deref()
+                        .unwrap_or_default(),
+                ])
+            });
+        key.certificate = config
+            .certificate()
+            .map(|certificate| fingerprint(&[certificate.as_bytes()]));
+        key.skip_cert_verification = config.skip_cert_verification();
+        key.proxy = config
+            .proxy()
+            .cloned();
+        key.unix_socket = config
+            .unix_socket()
+            .map(Path::to_path_buf);
+        key
+    }
+}
+
+impl From<&PoolKey> for PoolKey {
+    fn from(key: &PoolKey) -> Self {
+        key.clone()
+    }
+}
+
+impl Display for PoolKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}://{}:{} ({:?})", self.scheme, self.host, self.port, self.version)?;
+        if let Some(proxy) = &self.proxy {
+            write!(f, " via {}", proxy)?;
+        }
+        if let Some(unix_socket) = &self.unix_socket {
+            write!(f, " via {}", unix_socket.display())?;
+        }
+        Ok(())
+    }
+}
+
+/// Hash of certificate material, so keys stay small.
+fn fingerprint(parts: &[&[u8]]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    parts
+        .iter()
+        .for_each(|part| part.hash(&mut hasher));
+    hasher.finish()
+}
+
+/// Result of a pool checkout.
+pub enum Checkout<C> {
+    /// A connection ready to send a request.
+    Ready(C),
+    /// An HTTP/1.1 connection still reading a previous response.
+    Busy(C),
+    /// No connection is available, a new one must be opened.
+    Connect,
+    /// A slot of the per host limiter must be acquired first.
+    Limited(Arc<HostLimiter>),
+}
+
+struct Waiter {
+    ticket: u64,
+    waker: Option<Waker>,
+    granted: bool,
+}
+
+struct LimiterState {
+    available: usize,
+    next_ticket: u64,
+    queue: VecDeque<Waiter>,
+}
+
+/// Fair limiter for the HTTP/1.1 connections of an origin.
+///
+/// Slots are handed to waiting requests in the order they arrived.
+pub struct HostLimiter {
+    max: usize,
+    state: Mutex<LimiterState>,
+}
+
+impl HostLimiter {
+    /// Allow create a new limiter.
+    ///
+    /// # Arguments
+    ///
+    /// * `max` - The number of slots.
+    ///
+    /// # Returns
+    ///
+    /// * `HostLimiter` - The new limiter.
+    ///
+    pub fn new(max: usize) -> Self {
+        let max = max.max(1);
+        Self {
+            max,
+            state: Mutex::new(LimiterState {
+                available: max,
+                next_ticket: 0,
+                queue: VecDeque::new(),
+            }),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, LimiterState> {
+        self.state
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Returns the number of requests waiting for a slot.
+    ///
+    /// # Returns
+    ///
+    /// * `usize` - The number of waiting requests.
+    ///
+    pub fn waiting(&self) -> usize {
+        self.lock()
+            .queue
+            .iter()
+            .filter(|waiter| !waiter.granted)
+            .count()
+    }
+
+    /// Returns the number of slots in use.
+    ///
+    /// # Returns
+    ///
+    /// * `usize` - The number of slots in use.
+    ///
+    pub fn in_use(&self) -> usize {
+        self.max
+            - self
+                .lock()
+                .available
+    }
+
+    /// Acquire a slot, waiting in line if none is available.
+    ///
+    /// # Returns
+    ///
+    /// * `Acquire` - A future resolving to the permit.
+    ///
+    pub fn acquire(self: &Arc<Self>) -> Acquire {
+        Acquire { limiter: Arc::clone(self), ticket: None }
+    }
+
+    fn release(&self) {
+        let mut state = self.lock();
+        match state
+            .queue
+            .iter_mut()
+            .find(|waiter| !waiter.granted)
+        {
+            Some(waiter) => {
+                waiter.granted = true;
+                if let Some(waker) = waiter.waker.take() {
+                    waker.wake();
+                }
+            }
+            None => state.available += 1,
+        }
+    }
+}
+
+/// Future returned by [`HostLimiter::acquire`].
+pub struct Acquire {
+    limiter: Arc<HostLimiter>,
+    ticket: Option<u64>,
+}
+
+impl Future for Acquire {
+    type Output = HostPermit;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let limiter = Arc::clone(&self.limiter);
+        let mut state = limiter.lock();
+
+        let Some(ticket) = self.ticket else {
+            if state.available > 0
+                && state
+                    .queue
+                    .is_empty()
+            {
+                state.available -= 1;
+                return Poll::Ready(HostPermit { limiter: Arc::clone(&limiter) });
+            }
+
+            let ticket = state.next_ticket;
+            state.next_ticket += 1;
+            state
+                .queue
+                .push_back(Waiter { ticket, waker: Some(cx.waker().clone()), granted: false });
+            self.ticket = Some(ticket);
+            return Poll::Pending;
+        };
+
+        let Some(index) = state
+            .queue
+            .iter()
+            .position(|waiter| waiter.ticket == ticket)
+        else {
+            return Poll::Pending;
+        };
+
+        if state.queue[index].granted {
+            state
+                .queue
+                .remove(index);
+            self.ticket = None;
+            return Poll::Ready(HostPermit { limiter: Arc::clone(&limiter) });
+        }
+
+        state.queue[index].waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+impl Drop for Acquire {
+    fn drop(&mut self) {
+        let Some(ticket) = self.ticket else {
+            return;
+        };
+
+        let granted = {
+            let mut state = self.limiter.lock();
+            match state
+                .queue
+                .iter()
+                .position(|waiter| waiter.ticket == ticket)
+            {
+                Some(index) => state
+                    .queue
+                    .remove(index)
+                    .is_some_and(|waiter| waiter.granted),
+                None => false,
+            }
+        };
+
+        // A slot handed to a request that gave up goes to the next one in line.
+        if granted {
+            self.limiter
+                .release();
+        }
+    }
+}
+
+/// Slot of a [`HostLimiter`], released when dropped.
+pub struct HostPermit {
+    limiter: Arc<HostLimiter>,
+}
+
+impl Drop for HostPermit {
+    fn drop(&mut self) {
+        self.limiter
+            .release();
+    }
+}
+
+/// Pooled connection and the time it was last used.
+struct Idle<C> {
+    connection: C,
+    since: Instant,
+}
+
+impl<C> Idle<C> {
+    fn new(connection: C, now: Instant) -> Self {
+        Self { connection, since: now }
+    }
+
+    /// Check if the connection was idle for too long or was closed.
+    #[inline]
+    fn is_stale(&self, now: Instant, keep_alive: Duration) -> bool
+    where
+        C: HttpConnectionDispatcher,
+    {
+        now.saturating_duration_since(self.since) >= keep_alive
+            || self
+                .connection
+                .is_closed()
+    }
+}
+
+/// Connections kept for a single origin.
+struct PoolEntry<C> {
+    multiplexed: Option<Idle<C>>,
+    idle: Vec<Idle<C>>,
+    limiter: Arc<HostLimiter>,
+}
+
+impl<C> PoolEntry<C> {
+    fn new(max_connections: usize) -> Self {
+        Self {
+            multiplexed: None,
+            idle: Vec::new(),
+            limiter: Arc::new(HostLimiter::new(max_connections)),
+        }
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        self.idle.len()
+            + self
+                .multiplexed
+                .is_some() as usize
+    }
+
+    fn evict_stale(&mut self, now: Instant, keep_alive: Duration)
+    where
+        C: HttpConnectionDispatcher,
+    {
+        if self
+            .multiplexed
+            .as_ref()
+            .is_some_and(|idle| idle.is_stale(now, keep_alive))
+        {
+            self.multiplexed = None;
+        }
+        self.idle
+            .retain(|idle| !idle.is_stale(now, keep_alive));
+    }
+
+    /// Time the least recently used connection was last used.
+    fn oldest(&self) -> Option<Instant> {
+        self.idle
+            .iter()
+            .chain(
+                self.multiplexed
+                    .as_ref(),
+            )
+            .map(|idle| idle.since)
+            .min()
+    }
+
+    fn evict_oldest(&mut self) {
+        let oldest = self
+            .idle
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, idle)| idle.since);
+
+        match (oldest, &self.multiplexed) {
+            (Some((_, idle)), Some(multiplexed)) if multiplexed.since < idle.since => {
+                self.multiplexed = None;
+            }
+            (Some((index, _)), _) => {
+                self.idle
+                    .remove(index);
+            }
+            (None, _) => self.multiplexed = None,
+        }
+    }
+
+    /// Check if the entry can be dropped from the cache.
+    #[inline]
+    fn is_unused(&self) -> bool {
+        self.len() == 0 && Arc::strong_count(&self.limiter) == 1
+    }
+}
+
+/// Cache of pooled connections, keyed by origin.
+///
+/// Connections idle for longer than `keep_alive_duration` are closed, and at
+/// most `max_idle_connections` are kept, the least recently used ones are
+/// closed first.
+///
+/// # Type Parameters
+///
+/// * `C` - The connection type.
+///
+pub struct ConnectionCache<C> {
+    entries: HashMap<PoolKey, PoolEntry<C>>,
+    max_connections_per_host: usize,
+    max_idle_connections: usize,
+    keep_alive_duration: Duration,
+    checkout_timeout: Duration,
+}
+
+impl<C> Default for ConnectionCache<C> {
+    fn default() -> Self {
+        Self {
+            entries: HashMap::new(),
+            max_connections_per_host: DEFAULT_MAX_CONNECTIONS_PER_HOST,
+            max_idle_connections: DEFAULT_MAX_IDLE_CONNECTIONS,
+            keep_alive_duration: DEFAULT_KEEP_ALIVE_DURATION,
+            checkout_timeout: DEFAULT_CHECKOUT_TIMEOUT,
+        }
+    }
+}
+
+impl<C> ConnectionCache<C>
+where
+    C: HttpConnectionDispatcher,
+{
+    /// Allow create a new connection cache.
+    ///
+    /// # Returns
+    ///
+    /// * `ConnectionCache<C>` - The new cache.
+    ///
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allow set max connections per host
+    ///
+    /// # Arguments
+    ///
+    /// * `max_connections_per_host` - The max HTTP/1.1 connections per origin.
+    ///
+    pub fn set_max_connections_per_host(&mut self, max_connections_per_host: usize) {
+        self.max_connections_per_host = max_connections_per_host;
+    }
+
+    /// Allow get max connections per host at any time.
+    ///
+    /// # Returns
+    ///
+    /// * `usize` - The max HTTP/1.1 connections per origin.
+    ///
+    pub fn max_connections_per_host(&self) -> usize {
+        self.max_connections_per_host
+    }
+
+    /// Allow set max idle connections
+    ///
+    /// # Arguments
+    ///
+    /// * `max_idle_connections` - The max idle connections, across all origins.
+    ///
+    pub fn set_max_idle_connections(&mut self, max_idle_connections: usize) {
+        self.max_idle_connections = max_idle_connections;
+        self.evict_lru();
+    }
+
+    /// Allow get max idle connections at any time.
+    ///
+    /// # Returns
+    ///
+    /// * `usize` - The max idle connections.
+    ///
+    pub fn max_idle_connections(&self) -> usize {
+        self.max_idle_connections
+    }
+
+    /// Allow set keep alive duration
+    ///
+    /// # Arguments
+    ///
+    /// * `keep_alive_duration` - How long an idle connection is kept.
+    ///
+    pub fn set_keep_alive_duration(&mut self, keep_alive_duration: Duration) {
+        self.keep_alive_duration = keep_alive_duration;
+        self.evict_expired();
+    }
+
+    /// Allow get keep alive duration at any time.
+    ///
+    /// # Returns
+    ///
+    /// * `Duration` - How long an idle connection is kept.
+    ///
+    pub fn keep_alive_duration(&self) -> Duration {
+        self.keep_alive_duration
+    }
+
+    /// Allow set checkout timeout
+    ///
+    /// # Arguments
+    ///
+    /// * `checkout_timeout` - How long a request waits for a connection.
+    ///
+    pub fn set_checkout_timeout(&mut self, checkout_timeout: Duration) {
+        self.checkout_timeout = checkout_timeout;
+    }
+
+    /// Allow get checkout timeout at any time.
+    ///
+    /// # Returns
+    ///
+    /// * `Duration` - How long a request waits for a connection.
+    ///
+    pub fn checkout_timeout(&self) -> Duration {
+        self.checkout_timeout
+    }
+
+    /// Returns the number of pooled connections.
+    ///
+    /// # Returns
+    ///
+    /// * `usize` - The number of connections.
+    ///
+    pub fn len(&self) -> usize {
+        self.entries
+            .values()
+            .map(PoolEntry::len)
+            .sum()
+    }
+
+    /// Check if the cache holds no connections.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - `true` if there are no connections.
+    ///
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of requests waiting for a connection.
+    ///
+    /// # Returns
+    ///
+    /// * `usize` - The number of waiting requests.
+    ///
+    pub fn waiting_count(&self) -> usize {
+        self.entries
+            .values()
+            .map(|entry| {
+                entry
+                    .limiter
+                    .waiting()
+            })
+            .sum()
+    }
+
+    /// Close the connections idle for longer than the keep alive duration,
+    /// and drop the ones closed by the peer.
+    ///
+    /// This runs on every checkin, runtimes may also call it periodically.
+    pub fn evict_expired(&mut self) {
+        let now = Instant::now();
+        let keep_alive = self.keep_alive_duration;
+        self.entries
+            .values_mut()
+            .for_each(|entry| entry.evict_stale(now, keep_alive));
+        self.entries
+            .retain(|_, entry| !entry.is_unused());
+    }
+
+    /// Close the least recently used connections above the idle limit.
+    fn evict_lru(&mut self) {
+        let mut len = self.len();
+        while len > self.max_idle_connections {
+            let Some(entry) = self
+                .entries
+                .values_mut()
+                .filter_map(|entry| {
+                    entry
+                        .oldest()
+                        .map(|oldest| (oldest, entry))
+                })
+                .min_by_key(|(oldest, _)| *oldest)
+                .map(|(_, entry)| entry)
+            else {
+                break;
+            };
+            entry.evict_oldest();
+            len -= 1;
+        }
+        self.entries
+            .retain(|_, entry| !entry.is_unused());
+    }
+
+    /// Check a connection out of the cache.
+    ///
+    /// HTTP/1.1 connections are only handed out to requests holding a
+    /// permit of the origin limiter, [`Checkout::Limited`] is returned otherwise.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The pool key.
+    /// * `permit` - The permit held by the request, if any.
+    ///
+    /// # Returns
+    ///
+    /// * `Checkout<C>` - The result of the checkout.
+    ///
+    pub fn checkout(&mut self, key: &PoolKey, permit: Option<&HostPermit>) -> Checkout<C> {
+        let now = Instant::now();
+        let keep_alive = self.keep_alive_duration;
+        let max_connections = self.max_connections_per_host;
+        let entry = self
+            .entries
+            .entry_ref(key)
+            .or_insert_with(|| PoolEntry::new(max_connections));
+
+        entry.evict_stale(now, keep_alive);
+
+        if let Some(multiplexed) = entry
+            .multiplexed
+            .as_mut()
+        {
+            if let Some(connection) = multiplexed
+                .connection
+                .try_clone()
+            {
+                multiplexed.since = now;
+                return Checkout::Ready(connection);
+            }
+        }
+
+        if permit.is_none() {
+            return Checkout::Limited(Arc::clone(&entry.limiter));
+        }
+
+        // Prefer the most recently used connection, so the others can expire.
+        if let Some(index) = entry
+            .idle
+            .iter()
+            .rposition(|idle| {
+                idle.connection
+                    .is_ready()
+            })
+        {
+            return Checkout::Ready(
+                entry
+                    .idle
+                    .remove(index)
+                    .connection,
+            );
+        }
+
+        // Every other permit holder uses at most one connection.
+        let in_use = entry
+            .limiter
+            .in_use()
+            .saturating_sub(1);
+        if entry
+            .idle
+            .is_empty()
+            || entry.idle.len() + in_use < entry.limiter.max
+        {
+            return Checkout::Connect;
+        }
+
+        Checkout::Busy(
+            entry
+                .idle
+                .remove(0)
+                .connection,
+        )
+    }
+
+    /// Return a connection to the cache.
+    ///
+    /// Closed connections are dropped. Expired connections are evicted, then the least recently used ones
+    /// if the cache holds more than `max_idle_connections`.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The pool key.
+    /// * `connection` - The connection.
+    ///
+    pub fn checkin(&mut self, key: &PoolKey, connection: C) {
+        let now = Instant::now();
+        let max_connections = self.max_connections_per_host;
+        let entry = self
+            .entries
+            .entry_ref(key)
+            .or_insert_with(|| PoolEntry::new(max_connections));
+
+        if connection.is_closed() {
+            // Nothing to reuse, the connection is dropped.
+        } else if connection.is_multiplexed() {
+            match entry
+                .multiplexed
+                .as_mut()
+            {
+                // Clones of a shared sender are simply dropped.
+                Some(multiplexed)
+                    if !multiplexed
+                        .connection
+                        .is_closed() =>
+                {
+                    multiplexed.since = now
+                }
+                _ => entry.multiplexed = Some(Idle::new(connection, now)),
+            }
+        } else {
+            entry
+                .idle
+                .push(Idle::new(connection, now));
+        }
+
+        self.evict_expired();
+        self.evict_lru();
+    }
+}
+
+/// Connection handed out by [`acquire`].
+pub(crate) struct Pooled<C> {
+    /// The connection.
+    pub(crate) connection: C,
+    /// The permit to release once the connection is checked back in.
+    pub(crate) permit: Option<HostPermit>,
+    /// Whether the connection was taken from the pool.
+    pub(crate) reused: bool,
+}
+
+/// Get a connection for a request, waiting for the per host limit if needed.
+///
+/// # Arguments
+///
+/// * `pool` - The connection pool.
+/// * `config` - The connection configuration.
+/// * `dns_resolver` - The DNS resolver to use.
+///
+/// # Returns
+///
+/// * `Result<Pooled<P::ConnectionDispather>>` - The connection.
+///
+pub(crate) async fn acquire<P, D>(
+    pool: &RwLock<P>,
+    config: &ConnectionConfig<'_, P::Identity, P::Certificate>,
+    dns_resolver: &D,
+) -> Result<Pooled<P::ConnectionDispather>>
+where
+    P: HttpConnectionPool,
+    D: DnsResolver,
+{
+    let mut permit = None;
+    loop {
+        let (checkout, checkout_timeout, transport) = {
+            let mut pool = pool.write().await;
+            (pool.checkout(config, permit.as_ref()), pool.checkout_timeout(), pool.transport())
+        };
+
+        match checkout {
+            Checkout::Ready(connection) => {
+                return Ok(Pooled { connection, permit, reused: true });
+            }
+            Checkout::Busy(mut connection) => {
+                match timeout(checkout_timeout, connection.ready()).await {
+                    Some(Ok(())) => return Ok(Pooled { connection, permit, reused: true }),
+                    // The connection is gone, the next checkout opens a new one.
+                    Some(Err(_)) => continue,
+                    None => return Err(checkout_timed_out(config, checkout_timeout)),
+                }
+            }
+            Checkout::Connect => {
+                let connection = P::connect(&transport, config, dns_resolver).await?;
+                return Ok(Pooled { connection, permit, reused: false });
+            }
+            Checkout::Limited(limiter) => {
+                match timeout(checkout_timeout, limiter.acquire()).await {
+                    Some(acquired) => permit = Some(acquired),
+                    None => return Err(checkout_timed_out(config, checkout_timeout)),
+                }
+            }
+        }
+    }
+}
+
+fn checkout_timed_out<I, C>(config: &ConnectionConfig<'_, I, C>, elapsed: Duration) -> DeboaError
+where
+    I: Identity,
+    C: Certificate,
+{
+    DeboaError::Connection(ConnectionError::Timeout {
+        message: format!(
+            "Timed out after {:?} waiting for a connection to {}:{}",
+            elapsed,
+            config.host(),
+            config.port()
+        ),
+        source: None,
+    })
+}
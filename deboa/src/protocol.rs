@@ -0,0 +1,364 @@
+//! Protocol module
+//!
+//! This module provides the settings of the HTTP/1, HTTP/2 and HTTP/3
+//! connections, set on the client builder and applied by the runtime crates
+//! on every handshake, and the policy choosing the protocol of a connection.
+//!
+//! # Features
+//!
+//! - Protocol policy: HTTP/1 only, HTTP/2 only, prefer HTTP/2 or prefer HTTP/3
+//! - ALPN protocols offered from the policy, with the negotiated one checked
+//! - HTTP/2 flow control, with fixed initial stream and connection windows or
+//!   an adaptive window
+//! - HTTP/2 frame size, concurrent streams and header list size limits
+//! - HTTP/2 keep-alive PINGs, closing the connection when they go unanswered
+//! - HTTP/1 header case, maximum number of headers and read buffer size
+//! - QUIC idle timeout, keep-alive, stream limits, initial RTT and congestion
+//!   controller of HTTP/3, with 0-RTT on resumed connections
+//!
+//! # Examples
+//!
+//! ```ignore
+//! use deboa::protocol::ProtocolPolicy;
+//! use deboa_tokio::Client;
+//! use std::time::Duration;
+//!
+//! let client = Client::builder()
+//!     .protocol_policy(ProtocolPolicy::PreferHttp2)
+//!     .http2_initial_stream_window_size(1024 * 1024)
+//!     .http2_keep_alive_interval(Duration::from_secs(30))
+//!     .http1_title_case_headers(true)
+//!     .http3_idle_timeout(Duration::from_secs(60))
+//!     .build();
+//! ```
+
+use crate::{
+    errors::{ConnectionError, DeboaError},
+    time::DelayTimer,
+    Result,
+};
+use http::Version;
+use hyper::client::conn::{http1, http2};
+use std::time::Duration;
+
+/// ALPN protocol of HTTP/1.1.
+pub const ALPN_HTTP1: &str = "http/1.1";
+/// ALPN protocol of HTTP/2.
+pub const ALPN_HTTP2: &str = "h2";
+/// ALPN protocol of HTTP/3.
+pub const ALPN_HTTP3: &str = "h3";
+
+/// Policy choosing the protocol of the connections of a client.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ProtocolPolicy {
+    /// Speak HTTP/1.1 only, never upgrading to h2c.
+    Http1Only,
+    /// Speak HTTP/2 only, with prior knowledge without TLS.
+    Http2Only,
+    /// Offer HTTP/2 and HTTP/1.1 over TLS, HTTP/2 first.
+    #[default]
+    PreferHttp2,
+    /// Try HTTP/3 over QUIC first, falling back to HTTP/2 and HTTP/1.1 over
+    /// TLS.
+    PreferHttp3,
+}
+
+impl ProtocolPolicy {
+    /// Get the ALPN protocols offered over TLS, in order of preference.
+    /// HTTP/3 is never offered, as it does not run over TCP.
+    ///
+    /// # Returns
+    ///
+    /// * `&'static [&'static str]` - The ALPN protocols.
+    ///
+    pub fn alpn(&self) -> &'static [&'static str] {
+        match self {
+            ProtocolPolicy::Http1Only => &[ALPN_HTTP1],
+            ProtocolPolicy::Http2Only => &[ALPN_HTTP2],
+            ProtocolPolicy::PreferHttp2 | ProtocolPolicy::PreferHttp3 => &[ALPN_HTTP2, ALPN_HTTP1],
+        }
+    }
+
+    /// Get whether HTTP/3 is tried before TCP.
+    pub fn prefers_http3(&self) -> bool {
+        *self == ProtocolPolicy::PreferHttp3
+    }
+
+    /// Get whether HTTP/3 may be used, for alternatives advertised with
+    /// `Alt-Svc`.
+    pub fn allows_http3(&self) -> bool {
+        matches!(self, ProtocolPolicy::PreferHttp2 | ProtocolPolicy::PreferHttp3)
+    }
+
+    /// Get the protocol of a TLS connection from the ALPN protocol the
+    /// server selected.
+    ///
+    /// # Arguments
+    ///
+    /// * `alpn` - The selected ALPN protocol, if any.
+    ///
+    /// # Errors
+    ///
+    /// * `DeboaError` - If the protocol was not offered by this policy.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Version>` - HTTP/2 or HTTP/1.1. Without ALPN, HTTP/2 for
+    ///   HTTP/2 only and HTTP/1.1 otherwise.
+    ///
+    pub fn negotiated(&self, alpn: Option<&[u8]>) -> Result<Version> {
+        let Some(alpn) = alpn else {
+            return Ok(match self {
+                ProtocolPolicy::Http2Only => Version::HTTP_2,
+                _ => Version::HTTP_11,
+            });
+        };
+
+        let protocol = String::from_utf8_lossy(alpn);
+        if !self
+            .alpn()
+            .contains(&protocol.as_ref())
+        {
+            return Err(DeboaError::Connection(ConnectionError::Tls {
+                message: format!("Unexpected ALPN protocol: {}", protocol),
+                source: None,
+            }));
+        }
+
+        Ok(match protocol.as_ref() {
+            ALPN_HTTP2 => Version::HTTP_2,
+            _ => Version::HTTP_11,
+        })
+    }
+}
+
+/// Settings of the HTTP/1 connections.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Http1Settings {
+    pub(crate) title_case_headers: bool,
+    pub(crate) preserve_header_case: bool,
+    pub(crate) max_headers: Option<usize>,
+    pub(crate) read_buffer_size: Option<usize>,
+}
+
+impl Http1Settings {
+    /// Get whether header names are sent in title case.
+    pub fn title_case_headers(&self) -> bool {
+        self.title_case_headers
+    }
+
+    /// Get whether the case of the header names of responses is kept.
+    pub fn preserve_header_case(&self) -> bool {
+        self.preserve_header_case
+    }
+
+    /// Get the maximum number of headers of a response.
+    pub fn max_headers(&self) -> Option<usize> {
+        self.max_headers
+    }
+
+    /// Get the size of the read buffer.
+    pub fn read_buffer_size(&self) -> Option<usize> {
+        self.read_buffer_size
+    }
+
+    /// Get a handshake builder with these settings.
+    ///
+    /// # Returns
+    ///
+    /// * `http1::Builder` - The hyper HTTP/1 handshake builder.
+    ///
+    pub fn builder(&self) -> http1::Builder {
+        let mut builder = http1::Builder::new();
+        builder
+            .title_case_headers(self.title_case_headers)
+            .preserve_header_case(self.preserve_header_case);
+        if let Some(max_headers) = self.max_headers {
+            builder.max_headers(max_headers);
+        }
+        if let Some(read_buffer_size) = self.read_buffer_size {
+            builder.read_buf_exact_size(Some(read_buffer_size));
+        }
+        builder
+    }
+}
+
+/// Settings of the HTTP/2 connections.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Http2Settings {
+    pub(crate) initial_stream_window_size: Option<u32>,
+    pub(crate) initial_connection_window_size: Option<u32>,
+    pub(crate) adaptive_window: bool,
+    pub(crate) max_frame_size: Option<u32>,
+    pub(crate) max_concurrent_streams: Option<u32>,
+    pub(crate) max_header_list_size: Option<u32>,
+    pub(crate) keep_alive_interval: Option<Duration>,
+    pub(crate) keep_alive_timeout: Option<Duration>,
+}
+
+impl Http2Settings {
+    /// Get the initial flow control window of the streams.
+    pub fn initial_stream_window_size(&self) -> Option<u32> {
+        self.initial_stream_window_size
+    }
+
+    /// Get the initial flow control window of the connection.
+    pub fn initial_connection_window_size(&self) -> Option<u32> {
+        self.initial_connection_window_size
+    }
+
+    /// Get whether the flow control windows adapt to the bandwidth-delay
+    /// product of the connection.
+    pub fn adaptive_window(&self) -> bool {
+        self.adaptive_window
+    }
+
+    /// Get the largest frame accepted from the server.
+    pub fn max_frame_size(&self) -> Option<u32> {
+        self.max_frame_size
+    }
+
+    /// Get the maximum number of streams the server may open.
+    pub fn max_concurrent_streams(&self) -> Option<u32> {
+        self.max_concurrent_streams
+    }
+
+    /// Get the largest header list accepted from the server.
+    pub fn max_header_list_size(&self) -> Option<u32> {
+        self.max_header_list_size
+    }
+
+    /// Get the interval of the keep-alive PINGs.
+    pub fn keep_alive_interval(&self) -> Option<Duration> {
+        self.keep_alive_interval
+    }
+
+    /// Get how long a keep-alive PING waits for its answer.
+    pub fn keep_alive_timeout(&self) -> Option<Duration> {
+        self.keep_alive_timeout
+    }
+
+    /// Get a handshake builder with these settings.
+    ///
+    /// # Arguments
+    ///
+    /// * `executor` - The executor running the connection tasks.
+    ///
+    /// # Returns
+    ///
+    /// * `http2::Builder<E>` - The hyper HTTP/2 handshake builder.
+    ///
+    pub fn builder<E>(&self, executor: E) -> http2::Builder<E>
+    where
+        E: Clone,
+    {
+        let mut builder = http2::Builder::new(executor);
+        builder
+            .timer(DelayTimer)
+            .max_frame_size(self.max_frame_size)
+            .max_concurrent_streams(self.max_concurrent_streams)
+            .keep_alive_interval(self.keep_alive_interval);
+        // The adaptive window takes over the initial windows.
+        if self.adaptive_window {
+            builder.adaptive_window(true);
+        } else {
+            builder
+                .initial_stream_window_size(self.initial_stream_window_size)
+                .initial_connection_window_size(self.initial_connection_window_size);
+        }
+        if let Some(max_header_list_size) = self.max_header_list_size {
+            builder.max_header_list_size(max_header_list_size);
+        }
+        if let Some(keep_alive_timeout) = self.keep_alive_timeout {
+            builder.keep_alive_timeout(keep_alive_timeout);
+        }
+        builder
+    }
+}
+
+/// Congestion controller of the QUIC connections.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CongestionController {
+    /// CUBIC, as in RFC 9438.
+    #[default]
+    Cubic,
+    /// NewReno, as in RFC 9002.
+    NewReno,
+    /// BBR, experimental.
+    Bbr,
+}
+
+/// Settings of the HTTP/3 connections, and of the QUIC transport under them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Http3Settings {
+    pub(crate) idle_timeout: Option<Duration>,
+    pub(crate) keep_alive_interval: Option<Duration>,
+    pub(crate) max_concurrent_bidi_streams: Option<u32>,
+    pub(crate) max_concurrent_uni_streams: Option<u32>,
+    pub(crate) initial_rtt: Option<Duration>,
+    pub(crate) congestion_controller: CongestionController,
+    pub(crate) zero_rtt: bool,
+}
+
+impl Http3Settings {
+    /// Get how long a connection stays open without traffic.
+    pub fn idle_timeout(&self) -> Option<Duration> {
+        self.idle_timeout
+    }
+
+    /// Get the interval of the keep-alive packets.
+    pub fn keep_alive_interval(&self) -> Option<Duration> {
+        self.keep_alive_interval
+    }
+
+    /// Get the maximum number of bidirectional streams the server may open.
+    pub fn max_concurrent_bidi_streams(&self) -> Option<u32> {
+        self.max_concurrent_bidi_streams
+    }
+
+    /// Get the maximum number of unidirectional streams the server may open.
+    pub fn max_concurrent_uni_streams(&self) -> Option<u32> {
+        self.max_concurrent_uni_streams
+    }
+
+    /// Get the round-trip time assumed before it is measured.
+    pub fn initial_rtt(&self) -> Option<Duration> {
+        self.initial_rtt
+    }
+
+    /// Get the congestion controller.
+    pub fn congestion_controller(&self) -> CongestionController {
+        self.congestion_controller
+    }
+
+    /// Get whether resumed connections send their first requests in 0-RTT
+    /// data, before the handshake completes.
+    pub fn zero_rtt(&self) -> bool {
+        self.zero_rtt
+    }
+}
+
+/// Settings of the HTTP/1, HTTP/2 and HTTP/3 connections of a client.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ProtocolSettings {
+    pub(crate) http1: Http1Settings,
+    pub(crate) http2: Http2Settings,
+    pub(crate) http3: Http3Settings,
+}
+
+impl ProtocolSettings {
+    /// Get the settings of the HTTP/1 connections.
+    pub fn http1(&self) -> &Http1Settings {
+        &self.http1
+    }
+
+    /// Get the settings of the HTTP/2 connections.
+    pub fn http2(&self) -> &Http2Settings {
+        &self.http2
+    }
+
+    /// Get the settings of the HTTP/3 connections.
+    pub fn http3(&self) -> &Http3Settings {
+        &self.http3
+    }
+}
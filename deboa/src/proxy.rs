@@ -0,0 +1,788 @@
+//! Proxy module
+//!
+//! This module provides the [`Proxy`] configuration used by the client to
+//! reach servers through HTTP and SOCKS5 proxies, and the tunnel handshakes
+//! shared by the runtime crates.
+//!
+//! # Features
+//!
+//! - Plain HTTP proxies, requests are forwarded in absolute-form
+//! - HTTPS and WebSocket over TLS requests are tunnelled with `CONNECT`
+//! - SOCKS5 proxies, with optional username/password authentication
+//! - Proxy basic authentication
+//! - `NO_PROXY` style exclusion lists
+//! - Opt-in configuration from the `HTTP_PROXY`, `HTTPS_PROXY` and `NO_PROXY`
+//!   environment variables
+//!
+//! # Examples
+//!
+//! ```ignore
+//! use deboa::proxy::{NoProxy, Proxy};
+//! use deboa_tokio::Client;
+//!
+//! let client = Client::builder()
+//!     .proxy(
+//!         Proxy::all("http://proxy.internal:3128")?
+//!             .basic_auth("user", "secret")
+//!             .no_proxy(NoProxy::new("localhost,.internal")),
+//!     )
+//!     .build();
+//! ```
+
+use crate::{
+    dns::DnsResolver,
+    errors::{ConnectionError, DeboaError, DnsError, ErrorSource, RequestError},
+    Result,
+};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use futures::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use http::HeaderValue;
+use std::{fmt::Display, net::IpAddr};
+use url::Url;
+
+/// Default port of HTTP proxies.
+pub const DEFAULT_HTTP_PROXY_PORT: u16 = 80;
+
+/// Default port of SOCKS5 proxies.
+pub const DEFAULT_SOCKS5_PROXY_PORT: u16 = 1080;
+
+/// Largest `CONNECT` response head accepted from a proxy.
+const MAX_CONNECT_RESPONSE_SIZE: usize = 8192;
+
+const SOCKS5_VERSION: u8 = 0x05;
+const SOCKS5_NO_AUTH: u8 = 0x00;
+const SOCKS5_USERNAME_PASSWORD: u8 = 0x02;
+const SOCKS5_NO_ACCEPTABLE_METHOD: u8 = 0xff;
+const SOCKS5_CONNECT: u8 = 0x01;
+const SOCKS5_IPV4: u8 = 0x01;
+const SOCKS5_DOMAIN: u8 = 0x03;
+const SOCKS5_IPV6: u8 = 0x04;
+
+/// Protocol spoken with the proxy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ProxyScheme {
+    /// HTTP proxy, `http://` URLs.
+    Http,
+    /// SOCKS5 proxy resolving the target host locally, `socks5://` URLs.
+    Socks5,
+    /// SOCKS5 proxy resolving the target host itself, `socks5h://` URLs.
+    Socks5h,
+}
+
+/// Requests sent through a proxy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Intercept {
+    Http,
+    Https,
+    All,
+}
+
+/// A proxy the client connects through.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Proxy {
+    intercept: Intercept,
+    scheme: ProxyScheme,
+    host: String,
+    port: u16,
+    credentials: Option<(String, String)>,
+    no_proxy: NoProxy,
+}
+
+impl Proxy {
+    /// Create a proxy used for `http` and `ws` requests.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The proxy url, `http://`, `socks5://` or `socks5h://`.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Proxy>` - The proxy or error.
+    ///
+    pub fn http(url: &str) -> Result<Self> {
+        Self::new(Intercept::Http, url)
+    }
+
+    /// Create a proxy used for `https` and `wss` requests.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The proxy url, `http://`, `socks5://` or `socks5h://`.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Proxy>` - The proxy or error.
+    ///
+    pub fn https(url: &str) -> Result<Self> {
+        Self::new(Intercept::Https, url)
+    }
+
+    /// Create a proxy used for all requests.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The proxy url, `http://`, `socks5://` or `socks5h://`.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Proxy>` - The proxy or error.
+    ///
+    pub fn all(url: &str) -> Result<Self> {
+        Self::new(Intercept::All, url)
+    }
+
+    fn new(intercept: Intercept, url: &str) -> Result<Self> {
+        // Proxy urls are often given without a scheme, e.g. `proxy:3128`.
+        let url = if url.contains("://") { url.to_string() } else { format!("http://{url}") };
+        let parsed = Url::parse(&url)
+            .map_err(|e| DeboaError::Request(RequestError::UrlParse { message: e.to_string() }))?;
+
+        let scheme = match parsed.scheme() {
+            "http" => ProxyScheme::Http,
+            "socks5" => ProxyScheme::Socks5,
+            "socks5h" => ProxyScheme::Socks5h,
+            scheme => {
+                return Err(DeboaError::Connection(ConnectionError::UnsupportedScheme {
+                    message: format!("Unsupported proxy scheme: {scheme}"),
+                }));
+            }
+        };
+
+        let Some(host) = parsed.host_str() else {
+            return Err(DeboaError::Request(RequestError::UrlParse {
+                message: format!("Missing proxy host: {url}"),
+            }));
+        };
+
+        let port = parsed
+            .port()
+            .unwrap_or(match scheme {
+                ProxyScheme::Http => DEFAULT_HTTP_PROXY_PORT,
+                ProxyScheme::Socks5 | ProxyScheme::Socks5h => DEFAULT_SOCKS5_PROXY_PORT,
+            });
+
+        let credentials = if parsed
+            .username()
+            .is_empty()
+        {
+            None
+        } else {
+            Some((
+                decode(parsed.username()),
+                decode(
+                    parsed
+                        .password()
+                        .unwrap_or_default(),
+                ),
+            ))
+        };
+
+        Ok(Self {
+            intercept,
+            scheme,
+            host: host
+                .trim_start_matches('[')
+                .trim_end_matches(']')
+                .to_string(),
+            port,
+            credentials,
+            no_proxy: NoProxy::default(),
+        })
+    }
+
+    /// Read the proxies from the environment.
+    ///
+    /// `http_proxy` and `https_proxy` are read first, then their upper case
+    /// variants, `no_proxy` or `NO_PROXY` applies to both proxies.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Proxy>` - The proxies found, invalid urls are ignored.
+    ///
+    pub fn from_env() -> Vec<Self> {
+        Self::from_lookup(|name| std::env::var(name).ok())
+    }
+
+    pub(crate) fn from_lookup<F>(lookup: F) -> Vec<Self>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let var = |name: &str| {
+            lookup(&name.to_ascii_lowercase())
+                .or_else(|| lookup(name))
+                .filter(|value| !value.is_empty())
+        };
+
+        let no_proxy = var("NO_PROXY")
+            .map(|rules| NoProxy::new(&rules))
+            .unwrap_or_default();
+
+        let mut proxies = Vec::new();
+        if let Some(url) = var("HTTP_PROXY") {
+            match Proxy::http(&url) {
+                Ok(proxy) => proxies.push(proxy.no_proxy(no_proxy.clone())),
+                Err(e) => log::warn!("Ignoring invalid HTTP_PROXY: {e}"),
+            }
+        }
+        if let Some(url) = var("HTTPS_PROXY") {
+            match Proxy::https(&url) {
+                Ok(proxy) => proxies.push(proxy.no_proxy(no_proxy)),
+                Err(e) => log::warn!("Ignoring invalid HTTPS_PROXY: {e}"),
+            }
+        }
+        proxies
+    }
+
+    /// Allow set the credentials sent to the proxy.
+    ///
+    /// # Arguments
+    ///
+    /// * `username` - The username.
+    /// * `password` - The password.
+    ///
+    /// # Returns
+    ///
+    /// * `Proxy` - The proxy with credentials.
+    ///
+    pub fn basic_auth(mut self, username: &str, password: &str) -> Self {
+        self.credentials = Some((username.to_string(), password.to_string()));
+        self
+    }
+
+    /// Allow set the hosts reached without the proxy.
+    ///
+    /// # Arguments
+    ///
+    /// * `no_proxy` - The exclusion list.
+    ///
+    /// # Returns
+    ///
+    /// * `Proxy` - The proxy with exclusion list.
+    ///
+    pub fn no_proxy(mut self, no_proxy: NoProxy) -> Self {
+        self.no_proxy = no_proxy;
+        self
+    }
+
+    /// Get the protocol spoken with the proxy.
+    #[inline]
+    pub fn scheme(&self) -> ProxyScheme {
+        self.scheme
+    }
+
+    /// Get the proxy host.
+    #[inline]
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+
+    /// Get the proxy port.
+    #[inline]
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// Get the username and password sent to the proxy.
+    #[inline]
+    pub fn credentials(&self) -> Option<(&str, &str)> {
+        self.credentials
+            .as_ref()
+            .map(|(username, password)| (username.as_str(), password.as_str()))
+    }
+
+    /// Check if a request is sent through this proxy.
+    ///
+    /// # Arguments
+    ///
+    /// * `scheme` - The request scheme.
+    /// * `host` - The request host.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - `true` if the proxy handles the request.
+    ///
+    pub fn intercepts(&self, scheme: &str, host: &str) -> bool {
+        let secure = matches!(scheme, "https" | "wss");
+        let intercepted = match self.intercept {
+            Intercept::Http => !secure,
+            Intercept::Https => secure,
+            Intercept::All => true,
+        };
+        intercepted
+            && !self
+                .no_proxy
+                .matches(host)
+    }
+
+    /// Check if a connection to the target goes through a tunnel.
+    ///
+    /// SOCKS5 proxies always tunnel, HTTP proxies tunnel TLS connections with
+    /// `CONNECT` and forward plain HTTP requests.
+    ///
+    /// # Arguments
+    ///
+    /// * `scheme` - The request scheme.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - `true` if a tunnel must be opened.
+    ///
+    pub fn is_tunnel(&self, scheme: &str) -> bool {
+        self.scheme != ProxyScheme::Http || matches!(scheme, "https" | "wss")
+    }
+
+    /// Get the `Proxy-Authorization` header value for HTTP proxies.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<HeaderValue>` - The header value, if credentials are set.
+    ///
+    pub fn authorization(&self) -> Option<HeaderValue> {
+        let (username, password) = self.credentials()?;
+        HeaderValue::from_str(&format!(
+            "Basic {}",
+            STANDARD.encode(format!("{username}:{password}"))
+        ))
+        .ok()
+    }
+}
+
+impl Display for Proxy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let scheme = match self.scheme {
+            ProxyScheme::Http => "http",
+            ProxyScheme::Socks5 => "socks5",
+            ProxyScheme::Socks5h => "socks5h",
+        };
+        write!(f, "{}://{}", scheme, authority(&self.host, self.port))
+    }
+}
+
+/// Hosts reached without going through a proxy.
+///
+/// Rules are separated by commas, each one being:
+///
+/// - `*`, matching every host
+/// - a domain, matching the domain and its subdomains, with or without a
+///   leading dot
+/// - an IP address, or a network in CIDR notation
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct NoProxy {
+    rules: Vec<NoProxyRule>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum NoProxyRule {
+    Wildcard,
+    Domain(String),
+    Network(IpAddr, u8),
+}
+
+impl NoProxy {
+    /// Create an exclusion list.
+    ///
+    /// # Arguments
+    ///
+    /// * `rules` - The rules, separated by commas.
+    ///
+    /// # Returns
+    ///
+    /// * `NoProxy` - The exclusion list, invalid rules are ignored.
+    ///
+    pub fn new(rules: &str) -> Self {
+        let rules = rules
+            .split(',')
+            .map(str::trim)
+            .filter(|rule| !rule.is_empty())
+            .filter_map(NoProxyRule::parse)
+            .collect();
+        Self { rules }
+    }
+
+    /// Read the exclusion list from the `no_proxy` or `NO_PROXY` environment
+    /// variable.
+    ///
+    /// # Returns
+    ///
+    /// * `NoProxy` - The exclusion list, empty if not set.
+    ///
+    pub fn from_env() -> Self {
+        std::env::var("no_proxy")
+            .or_else(|_| std::env::var("NO_PROXY"))
+            .map(|rules| NoProxy::new(&rules))
+            .unwrap_or_default()
+    }
+
+    /// Check if the list is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.rules
+            .is_empty()
+    }
+
+    /// Check if a host is reached without the proxy.
+    ///
+    /// # Arguments
+    ///
+    /// * `host` - The host, a domain or an IP address.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - `true` if the host is excluded.
+    ///
+    pub fn matches(&self, host: &str) -> bool {
+        let host = host
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .trim_end_matches('.')
+            .to_ascii_lowercase();
+        let ip = host
+            .parse::<IpAddr>()
+            .ok();
+
+        self.rules
+            .iter()
+            .any(|rule| match rule {
+                NoProxyRule::Wildcard => true,
+                NoProxyRule::Domain(domain) => {
+                    host == *domain
+                        || host
+                            .strip_suffix(domain.as_str())
+                            .is_some_and(|prefix| prefix.ends_with('.'))
+                }
+                NoProxyRule::Network(network, prefix) => {
+                    ip.is_some_and(|ip| in_network(&ip, network, *prefix))
+                }
+            })
+    }
+}
+
+impl NoProxyRule {
+    fn parse(rule: &str) -> Option<Self> {
+        if rule == "*" {
+            return Some(NoProxyRule::Wildcard);
+        }
+
+        if let Some((network, prefix)) = rule.split_once('/') {
+            let network = network
+                .parse::<IpAddr>()
+                .ok()?;
+            let prefix = prefix
+                .parse::<u8>()
+                .ok()?;
+            let max = if network.is_ipv4() { 32 } else { 128 };
+            return (prefix <= max).then_some(NoProxyRule::Network(network, prefix));
+        }
+
+        let rule = rule
+            .trim_start_matches('[')
+            .trim_end_matches(']');
+        if let Ok(ip) = rule.parse::<IpAddr>() {
+            let prefix = if ip.is_ipv4() { 32 } else { 128 };
+            return Some(NoProxyRule::Network(ip, prefix));
+        }
+
+        let domain = rule
+            .trim_start_matches("*.")
+            .trim_start_matches('.')
+            .trim_end_matches('.')
+            .to_ascii_lowercase();
+        (!domain.is_empty()).then_some(NoProxyRule::Domain(domain))
+    }
+}
+
+fn in_network(ip: &IpAddr, network: &IpAddr, prefix: u8) -> bool {
+    let (ip, network, bits) = match (ip, network) {
+        (IpAddr::V4(ip), IpAddr::V4(network)) => {
+            (u32::from(*ip) as u128, u32::from(*network) as u128, 32)
+        }
+        (IpAddr::V6(ip), IpAddr::V6(network)) => (u128::from(*ip), u128::from(*network), 128),
+        _ => return false,
+    };
+
+    if prefix == 0 {
+        return true;
+    }
+
+    let mask = (u128::MAX << (bits - prefix as u32)) & (u128::MAX >> (128 - bits));
+    ip & mask == network & mask
+}
+
+fn decode(value: &str) -> String {
+    urlencoding::decode(value)
+        .map(|value| value.into_owned())
+        .unwrap_or_else(|_| value.to_string())
+}
+
+pub(crate) fn authority(host: &str, port: u16) -> String {
+    if host.contains(':') {
+        format!("[{host}]:{port}")
+    } else {
+        format!("{host}:{port}")
+    }
+}
+
+fn proxy_error(message: impl Into<String>) -> DeboaError {
+    DeboaError::Connection(ConnectionError::Proxy { message: message.into(), source: None })
+}
+
+fn io_error(e: std::io::Error) -> DeboaError {
+    DeboaError::Connection(ConnectionError::Proxy {
+        message: e.to_string(),
+        source: Some(ErrorSource::new(e)),
+    })
+}
+
+/// Open a tunnel to the target through a proxy.
+///
+/// The stream must be connected to the proxy, once this returns it carries
+/// the bytes exchanged with the target.
+///
+/// # Arguments
+///
+/// * `stream` - The stream connected to the proxy.
+/// * `proxy` - The proxy.
+/// * `host` - The target host.
+/// * `port` - The target port.
+/// * `dns_resolver` - The resolver used by `socks5://` proxies.
+///
+/// # Returns
+///
+/// * `Result<()>` - An error if the proxy refused the tunnel.
+///
+pub async fn tunnel<S, D>(
+    stream: &mut S,
+    proxy: &Proxy,
+    host: &str,
+    port: u16,
+    dns_resolver: &D,
+) -> Result<()>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+    D: DnsResolver,
+{
+    match proxy.scheme {
+        ProxyScheme::Http => http_connect(stream, proxy, host, port).await,
+        ProxyScheme::Socks5h => socks5_connect(stream, proxy, host, port).await,
+        ProxyScheme::Socks5 => {
+            let ip = match host.parse::<IpAddr>() {
+                Ok(ip) => ip,
+                Err(_) => dns_resolver
+                    .resolve(host.to_string(), port)
+                    .await?
+                    .into_iter()
+                    .next()
+                    .ok_or_else(|| {
+                        DeboaError::Dns(DnsError::Resolve {
+                            host: host.to_string(),
+                            message: format!("No IP addresses found for hostname: {host}"),
+                            source: None,
+                        })
+                    })?,
+            };
+            socks5_connect(stream, proxy, &ip.to_string(), port).await
+        }
+    }
+}
+
+async fn http_connect<S>(stream: &mut S, proxy: &Proxy, host: &str, port: u16) -> Result<()>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let target = authority(host, port);
+    let mut request = format!("CONNECT {target} HTTP/1.1\r\nHost: {target}\r\n");
+    if let Some(authorization) = proxy.authorization() {
+        request.push_str("Proxy-Authorization: ");
+        request.push_str(
+            authorization
+                .to_str()
+                .unwrap_or_default(),
+        );
+        request.push_str("\r\n");
+    }
+    request.push_str("\r\n");
+
+    stream
+        .write_all(request.as_bytes())
+        .await
+        .map_err(io_error)?;
+    stream
+        .flush()
+        .await
+        .map_err(io_error)?;
+
+    // Read byte by byte, the bytes following the head belong to the target.
+    let mut head = Vec::with_capacity(256);
+    let mut byte = [0u8; 1];
+    while !head.ends_with(b"\r\n\r\n") {
+        if head.len() >= MAX_CONNECT_RESPONSE_SIZE {
+            return Err(proxy_error("CONNECT response too large"));
+        }
+        stream
+            .read_exact(&mut byte)
+            .await
+            .map_err(io_error)?;
+        head.push(byte[0]);
+    }
+
+    let head = String::from_utf8_lossy(&head);
+    let status_line = head
+        .lines()
+        .next()
+        .unwrap_or_default();
+    let mut parts = status_line.splitn(3, ' ');
+    let (Some(version), Some(status)) = (parts.next(), parts.next()) else {
+        return Err(proxy_error(format!("Invalid CONNECT response: {status_line}")));
+    };
+    if !version.starts_with("HTTP/1.") {
+        return Err(proxy_error(format!("Invalid CONNECT response: {status_line}")));
+    }
+
+    match status.parse::<u16>() {
+        Ok(200..=299) => Ok(()),
+        Ok(407) => Err(proxy_error(format!("Proxy authentication required ({status_line})"))),
+        _ => Err(proxy_error(format!("Proxy refused CONNECT to {target} ({status_line})"))),
+    }
+}
+
+async fn socks5_connect<S>(stream: &mut S, proxy: &Proxy, host: &str, port: u16) -> Result<()>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let greeting: &[u8] = if proxy
+        .credentials
+        .is_some()
+    {
+        &[SOCKS5_VERSION, 2, SOCKS5_NO_AUTH, SOCKS5_USERNAME_PASSWORD]
+    } else {
+        &[SOCKS5_VERSION, 1, SOCKS5_NO_AUTH]
+    };
+    stream
+        .write_all(greeting)
+        .await
+        .map_err(io_error)?;
+
+    let mut reply = [0u8; 2];
+    stream
+        .read_exact(&mut reply)
+        .await
+        .map_err(io_error)?;
+    if reply[0] != SOCKS5_VERSION {
+        return Err(proxy_error("Invalid SOCKS5 version in reply"));
+    }
+
+    match reply[1] {
+        SOCKS5_NO_AUTH => {}
+        SOCKS5_USERNAME_PASSWORD => {
+            let Some((username, password)) = proxy.credentials() else {
+                return Err(proxy_error("SOCKS5 proxy requires authentication"));
+            };
+            if username.len() > 255 || password.len() > 255 {
+                return Err(proxy_error("SOCKS5 credentials too long"));
+            }
+
+            let mut auth = Vec::with_capacity(3 + username.len() + password.len());
+            auth.push(0x01);
+            auth.push(username.len() as u8);
+            auth.extend_from_slice(username.as_bytes());
+            auth.push(password.len() as u8);
+            auth.extend_from_slice(password.as_bytes());
+            stream
+                .write_all(&auth)
+                .await
+                .map_err(io_error)?;
+
+            let mut status = [0u8; 2];
+            stream
+                .read_exact(&mut status)
+                .await
+                .map_err(io_error)?;
+            if status[1] != 0x00 {
+                return Err(proxy_error("SOCKS5 authentication failed"));
+            }
+        }
+        SOCKS5_NO_ACCEPTABLE_METHOD => {
+            return Err(proxy_error("SOCKS5 proxy accepted no authentication method"));
+        }
+        method => {
+            return Err(proxy_error(format!("Unsupported SOCKS5 authentication method: {method}")));
+        }
+    }
+
+    let mut request = vec![SOCKS5_VERSION, SOCKS5_CONNECT, 0x00];
+    match host.parse::<IpAddr>() {
+        Ok(IpAddr::V4(ip)) => {
+            request.push(SOCKS5_IPV4);
+            request.extend_from_slice(&ip.octets());
+        }
+        Ok(IpAddr::V6(ip)) => {
+            request.push(SOCKS5_IPV6);
+            request.extend_from_slice(&ip.octets());
+        }
+        Err(_) => {
+            if host.len() > 255 {
+                return Err(proxy_error("SOCKS5 target host too long"));
+            }
+            request.push(SOCKS5_DOMAIN);
+            request.push(host.len() as u8);
+            request.extend_from_slice(host.as_bytes());
+        }
+    }
+    request.extend_from_slice(&port.to_be_bytes());
+    stream
+        .write_all(&request)
+        .await
+        .map_err(io_error)?;
+
+    let mut reply = [0u8; 4];
+    stream
+        .read_exact(&mut reply)
+        .await
+        .map_err(io_error)?;
+    if reply[0] != SOCKS5_VERSION {
+        return Err(proxy_error("Invalid SOCKS5 version in reply"));
+    }
+    if reply[1] != 0x00 {
+        return Err(proxy_error(format!(
+            "SOCKS5 proxy refused connection to {}: {}",
+            authority(host, port),
+            socks5_reply_message(reply[1])
+        )));
+    }
+
+    // Skip the bound address, it is not needed to use the tunnel.
+    let address_len = match reply[3] {
+        SOCKS5_IPV4 => 4,
+        SOCKS5_IPV6 => 16,
+        SOCKS5_DOMAIN => {
+            let mut len = [0u8; 1];
+            stream
+                .read_exact(&mut len)
+                .await
+                .map_err(io_error)?;
+            len[0] as usize
+        }
+        kind => return Err(proxy_error(format!("Invalid SOCKS5 address type: {kind}"))),
+    };
+    let mut bound = vec![0u8; address_len + 2];
+    stream
+        .read_exact(&mut bound)
+        .await
+        .map_err(io_error)?;
+
+    Ok(())
+}
+
+fn socks5_reply_message(code: u8) -> &'static str {
+    match code {
+        0x01 => "general failure",
+        0x02 => "connection not allowed by ruleset",
+        0x03 => "network unreachable",
+        0x04 => "host unreachable",
+        0x05 => "connection refused",
+        0x06 => "TTL expired",
+        0x07 => "command not supported",
+        0x08 => "address type not supported",
+        _ => "unknown error",
+    }
+}
@@ -0,0 +1,444 @@
+//! Redirect handling for the Deboa HTTP client.
+//!
+//! This module provides the [`RedirectPolicy`] used by the client to decide
+//! whether 3xx responses are followed, and the bookkeeping that rewrites a
+//! request for the next hop.
+//!
+//! # Features
+//!
+//! - Disable redirects, follow up to a number of hops or decide with a closure
+//! - Method rewriting as described in RFC 9110, section 15.4
+//! - Credentials and cookies are dropped when a hop leaves the original origin
+//! - Redirect loop detection
+//!
+//! # Examples
+//!
+//! ```ignore
+//! use deboa::redirect::{RedirectAction, RedirectPolicy};
+//! use deboa_tokio::Client;
+//!
+//! let client = Client::builder()
+//!     .redirect_policy(RedirectPolicy::custom(|attempt| {
+//!         if attempt.previous().len() > 3 {
+//!             RedirectAction::Stop
+//!         } else {
+//!             RedirectAction::Follow
+//!         }
+//!     }))
+//!     .build();
+//! ```
+
+use crate::{
+    errors::{DeboaError, RequestError},
+    request::{default_version, DeboaRequest},
+    response::DeboaResponse,
+    Result,
+};
+use http::{header, request::Parts, HeaderValue, Method, StatusCode, Uri};
+use hyper_body_utils::HttpBody;
+use std::{fmt::Debug, sync::Arc};
+use url::Url;
+
+/// Default maximum number of redirects followed by the client.
+pub const DEFAULT_MAX_REDIRECTS: usize = 10;
+
+type RedirectFn = dyn Fn(&RedirectAttempt<'_>) -> RedirectAction + Send + Sync;
+
+/// Policy used to decide whether a redirect response should be followed.
+#[derive(Clone)]
+pub enum RedirectPolicy {
+    /// Never follow redirects, 3xx responses are returned as-is.
+    None,
+    /// Follow at most the given number of redirects.
+    Limited(usize),
+    /// Let a closure decide for every redirect.
+    Custom(Arc<RedirectFn>),
+}
+
+impl RedirectPolicy {
+    /// Create a policy that never follows redirects.
+    ///
+    /// # Returns
+    ///
+    /// * `RedirectPolicy` - The policy.
+    ///
+    pub fn none() -> Self {
+        RedirectPolicy::None
+    }
+
+    /// Create a policy that follows at most `max` redirects.
+    ///
+    /// # Arguments
+    ///
+    /// * `max` - The maximum number of hops.
+    ///
+    /// # Returns
+    ///
+    /// * `RedirectPolicy` - The policy.
+    ///
+    pub fn limited(max: usize) -> Self {
+        RedirectPolicy::Limited(max)
+    }
+
+    /// Create a policy backed by a closure.
+    ///
+    /// # Arguments
+    ///
+    /// * `f` - The closure deciding what to do with each redirect.
+    ///
+    /// # Returns
+    ///
+    /// * `RedirectPolicy` - The policy.
+    ///
+    pub fn custom<F>(f: F) -> Self
+    where
+        F: Fn(&RedirectAttempt<'_>) -> RedirectAction + Send + Sync + 'static,
+    {
+        RedirectPolicy::Custom(Arc::new(f))
+    }
+
+    /// Decide what to do with a redirect attempt.
+    ///
+    /// # Arguments
+    ///
+    /// * `attempt` - The redirect attempt.
+    ///
+    /// # Returns
+    ///
+    /// * `RedirectAction` - The action to take.
+    ///
+    pub fn redirect(&self, attempt: &RedirectAttempt<'_>) -> RedirectAction {
+        match self {
+            RedirectPolicy::None => RedirectAction::Stop,
+            RedirectPolicy::Limited(max) => {
+                if attempt
+                    .previous()
+                    .len()
+                    > *max
+                {
+                    RedirectAction::Error(format!("Too many redirects (max {})", max))
+                } else {
+                    RedirectAction::Follow
+                }
+            }
+            RedirectPolicy::Custom(f) => f(attempt),
+        }
+    }
+}
+
+impl Default for RedirectPolicy {
+    fn default() -> Self {
+        RedirectPolicy::Limited(DEFAULT_MAX_REDIRECTS)
+    }
+}
+
+impl Debug for RedirectPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RedirectPolicy::None => f.write_str("None"),
+            RedirectPolicy::Limited(max) => f
+                .debug_tuple("Limited")
+                .field(max)
+                .finish(),
+            RedirectPolicy::Custom(_) => f.write_str("Custom"),
+        }
+    }
+}
+
+/// Action returned by a [`RedirectPolicy`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum RedirectAction {
+    /// Follow the redirect.
+    Follow,
+    /// Stop and return the redirect response.
+    Stop,
+    /// Abort with an error.
+    Error(String),
+}
+
+/// A redirect about to be followed.
+#[derive(Debug)]
+pub struct RedirectAttempt<'a> {
+    status: StatusCode,
+    location: &'a Uri,
+    previous: &'a [Uri],
+}
+
+impl<'a> RedirectAttempt<'a> {
+    /// Status code of the redirect response.
+    ///
+    /// # Returns
+    ///
+    /// * `StatusCode` - The status code.
+    ///
+    #[inline]
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    /// Uri the client is being redirected to.
+    ///
+    /// # Returns
+    ///
+    /// * `&Uri` - The location.
+    ///
+    #[inline]
+    pub fn location(&self) -> &Uri {
+        self.location
+    }
+
+    /// Uris already requested, starting with the original one.
+    ///
+    /// # Returns
+    ///
+    /// * `&[Uri]` - The previous uris.
+    ///
+    #[inline]
+    pub fn previous(&self) -> &[Uri] {
+        self.previous
+    }
+}
+
+/// Keeps track of the hops of a request while redirects are followed.
+pub(crate) struct Redirector<'a> {
+    policy: &'a RedirectPolicy,
+    head: Parts,
+    body: Option<HttpBody>,
+    visited: Vec<(Method, Uri)>,
+    chain: Vec<Uri>,
+}
+
+impl<'a> Redirector<'a> {
+    /// Start tracking a request, returning the request to be sent.
+    pub(crate) fn new(
+        policy: &'a RedirectPolicy,
+        request: DeboaRequest,
+    ) -> Result<(Self, DeboaRequest)> {
+        let (parts, body) = request.into_parts();
+        let replay = body
+            .try_clone()
+            .ok();
+        let redirector = Redirector {
+            policy,
+            head: parts.clone(),
+            body: replay,
+            visited: vec![(parts.method.clone(), parts.uri.clone())],
+            chain: Vec::new(),
+        };
+        Ok((redirector, DeboaRequest::from_parts(parts, body)?))
+    }
+
+    /// Build the request for the next hop, if the response should be followed.
+    pub(crate) fn next(&mut self, response: &DeboaResponse) -> Result<Option<DeboaRequest>> {
+        let status = response.status();
+        let (method, keep_body) = match redirect_method(status, &self.head.method) {
+            Some(rewrite) => rewrite,
+            None => return Ok(None),
+        };
+
+        let Some(location) = response
+            .headers()
+            .get(header::LOCATION)
+            .and_then(|value| value.to_str().ok())
+        else {
+            return Ok(None);
+        };
+
+        let location = resolve_location(&self.head.uri, location)?;
+        if !matches!(location.scheme_str(), Some("http") | Some("https")) {
+            return Ok(None);
+        }
+
+        if keep_body && self.body.is_none() {
+            // The body was a stream and cannot be sent again.
+            return Ok(None);
+        }
+
+        let mut previous = self.chain.clone();
+        previous.push(
+            self.head
+                .uri
+                .clone(),
+        );
+
+        let attempt = RedirectAttempt { status, location: &location, previous: &previous };
+        match self
+            .policy
+            .redirect(&attempt)
+        {
+            RedirectAction::Follow => {}
+            RedirectAction::Stop => return Ok(None),
+            RedirectAction::Error(message) => {
+                return Err(DeboaError::Request(RequestError::Redirect { message }));
+            }
+        }
+
+        if self
+            .visited
+            .iter()
+            .any(|(m, uri)| *m == method && *uri == location)
+        {
+            return Err(DeboaError::Request(RequestError::Redirect {
+                message: format!("Redirect loop detected at {}", location),
+            }));
+        }
+
+        let mut parts = self.head.clone();
+
+        if !is_same_origin(&parts.uri, &location) {
+            parts
+                .headers
+                .remove(header::AUTHORIZATION);
+            parts
+                .headers
+                .remove(header::PROXY_AUTHORIZATION);
+            parts
+                .headers
+                .remove(header::COOKIE);
+        }
+
+        if !keep_body {
+            for name in [
+                header::CONTENT_TYPE,
+                header::CONTENT_LENGTH,
+                header::CONTENT_ENCODING,
+                header::TRANSFER_ENCODING,
+            ] {
+                parts
+                    .headers
+                    .remove(name);
+            }
+            self.body = Some(HttpBody::empty());
+        }
+
+        if let Some(host) = location
+            .authority()
+            .and_then(|authority| HeaderValue::from_str(authority.as_str()).ok())
+        {
+            parts
+                .headers
+                .insert(header::HOST, host);
+        }
+
+        // A redirect to another scheme asks for the version of that scheme.
+        if parts.uri.scheme() != location.scheme() {
+            parts.version = default_version(&location);
+        }
+
+        parts.method = method.clone();
+        parts.uri = location.clone();
+
+        let body = match &self.body {
+            Some(body) => body
+                .try_clone()
+                .map_err(|e| {
+                    DeboaError::Request(RequestError::Prepare { message: e.to_string() })
+                })?,
+            None => HttpBody::empty(),
+        };
+
+        self.chain = previous;
+        self.visited
+            .push((method, location));
+        self.head = parts.clone();
+
+        Ok(Some(DeboaRequest::from_parts(parts, body)?))
+    }
+
+    /// Attach the redirect chain to the final response.
+    pub(crate) fn finish(self, mut response: DeboaResponse) -> DeboaResponse {
+        response.set_redirects(self.chain);
+        response
+    }
+}
+
+/// Method and body handling for a redirect status, as described in RFC 9110.
+///
+/// # Arguments
+///
+/// * `status` - The response status.
+/// * `method` - The method of the request that got redirected.
+///
+/// # Returns
+///
+/// * `Option<(Method, bool)>` - The method to use for the next hop and whether
+///   the body must be sent again, or `None` if the status is not a redirect.
+///
+pub(crate) fn redirect_method(status: StatusCode, method: &Method) -> Option<(Method, bool)> {
+    match status {
+        StatusCode::SEE_OTHER => {
+            if *method == Method::HEAD {
+                Some((Method::HEAD, false))
+            } else {
+                Some((Method::GET, false))
+            }
+        }
+        StatusCode::MOVED_PERMANENTLY | StatusCode::FOUND => {
+            if *method == Method::POST {
+                Some((Method::GET, false))
+            } else {
+                Some((method.clone(), has_body(method)))
+            }
+        }
+        StatusCode::TEMPORARY_REDIRECT | StatusCode::PERMANENT_REDIRECT => {
+            Some((method.clone(), has_body(method)))
+        }
+        _ => None,
+    }
+}
+
+#[inline]
+fn has_body(method: &Method) -> bool {
+    !matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS | Method::TRACE)
+}
+
+/// Resolve a `Location` header value against the uri of the request.
+///
+/// # Arguments
+///
+/// * `base` - The uri of the redirected request.
+/// * `location` - The value of the `Location` header.
+///
+/// # Returns
+///
+/// * `Result<Uri>` - The absolute uri of the next hop.
+///
+pub(crate) fn resolve_location(base: &Uri, location: &str) -> Result<Uri> {
+    let base = Url::parse(&base.to_string())
+        .map_err(|e| DeboaError::Request(RequestError::UrlParse { message: e.to_string() }))?;
+    let url = base
+        .join(location)
+        .map_err(|e| DeboaError::Request(RequestError::UrlParse { message: e.to_string() }))?;
+    url.as_str()
+        .parse::<Uri>()
+        .map_err(|e| DeboaError::Request(RequestError::UrlParse { message: e.to_string() }))
+}
+
+/// Check whether two uris share scheme, host and port.
+///
+/// # Arguments
+///
+/// * `a` - The first uri.
+/// * `b` - The second uri.
+///
+/// # Returns
+///
+/// * `bool` - `true` if both uris have the same origin.
+///
+pub(crate) fn is_same_origin(a: &Uri, b: &Uri) -> bool {
+    let host_eq = match (a.host(), b.host()) {
+        (Some(a), Some(b)) => a.eq_ignore_ascii_case(b),
+        _ => false,
+    };
+    a.scheme() == b.scheme() && host_eq && port_or_default(a) == port_or_default(b)
+}
+
+#[inline]
+fn port_or_default(uri: &Uri) -> Option<u16> {
+    uri.port_u16()
+        .or(match uri.scheme_str() {
+            Some("http") | Some("ws") => Some(80),
+            Some("https") | Some("wss") => Some(443),
+            _ => None,
+        })
+}
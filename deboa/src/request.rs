@@ -66,10 +66,14 @@
 //! ```
 
 use crate::{
+    compression::{self, Decompress, Encoding},
     cookie::DeboaCookie,
     errors::{DeboaError, RequestError},
     form::{DeboaForm, Form},
+    options::RequestOptions,
+    redirect::RedirectPolicy,
     response::DeboaResponse,
+    retry::RetryPolicy,
     serde::RequestBody,
     url::IntoUrl,
     HttpClient, Result,
@@ -79,14 +83,19 @@ use bytes::Bytes;
 use hashbrown::HashMap;
 use http::{
     header::{self},
-    HeaderMap, HeaderName, HeaderValue, Method, Request, Uri, Version,
+    Extensions, HeaderMap, HeaderName, HeaderValue, Method, Request, Uri, Version,
 };
 use http_body_util::combinators::BoxBody;
 use hyper_body_utils::HttpBody;
 use log::error;
 use regex::Regex;
 use serde::Serialize;
-use std::{fmt::Debug, future::Future, str::FromStr};
+use std::{
+    fmt::Debug,
+    future::Future,
+    str::FromStr,
+    time::{Duration, Instant},
+};
 use url::Url;
 
 /// Bytes body type
@@ -118,6 +127,25 @@ pub type Http2Request = hyper::client::conn::http2::SendRequest<HttpBody>;
 pub trait IntoRequest: private::IntoRequestSealed {
     /// Convert self to a DeboaRequest
     fn into_request(self) -> Result<DeboaRequest>;
+
+    /// Convert self to a DeboaRequest, resolving a path against a base url.
+    ///
+    /// # Arguments
+    ///
+    /// * `base_url` - The base url, if any.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<DeboaRequest>` - The request.
+    ///
+    #[inline]
+    fn into_request_from(self, base_url: Option<&Url>) -> Result<DeboaRequest>
+    where
+        Self: Sized,
+    {
+        let _ = base_url;
+        self.into_request()
+    }
 }
 
 impl IntoRequest for DeboaRequest {
@@ -132,6 +160,16 @@ impl IntoRequest for &str {
     fn into_request(self) -> Result<DeboaRequest> {
         DeboaRequest::get(self)?.build()
     }
+
+    #[inline]
+    fn into_request_from(self, base_url: Option<&Url>) -> Result<DeboaRequest> {
+        match base_url {
+            Some(base_url) if crate::url::is_relative(self) => {
+                DeboaRequest::get(crate::url::resolve(base_url, self)?)?.build()
+            }
+            _ => self.into_request(),
+        }
+    }
 }
 
 impl IntoRequest for String {
@@ -139,6 +177,12 @@ impl IntoRequest for String {
     fn into_request(self) -> Result<DeboaRequest> {
         DeboaRequest::get(self)?.build()
     }
+
+    #[inline]
+    fn into_request_from(self, base_url: Option<&Url>) -> Result<DeboaRequest> {
+        self.as_str()
+            .into_request_from(base_url)
+    }
 }
 
 impl IntoRequest for Url {
@@ -308,8 +352,8 @@ impl Fetch for &str {
     where
         T: HttpClient + Send,
     {
-        DeboaRequest::get(*self)?
-            .send_with(client)
+        client
+            .execute(*self)
             .await
     }
 }
@@ -360,8 +404,8 @@ impl FetchWith for &str {
     where
         T: HttpClient,
     {
-        DeboaRequest::get(*self)?
-            .send_with(client)
+        client
+            .execute(*self)
             .await
     }
 }
@@ -372,12 +416,33 @@ impl FetchWith for String {
     where
         T: HttpClient,
     {
-        DeboaRequest::get(self)?
-            .send_with(client)
+        client
+            .execute(self.as_str())
             .await
     }
 }
 
+/// Get the protocol version a request to an url asks for by default.
+///
+/// Requests over TLS ask for HTTP/2, the version is then negotiated with
+/// ALPN. Cleartext requests ask for HTTP/1.1, HTTP/2 without TLS (h2c) is
+/// only used when asked for.
+///
+/// # Arguments
+///
+/// * `uri` - The url of the request.
+///
+/// # Returns
+///
+/// * `Version` - The protocol version.
+///
+pub(crate) fn default_version(uri: &Uri) -> Version {
+    match uri.scheme_str() {
+        Some("http") | Some("ws") => Version::HTTP_11,
+        _ => Version::HTTP_2,
+    }
+}
+
 /// A utility function to create a GET request within DeboaRequest.
 ///
 /// # Arguments
@@ -583,6 +648,7 @@ pub fn patch<T: IntoUrl>(url: T) -> Result<DeboaRequestBuilder> {
 /// * `form` - Optional form data for form submissions
 pub struct DeboaRequestBuilder {
     inner: Request<HttpBody>,
+    encoding: Option<Encoding>,
 }
 
 impl DeboaRequestBuilder {
@@ -948,6 +1014,197 @@ impl DeboaRequestBuilder {
         )
     }
 
+    /// Set whether the response is decompressed, enabled by default when a
+    /// compression feature is enabled. Disable it to get the raw bytes.
+    ///
+    /// # Arguments
+    ///
+    /// * `decompress` - `false` to keep the response as sent by the server.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The request builder.
+    ///
+    #[inline]
+    pub fn decompress(mut self, decompress: bool) -> Self {
+        self.inner
+            .extensions_mut()
+            .insert(Decompress(decompress));
+        self
+    }
+
+    /// Compress the body of the request, whichever way it was set, and set
+    /// the `Content-Encoding` header. The coding feature must be enabled.
+    ///
+    /// # Arguments
+    ///
+    /// * `encoding` - The coding.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The request builder.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,compile_fail
+    /// use deboa::{compression::Encoding, request::post};
+    ///
+    /// let request = post("https://some.api.com/events")?
+    ///   .body_as(JsonBody, events)?
+    ///   .compress(Encoding::Gzip)
+    ///   .build()?;
+    /// ```
+    #[inline]
+    pub fn compress(mut self, encoding: Encoding) -> Self {
+        self.encoding = Some(encoding);
+        self
+    }
+
+    /// Set the time allowed for the whole exchange, retries, redirects and
+    /// reading the response body included.
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - The timeout.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The request builder.
+    ///
+    #[inline]
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.options_mut()
+            .timeout = Some(timeout);
+        self
+    }
+
+    /// Set the instant the whole exchange must be done by.
+    ///
+    /// # Arguments
+    ///
+    /// * `deadline` - The deadline.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The request builder.
+    ///
+    #[inline]
+    pub fn deadline(mut self, deadline: Instant) -> Self {
+        self.options_mut()
+            .deadline = Some(deadline);
+        self
+    }
+
+    /// Set the time allowed to open a new connection, overriding the client one.
+    ///
+    /// # Arguments
+    ///
+    /// * `connect_timeout` - The timeout.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The request builder.
+    ///
+    #[inline]
+    pub fn connect_timeout(mut self, connect_timeout: Duration) -> Self {
+        self.options_mut()
+            .connect_timeout = Some(connect_timeout);
+        self
+    }
+
+    /// Set the time allowed to wait for the response head, overriding the
+    /// client request timeout.
+    ///
+    /// # Arguments
+    ///
+    /// * `response_timeout` - The timeout.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The request builder.
+    ///
+    #[inline]
+    pub fn response_timeout(mut self, response_timeout: Duration) -> Self {
+        self.options_mut()
+            .response_timeout = Some(response_timeout);
+        self
+    }
+
+    /// Set the time allowed between two chunks of the response body.
+    ///
+    /// # Arguments
+    ///
+    /// * `read_timeout` - The timeout.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The request builder.
+    ///
+    #[inline]
+    pub fn read_timeout(mut self, read_timeout: Duration) -> Self {
+        self.options_mut()
+            .read_timeout = Some(read_timeout);
+        self
+    }
+
+    /// Set the retry policy of the request, overriding the client one.
+    ///
+    /// # Arguments
+    ///
+    /// * `retry_policy` - The retry policy.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The request builder.
+    ///
+    #[inline]
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.options_mut()
+            .retry_policy = Some(retry_policy);
+        self
+    }
+
+    /// Set the redirect policy of the request, overriding the client one.
+    ///
+    /// # Arguments
+    ///
+    /// * `redirect_policy` - The redirect policy.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The request builder.
+    ///
+    #[inline]
+    pub fn redirect_policy(mut self, redirect_policy: RedirectPolicy) -> Self {
+        self.options_mut()
+            .redirect_policy = Some(redirect_policy);
+        self
+    }
+
+    /// Set all the per request settings at once.
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - The settings.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The request builder.
+    ///
+    #[inline]
+    pub fn options(mut self, options: RequestOptions) -> Self {
+        self.inner
+            .extensions_mut()
+            .insert(options);
+        self
+    }
+
+    fn options_mut(&mut self) -> &mut RequestOptions {
+        self.inner
+            .extensions_mut()
+            .get_or_insert_default()
+    }
+
     /// Build the request. Consuming the builder.
     ///
     /// # Returns
@@ -960,7 +1217,15 @@ impl DeboaRequestBuilder {
     ///
     #[inline]
     pub fn build(self) -> Result<DeboaRequest> {
-        Ok(DeboaRequest { inner: self.inner })
+        let Some(encoding) = self.encoding else {
+            return Ok(DeboaRequest { inner: self.inner });
+        };
+
+        let (mut parts, body) = self
+            .inner
+            .into_parts();
+        let body = compression::compress(&mut parts.headers, body, encoding)?;
+        Ok(DeboaRequest { inner: Request::from_parts(parts, body) })
     }
 
     /// Send the request. Consuming the builder.
@@ -1188,9 +1453,9 @@ impl FromStr for DeboaRequest {
             })?;
 
         let mut builder = Request::builder()
+            .version(default_version(&uri))
             .uri(uri)
-            .method(method)
-            .version(Version::HTTP_2);
+            .method(method);
 
         *builder
             .headers_mut()
@@ -1261,13 +1526,13 @@ impl DeboaRequest {
 
         let request = Request::builder()
             .method(method)
-            .version(Version::HTTP_2)
+            .version(default_version(&uri))
             .header(header::HOST, uri.host().unwrap())
             .uri(uri)
             .body(HttpBody::from_bytes(&[]))
             .map_err(|e| DeboaError::Request(RequestError::Prepare { message: e.to_string() }))?;
 
-        Ok(DeboaRequestBuilder { inner: request })
+        Ok(DeboaRequestBuilder { inner: request, encoding: None })
     }
 
     /// Allow make a GET request.
@@ -1511,6 +1776,56 @@ impl DeboaRequest {
             .headers_mut()
     }
 
+    /// Allow get request extensions at any time.
+    ///
+    /// # Returns
+    ///
+    /// * `&Extensions` - The extensions.
+    ///
+    #[inline]
+    pub fn extensions(&self) -> &Extensions {
+        self.inner
+            .extensions()
+    }
+
+    /// Return mutable extensions
+    ///
+    /// # Returns
+    ///
+    /// * `&mut Extensions` - The extensions.
+    ///
+    #[inline]
+    pub fn extensions_mut(&mut self) -> &mut Extensions {
+        self.inner
+            .extensions_mut()
+    }
+
+    /// Allow get the per request settings at any time.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<&RequestOptions>` - The settings, if any was set.
+    ///
+    #[inline]
+    pub fn options(&self) -> Option<&RequestOptions> {
+        self.inner
+            .extensions()
+            .get()
+    }
+
+    /// Allow get mutable per request settings at any time.
+    ///
+    /// # Returns
+    ///
+    /// * `&mut RequestOptions` - The settings.
+    ///
+    #[inline]
+    pub fn options_mut(&mut self) -> &mut RequestOptions {
+        self.inner
+            .extensions_mut()
+            .get_or_insert_default()
+    }
+
     /// Allow get cookies at any time.
     ///
     /// # Returns
@@ -1537,6 +1852,47 @@ impl DeboaRequest {
             })
     }
 
+    /// Try to clone the request, so it can be sent again.
+    /// Only requests with a buffered body can be cloned, streaming
+    /// bodies are consumed when the request is sent.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<DeboaRequest>` - The cloned request.
+    ///
+    /// # Errors
+    ///
+    /// * `DeboaError` - If the body can't be replayed.
+    ///
+    pub fn try_clone(&self) -> Result<DeboaRequest> {
+        let body = self
+            .inner
+            .body()
+            .try_clone()
+            .map_err(|e| DeboaError::Request(RequestError::Prepare { message: e.to_string() }))?;
+
+        let mut request = Request::new(body);
+        *request.method_mut() = self
+            .inner
+            .method()
+            .clone();
+        *request.uri_mut() = self
+            .inner
+            .uri()
+            .clone();
+        *request.version_mut() = self.inner.version();
+        *request.headers_mut() = self
+            .inner
+            .headers()
+            .clone();
+        *request.extensions_mut() = self
+            .inner
+            .extensions()
+            .clone();
+
+        Ok(DeboaRequest { inner: request })
+    }
+
     /// Allow get body at any time.
     ///
     /// # Returns
@@ -79,17 +79,30 @@
 //! }
 //! ```
 use crate::{
+    compression::is_decompress_error,
     cookie::DeboaCookie,
-    errors::{DeboaError, IoError},
+    errors::{DeboaError, ErrorSource, IoError, ResponseError},
     serde::ResponseBody,
     Result,
 };
-use http::{header, HeaderName, HeaderValue, Response};
+use bytes::{Bytes, BytesMut};
+use futures::Stream;
+use http::{header, HeaderName, HeaderValue, Response, Uri};
+use http_body::{Body, Frame};
 use http_body_util::BodyExt;
 use hyper_body_utils::HttpBody;
 use log::error;
 use serde::Deserialize;
-use std::{fmt::Debug, fs::write};
+use std::{
+    fmt::Debug,
+    fs::write,
+    pin::{pin, Pin},
+    sync::Mutex,
+    task::{Context, Poll},
+};
+
+/// Default number of body bytes kept in a [`ResponseError::Status`].
+pub const DEFAULT_BODY_PREVIEW_SIZE: usize = 1024;
 
 /// Trait to allow converting a type into a DeboaBody.
 ///
@@ -152,6 +165,24 @@ impl DeboaResponseBuilder {
         self
     }
 
+    /// Allow set response version at any time.
+    ///
+    /// # Arguments
+    ///
+    /// * `version` - The new version.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The response builder.
+    ///
+    #[inline]
+    pub fn version(mut self, version: http::Version) -> Self {
+        *self
+            .inner
+            .version_mut() = version;
+        self
+    }
+
     /// Allow set response headers at any time.
     ///
     /// # Arguments
@@ -218,7 +249,7 @@ impl DeboaResponseBuilder {
     ///
     #[inline]
     pub fn empty(self) -> DeboaResponse {
-        DeboaResponse { inner: self.inner }
+        DeboaResponse::new(self.inner)
     }
 
     /// Build the response. Consuming the builder.
@@ -229,7 +260,7 @@ impl DeboaResponseBuilder {
     ///
     #[inline]
     pub fn build(self) -> DeboaResponse {
-        DeboaResponse { inner: self.inner }
+        DeboaResponse::new(self.inner)
     }
 }
 
@@ -296,6 +327,7 @@ impl DeboaResponseBuilder {
 /// * `body` - The response body (can be streamed or buffered)
 pub struct DeboaResponse {
     inner: Response<HttpBody>,
+    redirects: Vec<Uri>,
 }
 
 impl Debug for DeboaResponse {
@@ -303,6 +335,7 @@ impl Debug for DeboaResponse {
         f.debug_struct("DeboaResponse")
             .field("status", &self.inner.status())
             .field("headers", &self.inner.headers())
+            .field("redirects", &self.redirects)
             .finish()
     }
 }
@@ -328,7 +361,7 @@ impl DeboaResponse {
     /// * `inner` - The inner response.
     ///
     pub fn new(inner: Response<HttpBody>) -> Self {
-        Self { inner }
+        Self { inner, redirects: Vec::new() }
     }
 
     /// Create a new DeboaResponseBuilder
@@ -360,6 +393,37 @@ impl DeboaResponse {
             .status_mut()
     }
 
+    /// Allow get version at any time.
+    /// The protocol the response was received with, as negotiated for its
+    /// connection.
+    ///
+    /// # Returns
+    ///
+    /// * `http::Version` - The version of the response.
+    ///
+    #[inline]
+    pub fn version(&self) -> http::Version {
+        self.inner.version()
+    }
+
+    /// Allow get the redirect chain at any time.
+    /// Every uri that answered with a followed redirect, in order,
+    /// starting with the uri of the original request.
+    ///
+    /// # Returns
+    ///
+    /// * `&[Uri]` - The redirect chain, empty if no redirect was followed.
+    ///
+    #[inline]
+    pub fn redirects(&self) -> &[Uri] {
+        &self.redirects
+    }
+
+    #[inline]
+    pub(crate) fn set_redirects(&mut self, redirects: Vec<Uri>) {
+        self.redirects = redirects;
+    }
+
     /// Allow get headers at any time.
     ///
     /// # Returns
@@ -558,6 +622,46 @@ impl DeboaResponse {
         (parts, body)
     }
 
+    /// Turn a response with a client or server error status into an error,
+    /// consuming body.
+    /// The error keeps the status, the headers and the first
+    /// [`DEFAULT_BODY_PREVIEW_SIZE`] bytes of the body.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<DeboaResponse>` - The response if its status is not an error, or
+    ///   a [`ResponseError::Status`].
+    ///
+    /// # Examples
+    ///
+    /// ```compile_fail
+    /// use deboa::request::get;
+    ///
+    /// let text = get("https://jsonplaceholder.typicode.com/posts")?
+    ///     .send_with(client)
+    ///     .await?
+    ///     .error_for_status()
+    ///     .await?
+    ///     .text()
+    ///     .await?;
+    /// ```
+    ///
+    pub async fn error_for_status(self) -> Result<Self> {
+        let status = self.status();
+        if !status.is_client_error() && !status.is_server_error() {
+            return Ok(self);
+        }
+
+        let (parts, body) = self.into_parts();
+        let (body, _) = read_prefix(body, DEFAULT_BODY_PREVIEW_SIZE).await;
+        Err(DeboaError::Response(ResponseError::Status {
+            status,
+            headers: Box::new(parts.headers),
+            body,
+            details: None,
+        }))
+    }
+
     /// Returns the response body as a deserialized type, consuming body.
     /// Useful for small responses. For larger responses, consider using `stream`.
     ///
@@ -670,11 +774,109 @@ impl DeboaResponse {
             .await;
         match bytes {
             Ok(bytes) => data.extend_from_slice(&bytes.to_bytes()),
-            Err(e) => {
-                error!("Failed to collect response body: {}", e);
-                return Err(DeboaError::Io(IoError::Content { message: e.to_string() }));
-            }
+            Err(e) => return Err(body_error(e)),
         }
         Ok(data)
     }
 }
+
+/// Convert an error raised while reading a body.
+fn body_error(e: std::io::Error) -> DeboaError {
+    if is_decompress_error(&e) {
+        error!("Failed to decompress response body: {}", e);
+        return DeboaError::Io(IoError::Decompress {
+            message: e.to_string(),
+            source: Some(ErrorSource::new(e)),
+        });
+    }
+
+    error!("Failed to collect response body: {}", e);
+    DeboaError::Io(IoError::Content { message: e.to_string(), source: Some(ErrorSource::new(e)) })
+}
+
+/// Read a body up to `limit` bytes.
+///
+/// # Arguments
+///
+/// * `body` - The body to read.
+/// * `limit` - The most bytes to read.
+///
+/// # Returns
+///
+/// * `(Bytes, bool)` - The bytes read, and `true` if they are the whole body.
+///
+pub(crate) async fn read_prefix(body: HttpBody, limit: usize) -> (Bytes, bool) {
+    let mut body = pin!(body);
+    let mut data = BytesMut::new();
+    while let Some(frame) = body.frame().await {
+        let Ok(frame) = frame else {
+            return (data.freeze(), false);
+        };
+        if let Ok(chunk) = frame.into_data() {
+            data.extend_from_slice(&chunk);
+            if data.len() > limit {
+                data.truncate(limit);
+                return (data.freeze(), false);
+            }
+        }
+    }
+    (data.freeze(), true)
+}
+
+/// Read a body in memory when it is at most `limit` bytes long.
+///
+/// # Arguments
+///
+/// * `body` - The body to read.
+/// * `limit` - The most bytes to read.
+///
+/// # Returns
+///
+/// * `Result<std::result::Result<Bytes, HttpBody>>` - The whole body or, once
+///   it is longer than `limit`, a body streaming the bytes read and then the
+///   rest of it.
+///
+pub(crate) async fn read_limited(
+    mut body: HttpBody,
+    limit: u64,
+) -> Result<std::result::Result<Bytes, HttpBody>> {
+    let mut data = BytesMut::new();
+    while let Some(frame) = body.frame().await {
+        let frame = frame.map_err(body_error)?;
+        if let Ok(chunk) = frame.into_data() {
+            data.extend_from_slice(&chunk);
+            if data.len() as u64 > limit {
+                return Ok(Err(HttpBody::from_generic_stream(PrefixedBody {
+                    prefix: Some(data.freeze()),
+                    body: Mutex::new(body),
+                })));
+            }
+        }
+    }
+    Ok(Ok(data.freeze()))
+}
+
+/// Body streaming bytes already read, then the rest of the body they were
+/// read from.
+struct PrefixedBody {
+    prefix: Option<Bytes>,
+    // Only accessed through `get_mut`, the lock makes the body `Sync`.
+    body: Mutex<HttpBody>,
+}
+
+impl Stream for PrefixedBody {
+    type Item = std::io::Result<Frame<Bytes>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if let Some(prefix) = this.prefix.take() {
+            return Poll::Ready(Some(Ok(Frame::data(prefix))));
+        }
+
+        let body = this
+            .body
+            .get_mut()
+            .unwrap_or_else(|e| e.into_inner());
+        Pin::new(body).poll_frame(cx)
+    }
+}
@@ -0,0 +1,439 @@
+//! Retry support for the Deboa HTTP client.
+//!
+//! This module provides the [`RetryPolicy`] used to retry requests that failed
+//! because of connection errors, timeouts or overloaded servers, and the
+//! [`Retry`] chain that applies a policy to any hook.
+//!
+//! # Features
+//!
+//! - Only idempotent methods are retried by default
+//! - Jittered exponential backoff
+//! - `Retry-After` support for 429 and 503 responses
+//! - A shared [`RetryBudget`] so retries cannot storm a failing service
+//!
+//! Only requests whose body can be replayed are retried, streaming bodies are
+//! sent once.
+//!
+//! # Examples
+//!
+//! ```ignore
+//! use deboa::retry::{Retry, RetryPolicy};
+//! use deboa_tokio::Client;
+//! use std::time::Duration;
+//!
+//! let policy = RetryPolicy::default()
+//!     .max_retries(5)
+//!     .base_delay(Duration::from_millis(200));
+//!
+//! // Either configure the client...
+//! let client = Client::builder()
+//!     .retry_policy(policy.clone())
+//!     .build();
+//!
+//! // ...or chain it as a hook.
+//! let client = Client::default().chain(Retry::new(policy));
+//! ```
+
+use crate::{
+    errors::{DeboaError, RequestError},
+    options,
+    request::DeboaRequest,
+    response::DeboaResponse,
+    Result,
+};
+use futures_timer::Delay;
+use http::{header, Method, StatusCode};
+use log::info;
+use rand::RngExt;
+use std::{
+    future::Future,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime},
+};
+use tackle::{Chain, Hook};
+
+/// Token bucket shared by every request using the same policy.
+///
+/// Each request deposits `ratio` tokens and each retry withdraws one, so once
+/// the initial `max_tokens` are spent retries are limited to `ratio` of the
+/// requests sent.
+#[derive(Debug, Clone)]
+pub struct RetryBudget {
+    ratio: f32,
+    max_tokens: f32,
+    tokens: Arc<Mutex<f32>>,
+}
+
+impl RetryBudget {
+    /// Create a new retry budget.
+    ///
+    /// # Arguments
+    ///
+    /// * `ratio` - Tokens deposited by every request.
+    /// * `max_tokens` - Maximum number of tokens, the budget starts full.
+    ///
+    /// # Returns
+    ///
+    /// * `RetryBudget` - The budget.
+    ///
+    pub fn new(ratio: f32, max_tokens: u32) -> Self {
+        Self {
+            ratio: ratio.max(0.0),
+            max_tokens: max_tokens as f32,
+            tokens: Arc::new(Mutex::new(max_tokens as f32)),
+        }
+    }
+
+    /// Deposit the tokens of a new request.
+    pub(crate) fn deposit(&self) {
+        if let Ok(mut tokens) = self.tokens.lock() {
+            *tokens = (*tokens + self.ratio).min(self.max_tokens);
+        }
+    }
+
+    /// Withdraw a token for a retry.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - `true` if the retry is allowed.
+    ///
+    pub(crate) fn withdraw(&self) -> bool {
+        match self.tokens.lock() {
+            Ok(mut tokens) if *tokens >= 1.0 => {
+                *tokens -= 1.0;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Default for RetryBudget {
+    fn default() -> Self {
+        Self::new(0.2, 10)
+    }
+}
+
+/// Policy used to decide whether, and when, a request is retried.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    max_retries: u32,
+    base_delay: Duration,
+    max_delay: Duration,
+    retry_non_idempotent: bool,
+    statuses: Vec<StatusCode>,
+    budget: RetryBudget,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(10),
+            retry_non_idempotent: false,
+            statuses: vec![
+                StatusCode::TOO_MANY_REQUESTS,
+                StatusCode::BAD_GATEWAY,
+                StatusCode::SERVICE_UNAVAILABLE,
+                StatusCode::GATEWAY_TIMEOUT,
+            ],
+            budget: RetryBudget::default(),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Set the maximum number of retries per request.
+    pub fn max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Set the delay used for the first retry, doubled on every attempt.
+    pub fn base_delay(mut self, base_delay: Duration) -> Self {
+        self.base_delay = base_delay;
+        self
+    }
+
+    /// Set the maximum delay between attempts.
+    ///
+    /// Responses asking for a longer `Retry-After` are not retried.
+    pub fn max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+
+    /// Allow retrying non idempotent methods such as POST and PATCH.
+    pub fn retry_non_idempotent(mut self, retry: bool) -> Self {
+        self.retry_non_idempotent = retry;
+        self
+    }
+
+    /// Set the response status codes that are retried.
+    pub fn statuses(mut self, statuses: Vec<StatusCode>) -> Self {
+        self.statuses = statuses;
+        self
+    }
+
+    /// Set the retry budget.
+    pub fn budget(mut self, budget: RetryBudget) -> Self {
+        self.budget = budget;
+        self
+    }
+
+    /// Check whether requests with the given method may be retried.
+    ///
+    /// # Arguments
+    ///
+    /// * `method` - The request method.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - `true` if the method can be retried.
+    ///
+    pub fn is_retryable(&self, method: &Method) -> bool {
+        self.retry_non_idempotent || is_idempotent(method)
+    }
+
+    /// Compute the delay before the next attempt, if the result is retryable.
+    ///
+    /// # Arguments
+    ///
+    /// * `result` - The result of the last attempt.
+    /// * `attempt` - The number of retries already made.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<Duration>` - The delay, or `None` if the result must be returned.
+    ///
+    pub fn retry_delay(&self, result: &Result<DeboaResponse>, attempt: u32) -> Option<Duration> {
+        match result {
+            Err(DeboaError::Connection(_))
+            | Err(DeboaError::Request(RequestError::Send { .. }))
+            | Err(DeboaError::Request(RequestError::Timeout { .. })) => Some(self.backoff(attempt)),
+            Ok(response)
+                if self
+                    .statuses
+                    .contains(&response.status()) =>
+            {
+                match retry_after(response) {
+                    Some(delay) if delay > self.max_delay => None,
+                    Some(delay) => Some(delay),
+                    None => Some(self.backoff(attempt)),
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Exponential backoff with jitter, between half and the full delay.
+    fn backoff(&self, attempt: u32) -> Duration {
+        let delay = self
+            .base_delay
+            .saturating_mul(1u32 << attempt.min(16))
+            .min(self.max_delay);
+        let half = delay / 2;
+        let jitter = rand::rng().random_range(0..=half.as_millis() as u64);
+        half + Duration::from_millis(jitter)
+    }
+
+    /// Send a request, retrying it according to the policy.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - The request to send.
+    /// * `send` - Function sending a single attempt.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<DeboaResponse>` - The result of the last attempt.
+    ///
+    pub(crate) async fn execute<F, Fut>(
+        &self,
+        request: DeboaRequest,
+        send: F,
+    ) -> Result<DeboaResponse>
+    where
+        F: Fn(DeboaRequest) -> Fut,
+        Fut: Future<Output = Result<DeboaResponse>>,
+    {
+        self.budget
+            .deposit();
+
+        if !self.is_retryable(request.method()) {
+            return send(request).await;
+        }
+
+        let mut request = request;
+        let mut attempt = 0;
+        loop {
+            let replay = request
+                .try_clone()
+                .ok();
+            let method = request
+                .method()
+                .clone();
+            let uri = request
+                .uri()
+                .clone();
+
+            let result = send(request).await;
+
+            let Some(next) = replay else {
+                return result;
+            };
+
+            if attempt >= self.max_retries {
+                return result;
+            }
+
+            let Some(delay) = self.retry_delay(&result, attempt) else {
+                return result;
+            };
+
+            // A retry that cannot start before the deadline is not worth it.
+            if next
+                .options()
+                .and_then(|options| options.deadline)
+                .is_some_and(|deadline| options::remaining(deadline) <= delay)
+            {
+                return result;
+            }
+
+            if !self
+                .budget
+                .withdraw()
+            {
+                return result;
+            }
+
+            drop(result);
+            attempt += 1;
+            info!("Retrying {} {} in {:?} (attempt {})", method, uri, delay, attempt);
+            Delay::new(delay).await;
+            request = next;
+        }
+    }
+}
+
+/// Check whether a method is idempotent, as defined in RFC 9110, section 9.2.2.
+///
+/// # Arguments
+///
+/// * `method` - The request method.
+///
+/// # Returns
+///
+/// * `bool` - `true` if the method is idempotent.
+///
+pub(crate) fn is_idempotent(method: &Method) -> bool {
+    matches!(
+        *method,
+        Method::GET | Method::HEAD | Method::OPTIONS | Method::TRACE | Method::PUT | Method::DELETE
+    )
+}
+
+/// Parse the `Retry-After` header of a response.
+///
+/// # Arguments
+///
+/// * `response` - The response.
+///
+/// # Returns
+///
+/// * `Option<Duration>` - The delay requested by the server.
+///
+pub fn retry_after(response: &DeboaResponse) -> Option<Duration> {
+    let value = response
+        .headers()
+        .get(header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim();
+
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    let date = httpdate::parse_http_date(value).ok()?;
+    Some(
+        date.duration_since(SystemTime::now())
+            .unwrap_or(Duration::ZERO),
+    )
+}
+
+/// Chain retrying requests according to a [`RetryPolicy`].
+#[derive(Debug, Clone, Default)]
+pub struct Retry {
+    policy: RetryPolicy,
+}
+
+impl Retry {
+    /// Create a new retry chain.
+    ///
+    /// # Arguments
+    ///
+    /// * `policy` - The retry policy.
+    ///
+    /// # Returns
+    ///
+    /// * `Retry` - The chain.
+    ///
+    pub fn new(policy: RetryPolicy) -> Self {
+        Self { policy }
+    }
+}
+
+impl<H> Chain<H, DeboaError, DeboaRequest, DeboaResponse> for Retry
+where
+    H: Hook<DeboaRequest, DeboaResponse, Result = Result<DeboaResponse>>,
+{
+    type Hook = RetryHook<H>;
+
+    fn chain(&self, hook: H) -> Self::Hook {
+        RetryHook { inner: hook, policy: self.policy.clone() }
+    }
+}
+
+/// Hook created by the [`Retry`] chain.
+pub struct RetryHook<H> {
+    inner: H,
+    policy: RetryPolicy,
+}
+
+impl<H> Hook<DeboaRequest, DeboaResponse> for RetryHook<H>
+where
+    H: Hook<DeboaRequest, DeboaResponse, Result = Result<DeboaResponse>>,
+{
+    type Result = Result<DeboaResponse>;
+    type Error = DeboaError;
+
+    async fn call(&self, mut request: DeboaRequest) -> Self::Result {
+        let deadline = options::start(&mut request);
+        let method = request
+            .method()
+            .clone();
+        let uri = request
+            .uri()
+            .clone();
+        let policy = request
+            .options()
+            .and_then(|options| {
+                options
+                    .retry_policy
+                    .clone()
+            })
+            .unwrap_or_else(|| self.policy.clone());
+
+        options::with_deadline(deadline, &method, &uri, async {
+            policy
+                .execute(request, |request| {
+                    self.inner
+                        .call(request)
+                })
+                .await
+        })
+        .await
+    }
+}
@@ -0,0 +1,256 @@
+//! Status code handling for the Deboa HTTP client.
+//!
+//! A response with an error status is returned as any other response. This
+//! module provides the [`ErrorForStatus`] chain, which turns responses whose
+//! status falls in chosen ranges into a [`ResponseError::Status`], keeping the
+//! status, the headers and the start of the body.
+//!
+//! The body can also be deserialized into a typed error, through any
+//! [`ResponseBody`] implementation, and read back with
+//! [`DeboaError::error_body`].
+//!
+//! # Examples
+//!
+//! ```ignore
+//! use deboa::status::ErrorForStatus;
+//! use deboa_extras::http::serde::json::JsonBody;
+//! use deboa_tokio::Client;
+//!
+//! #[derive(serde::Deserialize)]
+//! struct ApiError {
+//!     code: String,
+//! }
+//!
+//! let client = Client::default().chain(
+//!     ErrorForStatus::ranges([400..=499]).error_body::<ApiError, _>(JsonBody),
+//! );
+//!
+//! if let Err(e) = get("https://api.example.com/users/1")?.send_with(&client).await {
+//!     if let Some(error) = e.error_body::<ApiError>() {
+//!         eprintln!("{}: {}", e.status().unwrap(), error.code);
+//!     }
+//! }
+//! ```
+
+use crate::{
+    errors::{DeboaError, ErrorBody, ResponseError},
+    request::DeboaRequest,
+    response::{read_prefix, DeboaResponse, DEFAULT_BODY_PREVIEW_SIZE},
+    serde::ResponseBody,
+    Result,
+};
+use http::StatusCode;
+use serde::Deserialize;
+use std::{fmt, ops::RangeInclusive, sync::Arc};
+use tackle::{Chain, Hook};
+
+/// Default largest body read to deserialize a typed error.
+pub const DEFAULT_MAX_ERROR_BODY_SIZE: usize = 64 * 1024;
+
+type Deserializer = Arc<dyn Fn(Vec<u8>) -> Option<ErrorBody> + Send + Sync>;
+
+/// Chain turning responses with chosen statuses into errors.
+#[derive(Clone)]
+pub struct ErrorForStatus {
+    ranges: Vec<RangeInclusive<u16>>,
+    max_preview_size: usize,
+    max_body_size: usize,
+    deserializer: Option<Deserializer>,
+}
+
+impl ErrorForStatus {
+    /// Create a new chain for client and server errors, 400 to 599.
+    ///
+    /// # Returns
+    ///
+    /// * `ErrorForStatus` - The chain.
+    ///
+    pub fn new() -> Self {
+        Self::ranges([400..=599])
+    }
+
+    /// Create a new chain for the given status ranges.
+    ///
+    /// # Arguments
+    ///
+    /// * `ranges` - The status ranges, like `400..=499`.
+    ///
+    /// # Returns
+    ///
+    /// * `ErrorForStatus` - The chain.
+    ///
+    pub fn ranges<I: IntoIterator<Item = RangeInclusive<u16>>>(ranges: I) -> Self {
+        Self {
+            ranges: ranges
+                .into_iter()
+                .collect(),
+            max_preview_size: DEFAULT_BODY_PREVIEW_SIZE,
+            max_body_size: DEFAULT_MAX_ERROR_BODY_SIZE,
+            deserializer: None,
+        }
+    }
+
+    /// Allow set the number of body bytes kept in the error.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_preview_size` - The size in bytes.
+    ///
+    /// # Returns
+    ///
+    /// * `ErrorForStatus` - The chain.
+    ///
+    pub fn max_preview_size(mut self, max_preview_size: usize) -> Self {
+        self.max_preview_size = max_preview_size;
+        self
+    }
+
+    /// Allow set the largest body read to deserialize a typed error.
+    /// Larger bodies are not deserialized.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_body_size` - The size in bytes.
+    ///
+    /// # Returns
+    ///
+    /// * `ErrorForStatus` - The chain.
+    ///
+    pub fn max_body_size(mut self, max_body_size: usize) -> Self {
+        self.max_body_size = max_body_size;
+        self
+    }
+
+    /// Allow deserialize the body of matched responses as a `T`.
+    /// A body that fails to deserialize leaves the error without one.
+    ///
+    /// # Arguments
+    ///
+    /// * `body_type` - The body type to be deserialized.
+    ///
+    /// # Returns
+    ///
+    /// * `ErrorForStatus` - The chain.
+    ///
+    pub fn error_body<T, B>(mut self, body_type: B) -> Self
+    where
+        T: for<'a> Deserialize<'a> + Send + Sync + 'static,
+        B: ResponseBody + Send + Sync + 'static,
+    {
+        self.deserializer = Some(Arc::new(move |bytes| {
+            body_type
+                .deserialize::<T>(bytes)
+                .ok()
+                .map(ErrorBody::new)
+        }));
+        self
+    }
+
+    /// Check if a status is turned into an error.
+    ///
+    /// # Arguments
+    ///
+    /// * `status` - The status.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - True if the status falls in one of the ranges.
+    ///
+    pub fn matches(&self, status: StatusCode) -> bool {
+        self.ranges
+            .iter()
+            .any(|range| range.contains(&status.as_u16()))
+    }
+
+    async fn status_error(&self, response: DeboaResponse) -> DeboaError {
+        let status = response.status();
+        let (parts, body) = response.into_parts();
+        let limit = match self.deserializer {
+            Some(_) => self
+                .max_body_size
+                .max(self.max_preview_size),
+            None => self.max_preview_size,
+        };
+        let (body, complete) = read_prefix(body, limit).await;
+        let details = match &self.deserializer {
+            Some(deserializer) if complete && body.len() <= self.max_body_size => {
+                deserializer(body.to_vec())
+            }
+            _ => None,
+        };
+        DeboaError::Response(ResponseError::Status {
+            status,
+            headers: Box::new(parts.headers),
+            body: body.slice(
+                ..body
+                    .len()
+                    .min(self.max_preview_size),
+            ),
+            details,
+        })
+    }
+}
+
+impl Default for ErrorForStatus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for ErrorForStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ErrorForStatus")
+            .field("ranges", &self.ranges)
+            .field("max_preview_size", &self.max_preview_size)
+            .field("max_body_size", &self.max_body_size)
+            .field(
+                "error_body",
+                &self
+                    .deserializer
+                    .is_some(),
+            )
+            .finish()
+    }
+}
+
+impl<H> Chain<H, DeboaError, DeboaRequest, DeboaResponse> for ErrorForStatus
+where
+    H: Hook<DeboaRequest, DeboaResponse, Result = Result<DeboaResponse>>,
+{
+    type Hook = ErrorForStatusHook<H>;
+
+    fn chain(&self, hook: H) -> Self::Hook {
+        ErrorForStatusHook { inner: hook, chain: self.clone() }
+    }
+}
+
+/// Hook created by the [`ErrorForStatus`] chain.
+pub struct ErrorForStatusHook<H> {
+    inner: H,
+    chain: ErrorForStatus,
+}
+
+impl<H> Hook<DeboaRequest, DeboaResponse> for ErrorForStatusHook<H>
+where
+    H: Hook<DeboaRequest, DeboaResponse, Result = Result<DeboaResponse>>,
+{
+    type Result = Result<DeboaResponse>;
+    type Error = DeboaError;
+
+    async fn call(&self, request: DeboaRequest) -> Self::Result {
+        let response = self
+            .inner
+            .call(request)
+            .await?;
+        if !self
+            .chain
+            .matches(response.status())
+        {
+            return Ok(response);
+        }
+        Err(self
+            .chain
+            .status_error(response)
+            .await)
+    }
+}
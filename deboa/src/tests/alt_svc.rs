@@ -0,0 +1,143 @@
+use crate::alt_svc::{self, AltSvc, AltSvcCache, DEFAULT_MAX_AGE};
+use caramelo::{expect, matchers::eq};
+use http::{header, HeaderMap, HeaderValue};
+use std::time::Duration;
+
+fn headers(values: &[&'static str]) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    for value in values {
+        headers.append(header::ALT_SVC, HeaderValue::from_static(value));
+    }
+    headers
+}
+
+#[test]
+fn test_parse() {
+    let AltSvc::Services(services) =
+        alt_svc::parse(r#"h3=":443"; ma=3600, h3-29="alt.example.com:8443", h2="[::1]:443""#)
+    else {
+        panic!("expected services");
+    };
+
+    expect(services.len()).to_be(eq(3));
+    expect(services[0].protocol()).to_be(eq("h3"));
+    expect(services[0].host()).to_be(eq(None));
+    expect(services[0].port()).to_be(eq(443));
+    expect(services[0].max_age()).to_be(eq(Duration::from_secs(3600)));
+    expect(services[1].host()).to_be(eq(Some("alt.example.com")));
+    expect(services[1].port()).to_be(eq(8443));
+    expect(services[1].max_age()).to_be(eq(DEFAULT_MAX_AGE));
+    expect(services[2].host()).to_be(eq(Some("::1")));
+}
+
+#[test]
+fn test_parse_clear_and_invalid() {
+    expect(alt_svc::parse("clear")).to_be(eq(AltSvc::Clear));
+    expect(alt_svc::parse(r#"h3, ="":443", h3=":port""#)).to_be(eq(AltSvc::Services(vec![])));
+}
+
+#[test]
+fn test_cache_update() {
+    let cache = AltSvcCache::default();
+    cache.update("example.com", 443, &headers(&[r#"h2=":443", h3=":8443""#]));
+
+    let alternative = cache
+        .alternative("Example.com", 443)
+        .unwrap();
+    expect(alternative.port()).to_be(eq(8443));
+    expect(
+        cache
+            .alternative("example.com", 8443)
+            .is_none(),
+    )
+    .to_be(eq(true));
+
+    // A response without Alt-Svc keeps the alternative.
+    cache.update("example.com", 443, &HeaderMap::new());
+    expect(
+        cache
+            .alternative("example.com", 443)
+            .is_some(),
+    )
+    .to_be(eq(true));
+
+    cache.update("example.com", 443, &headers(&["clear"]));
+    expect(
+        cache
+            .alternative("example.com", 443)
+            .is_none(),
+    )
+    .to_be(eq(true));
+}
+
+#[test]
+fn test_cache_max_age() {
+    let cache = AltSvcCache::default();
+    cache.update("example.com", 443, &headers(&[r#"h3=":443"; ma=0"#]));
+    expect(
+        cache
+            .alternative("example.com", 443)
+            .is_none(),
+    )
+    .to_be(eq(true));
+}
+
+#[test]
+fn test_cache_broken() {
+    let cache = AltSvcCache::default();
+    cache.update("example.com", 443, &headers(&[r#"h3=":443""#]));
+    cache.mark_broken("example.com", 443);
+    expect(cache.is_broken("example.com", 443)).to_be(eq(true));
+    expect(
+        cache
+            .alternative("example.com", 443)
+            .is_none(),
+    )
+    .to_be(eq(true));
+
+    // Advertised again, it stays broken.
+    cache.update("example.com", 443, &headers(&[r#"h3=":443""#]));
+    expect(cache.is_broken("example.com", 443)).to_be(eq(true));
+
+    let cache = AltSvcCache::new(Duration::ZERO);
+    cache.update("example.com", 443, &headers(&[r#"h3=":443""#]));
+    cache.mark_broken("example.com", 443);
+    expect(
+        cache
+            .alternative("example.com", 443)
+            .is_some(),
+    )
+    .to_be(eq(true));
+}
+
+#[test]
+fn test_cache_broken_origin() {
+    // HTTP/3 failing on an origin without alternative is remembered too.
+    let cache = AltSvcCache::default();
+    cache.mark_broken("example.com", 443);
+    expect(cache.is_broken("example.com", 443)).to_be(eq(true));
+    expect(
+        cache
+            .alternative("example.com", 443)
+            .is_none(),
+    )
+    .to_be(eq(true));
+
+    // Advertised afterwards, the alternative is broken as well.
+    cache.update("example.com", 443, &headers(&[r#"h3=":443""#]));
+    expect(cache.is_broken("example.com", 443)).to_be(eq(true));
+    expect(
+        cache
+            .alternative("example.com", 443)
+            .is_none(),
+    )
+    .to_be(eq(true));
+
+    // Withdrawn, the alternative is gone but the origin stays broken.
+    cache.update("example.com", 443, &headers(&["clear"]));
+    expect(cache.is_broken("example.com", 443)).to_be(eq(true));
+
+    let cache = AltSvcCache::new(Duration::ZERO);
+    cache.mark_broken("example.com", 443);
+    expect(cache.is_broken("example.com", 443)).to_be(eq(false));
+}
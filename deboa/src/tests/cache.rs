@@ -1,8 +1,437 @@
+use crate::{
+    cache::{Cache, CacheControl, CachedResponse, DeboaCache, MemoryCache},
+    errors::DeboaError,
+    request::DeboaRequest,
+    response::DeboaResponse,
+    tests::TEST_URL,
+    Result, TestResult,
+};
+use bytes::Bytes;
+use caramelo::{expect, matchers::eq};
+use futures::executor::block_on;
+use http::{header, HeaderMap, HeaderValue, StatusCode};
+use http_body::Frame;
+use http_body_util::BodyExt;
+use hyper_body_utils::HttpBody;
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    rc::Rc,
+    time::{Duration, SystemTime},
+};
+use tackle::{Chain, Hook};
+
+/// Hook answering with queued responses and recording the requests headers.
+struct ScriptedHook {
+    responses: RefCell<VecDeque<DeboaResponse>>,
+    requests: Rc<RefCell<Vec<HeaderMap>>>,
+}
+
+impl ScriptedHook {
+    fn new(responses: Vec<DeboaResponse>) -> (Self, Rc<RefCell<Vec<HeaderMap>>>) {
+        let requests = Rc::new(RefCell::new(Vec::new()));
+        let hook = Self { responses: RefCell::new(responses.into()), requests: requests.clone() };
+        (hook, requests)
+    }
+}
+
+impl Hook<DeboaRequest, DeboaResponse> for ScriptedHook {
+    type Result = Result<DeboaResponse>;
+    type Error = DeboaError;
+
+    async fn call(&self, request: DeboaRequest) -> Self::Result {
+        self.requests
+            .borrow_mut()
+            .push(
+                request
+                    .headers()
+                    .clone(),
+            );
+        Ok(self
+            .responses
+            .borrow_mut()
+            .pop_front()
+            .expect("unexpected request"))
+    }
+}
+
+fn response(cache_control: &str, body: &str) -> DeboaResponse {
+    DeboaResponse::builder()
+        .status(StatusCode::OK)
+        .header(header::CACHE_CONTROL, cache_control)
+        .body(body.as_bytes())
+        .build()
+}
+
+fn cached(headers: &[(header::HeaderName, &str)]) -> CachedResponse {
+    let mut map = HeaderMap::new();
+    for (name, value) in headers {
+        map.insert(name.clone(), HeaderValue::from_str(value).unwrap());
+    }
+    let now = SystemTime::now();
+    CachedResponse::new(StatusCode::OK, map, Bytes::from_static(b"hello"), now, now)
+}
+
+fn body(response: DeboaResponse) -> Result<String> {
+    let bytes = block_on(response.bytes())?;
+    Ok(String::from_utf8(bytes).unwrap())
+}
+
+#[test]
+fn test_cache_put() {
+    let cache = MemoryCache::default();
+    block_on(cache.put("GET /", cached(&[])));
+    expect(cache.len()).to_be(eq(1));
+}
+
+#[test]
+fn test_cache_get() {
+    let cache = MemoryCache::default();
+    expect(block_on(cache.get("GET /")).is_none()).to_be(eq(true));
+
+    block_on(cache.put("GET /", cached(&[])));
+    let stored = block_on(cache.get("GET /")).unwrap();
+    expect(stored.body()).to_be(eq(&Bytes::from_static(b"hello")));
+}
+
+#[test]
+fn test_cache_remove() {
+    let cache = MemoryCache::default();
+    block_on(cache.put("GET /", cached(&[])));
+    block_on(cache.remove("GET /"));
+    expect(cache.is_empty()).to_be(eq(true));
+}
+
+#[test]
+fn test_cache_evicts_least_recently_used() {
+    let cache = MemoryCache::new(2);
+    block_on(cache.put("GET /a", cached(&[])));
+    block_on(cache.put("GET /b", cached(&[])));
+    block_on(cache.get("GET /a"));
+    block_on(cache.put("GET /c", cached(&[])));
+
+    expect(cache.len()).to_be(eq(2));
+    expect(block_on(cache.get("GET /a")).is_some()).to_be(eq(true));
+    expect(block_on(cache.get("GET /b")).is_none()).to_be(eq(true));
+}
+
+#[test]
+fn test_cache_control() {
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        header::CACHE_CONTROL,
+        HeaderValue::from_static("private, max-age=60, stale-while-revalidate=\"30\""),
+    );
+    let cache_control = CacheControl::from_headers(&headers);
+    expect(cache_control.private).to_be(eq(true));
+    expect(cache_control.max_age).to_be(eq(Some(Duration::from_secs(60))));
+    expect(cache_control.stale_while_revalidate).to_be(eq(Some(Duration::from_secs(30))));
+    expect(cache_control.no_store).to_be(eq(false));
+}
+
+#[test]
+fn test_freshness_lifetime() {
+    let stored = cached(&[(header::CACHE_CONTROL, "max-age=60, s-maxage=10")]);
+    expect(stored.freshness_lifetime(false)).to_be(eq(Some(Duration::from_secs(60))));
+    expect(stored.freshness_lifetime(true)).to_be(eq(Some(Duration::from_secs(10))));
+
+    let stored = cached(&[
+        (header::DATE, "Sun, 06 Nov 1994 08:49:37 GMT"),
+        (header::EXPIRES, "Sun, 06 Nov 1994 08:50:37 GMT"),
+    ]);
+    expect(stored.freshness_lifetime(false)).to_be(eq(Some(Duration::from_secs(60))));
+
+    let stored = cached(&[(header::EXPIRES, "0")]);
+    expect(stored.freshness_lifetime(false)).to_be(eq(Some(Duration::ZERO)));
+
+    let stored = cached(&[
+        (header::DATE, "Sun, 06 Nov 1994 08:49:37 GMT"),
+        (header::LAST_MODIFIED, "Sun, 06 Nov 1994 08:32:57 GMT"),
+    ]);
+    expect(stored.freshness_lifetime(false)).to_be(eq(Some(Duration::from_secs(100))));
+
+    expect(cached(&[]).freshness_lifetime(false)).to_be(eq(None));
+}
+
+#[test]
+fn test_age() {
+    let stored = cached(&[(header::AGE, "30")]);
+    let later = stored.response_time() + Duration::from_secs(10);
+    expect(stored.age(later)).to_be(eq(Duration::from_secs(40)));
+}
+
 #[test]
-fn test_cache_put() {}
+fn test_cache_hook_serves_fresh_response() -> TestResult<()> {
+    let (inner, requests) = ScriptedHook::new(vec![response("max-age=60", "hello")]);
+    let hook = Cache::new(MemoryCache::default()).chain(inner);
+
+    let first = block_on(hook.call(DeboaRequest::get(TEST_URL)?.build()?))?;
+    expect(body(first)?).to_be(eq("hello".to_string()));
+
+    let second = block_on(hook.call(DeboaRequest::get(TEST_URL)?.build()?))?;
+    expect(
+        second
+            .headers()
+            .contains_key(header::AGE),
+    )
+    .to_be(eq(true));
+    expect(body(second)?).to_be(eq("hello".to_string()));
+    expect(
+        requests
+            .borrow()
+            .len(),
+    )
+    .to_be(eq(1));
+    Ok(())
+}
+
+#[test]
+fn test_cache_hook_skips_no_store() -> TestResult<()> {
+    let (inner, requests) =
+        ScriptedHook::new(vec![response("no-store", "one"), response("no-store", "two")]);
+    let hook = Cache::new(MemoryCache::default()).chain(inner);
+
+    block_on(hook.call(DeboaRequest::get(TEST_URL)?.build()?))?;
+    let second = block_on(hook.call(DeboaRequest::get(TEST_URL)?.build()?))?;
+    expect(body(second)?).to_be(eq("two".to_string()));
+    expect(
+        requests
+            .borrow()
+            .len(),
+    )
+    .to_be(eq(2));
+    Ok(())
+}
+
+/// Response with a streamed body and no `Content-Length`.
+fn chunked_response(chunks: &[&'static str]) -> DeboaResponse {
+    let frames: Vec<_> = chunks
+        .iter()
+        .map(|chunk| Ok::<_, std::io::Error>(Frame::data(Bytes::from_static(chunk.as_bytes()))))
+        .collect();
+    DeboaResponse::new(
+        http::Response::builder()
+            .header(header::CACHE_CONTROL, "max-age=60")
+            .body(HttpBody::from_generic_stream(futures::stream::iter(frames)))
+            .unwrap(),
+    )
+}
+
+#[test]
+fn test_cache_hook_passes_through_large_chunked_body() -> TestResult<()> {
+    let chunks = ["hello ", "streaming ", "deboa"];
+    let (inner, requests) =
+        ScriptedHook::new(vec![chunked_response(&chunks), chunked_response(&chunks)]);
+    let hook = Cache::new(MemoryCache::default())
+        .max_body_size(8)
+        .chain(inner);
+
+    let first = block_on(hook.call(DeboaRequest::get(TEST_URL)?.build()?))?;
+    expect(body(first)?).to_be(eq("hello streaming deboa".to_string()));
+
+    let second = block_on(hook.call(DeboaRequest::get(TEST_URL)?.build()?))?;
+    expect(body(second)?).to_be(eq("hello streaming deboa".to_string()));
+    expect(
+        requests
+            .borrow()
+            .len(),
+    )
+    .to_be(eq(2));
+    Ok(())
+}
+
+#[test]
+fn test_cache_hook_streams_large_chunked_body() -> TestResult<()> {
+    // The body never ends, it must be passed through once too large.
+    let frames = ["hello ", "streaming ", "deboa"]
+        .map(|chunk| Ok::<_, std::io::Error>(Frame::data(Bytes::from_static(chunk.as_bytes()))));
+    let stream =
+        futures::StreamExt::chain(futures::stream::iter(frames), futures::stream::pending());
+    let response = DeboaResponse::new(
+        http::Response::builder()
+            .header(header::CACHE_CONTROL, "max-age=60")
+            .body(HttpBody::from_generic_stream(stream))
+            .unwrap(),
+    );
+    let (inner, _) = ScriptedHook::new(vec![response]);
+    let hook = Cache::new(MemoryCache::default())
+        .max_body_size(8)
+        .chain(inner);
+
+    let response = block_on(hook.call(DeboaRequest::get(TEST_URL)?.build()?))?;
+    let mut body = response.inner_body();
+    let frame = block_on(body.frame())
+        .unwrap()
+        .unwrap();
+    expect(
+        frame
+            .into_data()
+            .unwrap(),
+    )
+    .to_be(eq(Bytes::from_static(b"hello streaming ")));
+    let frame = block_on(body.frame())
+        .unwrap()
+        .unwrap();
+    expect(
+        frame
+            .into_data()
+            .unwrap(),
+    )
+    .to_be(eq(Bytes::from_static(b"deboa")));
+    Ok(())
+}
+
+#[test]
+fn test_cache_hook_skips_large_content_length() -> TestResult<()> {
+    // The body never ends, it must not be read.
+    let response = DeboaResponse::new(
+        http::Response::builder()
+            .header(header::CACHE_CONTROL, "max-age=60")
+            .header(header::CONTENT_LENGTH, "1024")
+            .body(HttpBody::from_generic_stream(futures::stream::pending::<
+                std::io::Result<Frame<Bytes>>,
+            >()))
+            .unwrap(),
+    );
+    let (inner, _) = ScriptedHook::new(vec![response]);
+    let hook = Cache::new(MemoryCache::default())
+        .max_body_size(8)
+        .chain(inner);
+
+    let response = block_on(hook.call(DeboaRequest::get(TEST_URL)?.build()?))?;
+    expect(
+        response
+            .headers()
+            .get(header::CONTENT_LENGTH)
+            .unwrap(),
+    )
+    .to_be(eq(&HeaderValue::from_static("1024")));
+    Ok(())
+}
+
+#[test]
+fn test_cache_hook_revalidates_with_etag() -> TestResult<()> {
+    let stale = DeboaResponse::builder()
+        .status(StatusCode::OK)
+        .header(header::CACHE_CONTROL, "max-age=0")
+        .header(header::ETAG, "\"v1\"")
+        .body(b"hello".as_ref())
+        .build();
+    let not_modified = DeboaResponse::builder()
+        .status(StatusCode::NOT_MODIFIED)
+        .header(header::CACHE_CONTROL, "max-age=60")
+        .build();
+    let (inner, requests) = ScriptedHook::new(vec![stale, not_modified]);
+    let hook = Cache::new(MemoryCache::default()).chain(inner);
+
+    block_on(hook.call(DeboaRequest::get(TEST_URL)?.build()?))?;
+    let second = block_on(hook.call(DeboaRequest::get(TEST_URL)?.build()?))?;
+    expect(second.status()).to_be(eq(StatusCode::OK));
+    expect(body(second)?).to_be(eq("hello".to_string()));
+    expect(requests.borrow()[1].get(header::IF_NONE_MATCH))
+        .to_be(eq(Some(&HeaderValue::from_static("\"v1\""))));
+
+    // The 304 refreshed the stored response, it's fresh now.
+    block_on(hook.call(DeboaRequest::get(TEST_URL)?.build()?))?;
+    expect(
+        requests
+            .borrow()
+            .len(),
+    )
+    .to_be(eq(2));
+    Ok(())
+}
+
+#[test]
+fn test_cache_hook_stale_while_revalidate() -> TestResult<()> {
+    let stale = DeboaResponse::builder()
+        .status(StatusCode::OK)
+        .header(header::CACHE_CONTROL, "max-age=0, stale-while-revalidate=60")
+        .header(header::ETAG, "\"v1\"")
+        .body(b"hello".as_ref())
+        .build();
+    let not_modified = DeboaResponse::builder()
+        .status(StatusCode::NOT_MODIFIED)
+        .build();
+    let (inner, requests) = ScriptedHook::new(vec![stale, not_modified]);
+    let hook = Cache::new(MemoryCache::default()).chain(inner);
+
+    block_on(hook.call(DeboaRequest::get(TEST_URL)?.build()?))?;
+    let second = block_on(hook.call(DeboaRequest::get(TEST_URL)?.build()?))?;
+    expect(body(second)?).to_be(eq("hello".to_string()));
+    expect(
+        requests
+            .borrow()
+            .len(),
+    )
+    .to_be(eq(1));
+
+    let third = block_on(hook.call(DeboaRequest::get(TEST_URL)?.build()?))?;
+    expect(body(third)?).to_be(eq("hello".to_string()));
+    expect(
+        requests
+            .borrow()
+            .len(),
+    )
+    .to_be(eq(2));
+    Ok(())
+}
 
 #[test]
-fn test_cache_get() {}
+fn test_cache_hook_vary_mismatch() -> TestResult<()> {
+    let english = DeboaResponse::builder()
+        .status(StatusCode::OK)
+        .header(header::CACHE_CONTROL, "max-age=60")
+        .header(header::VARY, "Accept-Language")
+        .body(b"hello".as_ref())
+        .build();
+    let (inner, requests) = ScriptedHook::new(vec![english, response("max-age=60", "olá")]);
+    let hook = Cache::new(MemoryCache::default()).chain(inner);
+
+    let request = DeboaRequest::get(TEST_URL)?
+        .header(header::ACCEPT_LANGUAGE, "en")
+        .build()?;
+    block_on(hook.call(request))?;
+
+    let request = DeboaRequest::get(TEST_URL)?
+        .header(header::ACCEPT_LANGUAGE, "pt")
+        .build()?;
+    let second = block_on(hook.call(request))?;
+    expect(body(second)?).to_be(eq("olá".to_string()));
+    expect(
+        requests
+            .borrow()
+            .len(),
+    )
+    .to_be(eq(2));
+    Ok(())
+}
 
 #[test]
-fn test_cache_remove() {}
+fn test_cache_hook_invalidates_on_post() -> TestResult<()> {
+    let created = DeboaResponse::builder()
+        .status(StatusCode::CREATED)
+        .build();
+    let (inner, requests) = ScriptedHook::new(vec![
+        response("max-age=60", "one"),
+        created,
+        response("max-age=60", "two"),
+    ]);
+    let hook = Cache::new(MemoryCache::default()).chain(inner);
+
+    block_on(hook.call(DeboaRequest::get(TEST_URL)?.build()?))?;
+    let request = DeboaRequest::post(TEST_URL)?
+        .text("hello")
+        .build()?;
+    block_on(hook.call(request))?;
+
+    let third = block_on(hook.call(DeboaRequest::get(TEST_URL)?.build()?))?;
+    expect(body(third)?).to_be(eq("two".to_string()));
+    expect(
+        requests
+            .borrow()
+            .len(),
+    )
+    .to_be(eq(3));
+    Ok(())
+}
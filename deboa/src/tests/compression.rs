@@ -0,0 +1,267 @@
+use crate::{
+    compression::{self, Encoding},
+    request::DeboaRequest,
+    tests::TEST_URL,
+    TestResult,
+};
+use caramelo::{expect, matchers::eq};
+use http::header;
+
+#[cfg(any(feature = "gzip", not(feature = "zstd")))]
+use crate::errors::{DeboaError, IoError};
+#[cfg(feature = "gzip")]
+use {
+    crate::response::DeboaResponse,
+    bytes::Bytes,
+    futures::{executor::block_on, io::AsyncReadExt},
+    http::StatusCode,
+    http_body::Frame,
+    http_body_util::BodyExt,
+    hyper_body_utils::HttpBody,
+};
+
+#[cfg(feature = "gzip")]
+fn gzip(data: &[u8]) -> Vec<u8> {
+    let mut encoded = Vec::new();
+    block_on(async_compression::futures::bufread::GzipEncoder::new(data).read_to_end(&mut encoded))
+        .unwrap();
+    encoded
+}
+
+#[cfg(feature = "gzip")]
+fn gunzip(data: &[u8]) -> Vec<u8> {
+    let mut decoded = Vec::new();
+    block_on(async_compression::futures::bufread::GzipDecoder::new(data).read_to_end(&mut decoded))
+        .unwrap();
+    decoded
+}
+
+#[cfg(feature = "gzip")]
+fn request_body(request: DeboaRequest) -> Vec<u8> {
+    let body = block_on(
+        request
+            .body()
+            .into_body()
+            .collect(),
+    )
+    .unwrap();
+    body.to_bytes()
+        .to_vec()
+}
+
+#[cfg(feature = "gzip")]
+fn encoded_response(encoding: &str, body: Vec<u8>) -> DeboaResponse {
+    DeboaResponse::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_ENCODING, encoding)
+        .header(
+            header::CONTENT_LENGTH,
+            &body
+                .len()
+                .to_string(),
+        )
+        .body(body)
+        .build()
+}
+
+#[test]
+fn test_encoding_from_name() {
+    expect(Encoding::from_name("gzip")).to_be(eq(Some(Encoding::Gzip)));
+    expect(Encoding::from_name(" X-GZIP ")).to_be(eq(Some(Encoding::Gzip)));
+    expect(Encoding::from_name("br")).to_be(eq(Some(Encoding::Brotli)));
+    expect(Encoding::from_name("zstd")).to_be(eq(Some(Encoding::Zstd)));
+    expect(Encoding::from_name("compress")).to_be(eq(None));
+}
+
+#[test]
+fn test_accept_encoding() -> TestResult<()> {
+    let mut request = DeboaRequest::get(TEST_URL)?.build()?;
+    let decompress = compression::prepare(&mut request);
+
+    let enabled = Encoding::ALL
+        .iter()
+        .any(Encoding::is_enabled);
+    expect(decompress).to_be(eq(enabled));
+    expect(
+        request
+            .headers()
+            .get(header::ACCEPT_ENCODING)
+            .cloned(),
+    )
+    .to_be(eq(compression::accept_encoding()));
+    Ok(())
+}
+
+#[test]
+fn test_accept_encoding_opt_out() -> TestResult<()> {
+    let mut request = DeboaRequest::get(TEST_URL)?
+        .decompress(false)
+        .build()?;
+    expect(compression::prepare(&mut request)).to_be(eq(false));
+    expect(
+        request
+            .headers()
+            .contains_key(header::ACCEPT_ENCODING),
+    )
+    .to_be(eq(false));
+
+    let mut request = DeboaRequest::get(TEST_URL)?
+        .header(header::ACCEPT_ENCODING, "identity")
+        .build()?;
+    expect(compression::prepare(&mut request)).to_be(eq(false));
+    expect(
+        request
+            .headers()
+            .get(header::ACCEPT_ENCODING)
+            .map(|value| value.as_bytes()),
+    )
+    .to_be(eq(Some(b"identity".as_ref())));
+    Ok(())
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn test_decompress_gzip() -> TestResult<()> {
+    let response = compression::decompress(encoded_response("gzip", gzip(b"hello deboa")));
+    expect(
+        response
+            .headers()
+            .contains_key(header::CONTENT_ENCODING),
+    )
+    .to_be(eq(false));
+    expect(
+        response
+            .headers()
+            .contains_key(header::CONTENT_LENGTH),
+    )
+    .to_be(eq(false));
+    expect(block_on(response.bytes())?).to_be(eq(b"hello deboa".to_vec()));
+    Ok(())
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn test_decompress_large_body() -> TestResult<()> {
+    let body = "deboa ".repeat(10_000);
+    let response = compression::decompress(encoded_response("gzip", gzip(body.as_bytes())));
+    expect(block_on(response.bytes())?).to_be(eq(body.into_bytes()));
+    Ok(())
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn test_decompress_multiple_codings() -> TestResult<()> {
+    let body = gzip(&gzip(b"twice"));
+    let response = compression::decompress(encoded_response("gzip, identity, gzip", body));
+    expect(block_on(response.bytes())?).to_be(eq(b"twice".to_vec()));
+    Ok(())
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn test_decompress_unsupported_coding() -> TestResult<()> {
+    let response = compression::decompress(encoded_response("compress", b"raw".to_vec()));
+    expect(
+        response
+            .headers()
+            .contains_key(header::CONTENT_ENCODING),
+    )
+    .to_be(eq(true));
+    expect(block_on(response.bytes())?).to_be(eq(b"raw".to_vec()));
+    Ok(())
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn test_decompress_invalid_body() {
+    let response = compression::decompress(encoded_response("gzip", b"not gzip".to_vec()));
+    let result = block_on(response.bytes());
+    expect(matches!(result, Err(DeboaError::Io(IoError::Decompress { .. })))).to_be(eq(true));
+    let error = result.expect_err("invalid body must fail");
+    expect(error.downcast_source::<std::io::Error>().is_some()).to_be(eq(true));
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn test_compress_request_body() -> TestResult<()> {
+    let request = DeboaRequest::post(TEST_URL)?
+        .compress(Encoding::Gzip)
+        .text("hello deboa")
+        .build()?;
+    expect(
+        request
+            .headers()
+            .get(header::CONTENT_ENCODING)
+            .map(|value| value.as_bytes()),
+    )
+    .to_be(eq(Some(b"gzip".as_ref())));
+
+    let length = request
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .cloned();
+    let body = request_body(request);
+    expect(length).to_be(eq(Some(body.len().into())));
+    expect(gunzip(&body)).to_be(eq(b"hello deboa".to_vec()));
+    Ok(())
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn test_compress_streaming_request_body() -> TestResult<()> {
+    let chunks = ["hello ", "streaming ", "deboa"]
+        .map(|chunk| Ok::<_, std::io::Error>(Frame::data(Bytes::from_static(chunk.as_bytes()))));
+    let request = DeboaRequest::post(TEST_URL)?
+        .header(header::CONTENT_LENGTH, "21")
+        .body(HttpBody::from_generic_stream(futures::stream::iter(chunks)))
+        .compress(Encoding::Gzip)
+        .build()?;
+    expect(
+        request
+            .headers()
+            .contains_key(header::CONTENT_LENGTH),
+    )
+    .to_be(eq(false));
+    expect(gunzip(&request_body(request))).to_be(eq(b"hello streaming deboa".to_vec()));
+    Ok(())
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn test_compress_request_threshold() -> TestResult<()> {
+    let request = DeboaRequest::post(TEST_URL)?
+        .text("small")
+        .build()?;
+    let request = compression::compress_request(request, Encoding::Gzip, 1024)?;
+    expect(
+        request
+            .headers()
+            .contains_key(header::CONTENT_ENCODING),
+    )
+    .to_be(eq(false));
+
+    let text = "large ".repeat(200);
+    let request = DeboaRequest::post(TEST_URL)?
+        .text(&text)
+        .build()?;
+    let request = compression::compress_request(request, Encoding::Gzip, 1024)?;
+    expect(
+        request
+            .headers()
+            .contains_key(header::CONTENT_ENCODING),
+    )
+    .to_be(eq(true));
+    expect(gunzip(&request_body(request))).to_be(eq(text.into_bytes()));
+    Ok(())
+}
+
+#[cfg(not(feature = "zstd"))]
+#[test]
+fn test_compress_disabled_encoding() -> TestResult<()> {
+    let result = DeboaRequest::post(TEST_URL)?
+        .text("hello")
+        .compress(Encoding::Zstd)
+        .build();
+    expect(matches!(result, Err(DeboaError::Io(IoError::Compress { .. })))).to_be(eq(true));
+    Ok(())
+}
@@ -0,0 +1,134 @@
+use crate::errors::{ConnectionError, DeboaError, DnsError, ErrorSource, IoError, RequestError};
+use caramelo::{expect, matchers::eq};
+use http::{Method, Uri};
+use std::{error::Error, io};
+
+fn tcp_error(kind: io::ErrorKind) -> DeboaError {
+    let e = io::Error::new(kind, "connect failed");
+    DeboaError::Connection(ConnectionError::Tcp {
+        message: e.to_string(),
+        source: Some(ErrorSource::new(e)),
+    })
+}
+
+fn send_error() -> DeboaError {
+    DeboaError::Request(RequestError::Send {
+        message: "Failed".to_string(),
+        method: None,
+        url: None,
+        source: None,
+    })
+}
+
+#[test]
+fn test_source_chain() {
+    let error = tcp_error(io::ErrorKind::ConnectionRefused);
+
+    let connection = error
+        .source()
+        .unwrap();
+    expect(connection.to_string()).to_be(eq("Tcp error: connect failed".to_string()));
+    let source = connection
+        .source()
+        .unwrap();
+    expect(source.to_string()).to_be(eq("connect failed".to_string()));
+
+    let io_error = error
+        .downcast_source::<io::Error>()
+        .unwrap();
+    expect(io_error.kind()).to_be(eq(io::ErrorKind::ConnectionRefused));
+}
+
+#[test]
+fn test_downcast_wrapped_io_error() {
+    let e = io::Error::other(DnsError::Resolver { message: "no config".to_string(), source: None });
+    let error = DeboaError::Connection(ConnectionError::Tls {
+        message: e.to_string(),
+        source: Some(ErrorSource::new(e)),
+    });
+    expect(
+        error
+            .downcast_source::<DnsError>()
+            .is_some(),
+    )
+    .to_be(eq(true));
+}
+
+#[test]
+fn test_clone_keeps_source() {
+    let error = tcp_error(io::ErrorKind::ConnectionReset);
+    let clone = error.clone();
+    expect(clone == error).to_be(eq(true));
+    expect(
+        clone
+            .downcast_source::<io::Error>()
+            .map(io::Error::kind),
+    )
+    .to_be(eq(Some(io::ErrorKind::ConnectionReset)));
+}
+
+#[test]
+fn test_is_timeout() {
+    let request_timeout = DeboaError::Request(RequestError::Timeout {
+        message: "Request timed out".to_string(),
+        method: None,
+        url: None,
+        source: None,
+    });
+    expect(request_timeout.is_timeout()).to_be(eq(true));
+    expect(tcp_error(io::ErrorKind::TimedOut).is_timeout()).to_be(eq(true));
+    expect(tcp_error(io::ErrorKind::ConnectionRefused).is_timeout()).to_be(eq(false));
+    expect(send_error().is_timeout()).to_be(eq(false));
+}
+
+#[test]
+fn test_is_connect() {
+    let tls = DeboaError::Connection(ConnectionError::Tls {
+        message: "invalid certificate".to_string(),
+        source: None,
+    });
+    let closed = DeboaError::Connection(ConnectionError::Closed {
+        message: "connection closed".to_string(),
+        source: None,
+    });
+
+    expect(tcp_error(io::ErrorKind::ConnectionRefused).is_connect()).to_be(eq(true));
+    expect(tls.is_connect()).to_be(eq(true));
+    expect(tls.is_tls()).to_be(eq(true));
+    expect(closed.is_connect()).to_be(eq(false));
+    expect(send_error().is_connect()).to_be(eq(false));
+}
+
+#[test]
+fn test_is_dns() {
+    let error = DeboaError::Dns(DnsError::Resolve {
+        host: "example.invalid".to_string(),
+        message: "not found".to_string(),
+        source: None,
+    });
+    expect(error.is_dns()).to_be(eq(true));
+    expect(error.is_connect()).to_be(eq(false));
+}
+
+#[test]
+fn test_is_body() {
+    let error = DeboaError::Io(IoError::Content { message: "truncated".to_string(), source: None });
+    expect(error.is_body()).to_be(eq(true));
+    expect(send_error().is_body()).to_be(eq(false));
+}
+
+#[test]
+fn test_with_request() {
+    let url = Uri::from_static("https://example.com/users");
+    let error = send_error().with_request(&Method::POST, &url);
+    expect(error.method()).to_be(eq(Some(&Method::POST)));
+    expect(error.url()).to_be(eq(Some(&url)));
+
+    // Known values are kept.
+    let error = error.with_request(&Method::GET, &Uri::from_static("https://other.com"));
+    expect(error.method()).to_be(eq(Some(&Method::POST)));
+    expect(error.url()).to_be(eq(Some(&url)));
+
+    let error = tcp_error(io::ErrorKind::ConnectionRefused).with_request(&Method::GET, &url);
+    expect(error.url()).to_be(eq(None));
+}
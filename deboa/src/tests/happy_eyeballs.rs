@@ -0,0 +1,95 @@
+use crate::{
+    errors::{ConnectionError, DeboaError},
+    happy_eyeballs::{bind_addr_for, race, sort_addresses},
+    TestResult,
+};
+use caramelo::{expect, matchers::eq};
+use futures::executor::block_on;
+use futures_timer::Delay;
+use std::{
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    time::Duration,
+};
+
+fn v4(last: u8) -> IpAddr {
+    IpAddr::V4(Ipv4Addr::new(192, 0, 2, last))
+}
+
+fn v6(last: u16) -> IpAddr {
+    IpAddr::V6(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, last))
+}
+
+fn refused(addr: IpAddr) -> DeboaError {
+    DeboaError::Connection(ConnectionError::Tcp {
+        message: format!("{} refused", addr),
+        source: None,
+    })
+}
+
+#[test]
+fn test_sort_addresses_interleaves_families() -> TestResult<()> {
+    let unspecified = IpAddr::V4(Ipv4Addr::UNSPECIFIED);
+    let sorted = sort_addresses(vec![v6(1), v6(2), v6(3), v4(1), v4(2)], &unspecified);
+    expect(sorted).to_be(eq(vec![v6(1), v4(1), v6(2), v4(2), v6(3)]));
+
+    let sorted = sort_addresses(vec![v4(1), v4(2), v6(1)], &unspecified);
+    expect(sorted).to_be(eq(vec![v4(1), v6(1), v4(2)]));
+    Ok(())
+}
+
+#[test]
+fn test_sort_addresses_honours_bind_address() -> TestResult<()> {
+    let bind = IpAddr::V4(Ipv4Addr::LOCALHOST);
+    let sorted = sort_addresses(vec![v6(1), v4(1), v6(2), v4(2)], &bind);
+    expect(sorted).to_be(eq(vec![v4(1), v4(2)]));
+    Ok(())
+}
+
+#[test]
+fn test_bind_addr_for() -> TestResult<()> {
+    let unspecified = IpAddr::V4(Ipv4Addr::UNSPECIFIED);
+    expect(bind_addr_for(&unspecified, &v6(1))).to_be(eq(IpAddr::V6(Ipv6Addr::UNSPECIFIED)));
+    expect(bind_addr_for(&unspecified, &v4(1))).to_be(eq(unspecified));
+
+    let bind = IpAddr::V4(Ipv4Addr::LOCALHOST);
+    expect(bind_addr_for(&bind, &v6(1))).to_be(eq(bind));
+    Ok(())
+}
+
+#[test]
+fn test_race_falls_back_after_failure() -> TestResult<()> {
+    let addrs = [v6(1), v4(1)];
+    let winner = block_on(race(&addrs, Duration::from_secs(10), |addr| async move {
+        if addr.is_ipv6() {
+            Err(refused(addr))
+        } else {
+            Ok(addr)
+        }
+    }))?;
+    expect(winner).to_be(eq(v4(1)));
+    Ok(())
+}
+
+#[test]
+fn test_race_starts_next_attempt_after_delay() -> TestResult<()> {
+    let addrs = [v6(1), v4(1)];
+    let winner = block_on(race(&addrs, Duration::from_millis(10), |addr| async move {
+        if addr.is_ipv6() {
+            // A black-holed address, never answering in time.
+            Delay::new(Duration::from_secs(5)).await;
+        }
+        Ok(addr)
+    }))?;
+    expect(winner).to_be(eq(v4(1)));
+    Ok(())
+}
+
+#[test]
+fn test_race_returns_last_error() -> TestResult<()> {
+    let addrs = [v6(1), v4(1)];
+    let result = block_on(race(&addrs, Duration::from_millis(10), |addr| async move {
+        Err::<IpAddr, _>(refused(addr))
+    }));
+    expect(result).to_be(eq(Err(refused(v4(1)))));
+    Ok(())
+}
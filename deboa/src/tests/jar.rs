@@ -0,0 +1,225 @@
+use crate::{
+    errors::DeboaError,
+    jar::{self, CookieJar, CookieStore},
+    request::DeboaRequest,
+    TestResult,
+};
+use caramelo::{expect, matchers::eq};
+use http::{header, HeaderMap, HeaderValue, Uri};
+
+fn set_cookies(jar: &CookieJar, url: &str, cookies: &[&str]) {
+    let mut headers = HeaderMap::new();
+    for cookie in cookies {
+        headers.append(header::SET_COOKIE, HeaderValue::from_str(cookie).unwrap());
+    }
+    jar.set_cookies(&Uri::try_from(url).unwrap(), jar::parse_set_cookies(&headers));
+}
+
+fn cookie_header(jar: &CookieJar, url: &str) -> String {
+    jar.cookies(&Uri::try_from(url).unwrap())
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+#[test]
+fn test_host_only_cookie() {
+    let jar = CookieJar::new();
+    set_cookies(&jar, "https://example.com/", &["id=1"]);
+
+    expect(cookie_header(&jar, "https://example.com/")).to_be(eq("id=1".to_string()));
+    expect(cookie_header(&jar, "https://www.example.com/")).to_be(eq(String::new()));
+    expect(cookie_header(&jar, "https://other.com/")).to_be(eq(String::new()));
+}
+
+#[test]
+fn test_domain_cookie() {
+    let jar = CookieJar::new();
+    set_cookies(&jar, "https://www.example.com/", &["id=1; Domain=.Example.com"]);
+
+    expect(cookie_header(&jar, "https://example.com/")).to_be(eq("id=1".to_string()));
+    expect(cookie_header(&jar, "https://api.example.com/")).to_be(eq("id=1".to_string()));
+    expect(cookie_header(&jar, "https://badexample.com/")).to_be(eq(String::new()));
+}
+
+#[test]
+fn test_rejects_foreign_domain() {
+    let jar = CookieJar::new();
+    set_cookies(
+        &jar,
+        "https://example.com/",
+        &["id=1; Domain=other.com", "id=2; Domain=www.example.com"],
+    );
+    expect(jar.is_empty()).to_be(eq(true));
+}
+
+#[test]
+fn test_rejects_public_suffix() {
+    let jar = CookieJar::new();
+    set_cookies(&jar, "https://example.co.uk/", &["a=1; Domain=co.uk"]);
+    set_cookies(&jar, "https://example.com/", &["b=2; Domain=com"]);
+    expect(jar.is_empty()).to_be(eq(true));
+}
+
+#[test]
+fn test_path_matching() {
+    let jar = CookieJar::new();
+    set_cookies(&jar, "https://example.com/docs/page", &["default=1", "api=2; Path=/api"]);
+
+    expect(cookie_header(&jar, "https://example.com/docs")).to_be(eq("default=1".to_string()));
+    expect(cookie_header(&jar, "https://example.com/docs/other"))
+        .to_be(eq("default=1".to_string()));
+    expect(cookie_header(&jar, "https://example.com/documents")).to_be(eq(String::new()));
+    expect(cookie_header(&jar, "https://example.com/api/users")).to_be(eq("api=2".to_string()));
+    expect(cookie_header(&jar, "https://example.com/")).to_be(eq(String::new()));
+}
+
+#[test]
+fn test_longer_paths_first() {
+    let jar = CookieJar::new();
+    set_cookies(&jar, "https://example.com/", &["a=1; Path=/", "b=2; Path=/api", "c=3; Path=/"]);
+    expect(cookie_header(&jar, "https://example.com/api")).to_be(eq("b=2; a=1; c=3".to_string()));
+}
+
+#[test]
+fn test_secure_cookie() {
+    let jar = CookieJar::new();
+    set_cookies(&jar, "http://example.com/", &["insecure=1; Secure"]);
+    expect(jar.is_empty()).to_be(eq(true));
+
+    set_cookies(&jar, "https://example.com/", &["id=1; Secure"]);
+    expect(cookie_header(&jar, "https://example.com/")).to_be(eq("id=1".to_string()));
+    expect(cookie_header(&jar, "http://example.com/")).to_be(eq(String::new()));
+
+    // Insecure origins may not overwrite it either.
+    set_cookies(&jar, "http://example.com/", &["id=2"]);
+    expect(cookie_header(&jar, "https://example.com/")).to_be(eq("id=1".to_string()));
+}
+
+#[test]
+fn test_replaces_cookie() {
+    let jar = CookieJar::new();
+    set_cookies(&jar, "https://example.com/", &["id=1"]);
+    set_cookies(&jar, "https://example.com/", &["id=2"]);
+    expect(jar.len()).to_be(eq(1));
+    expect(cookie_header(&jar, "https://example.com/")).to_be(eq("id=2".to_string()));
+}
+
+#[test]
+fn test_expired_cookie_removes_stored() {
+    let jar = CookieJar::new();
+    set_cookies(&jar, "https://example.com/", &["id=1; Max-Age=3600", "other=1"]);
+    expect(jar.len()).to_be(eq(2));
+
+    set_cookies(&jar, "https://example.com/", &["id=1; Max-Age=0"]);
+    set_cookies(&jar, "https://example.com/", &["other=1; Expires=Thu, 01 Jan 1970 00:00:00 GMT"]);
+    expect(jar.is_empty()).to_be(eq(true));
+}
+
+#[test]
+fn test_max_age_precedence() {
+    let jar = CookieJar::new();
+    set_cookies(
+        &jar,
+        "https://example.com/",
+        &["id=1; Max-Age=3600; Expires=Thu, 01 Jan 1970 00:00:00 GMT"],
+    );
+    expect(cookie_header(&jar, "https://example.com/")).to_be(eq("id=1".to_string()));
+}
+
+#[test]
+fn test_add_cookies_keeps_request_cookies() -> TestResult<()> {
+    let jar = CookieJar::new();
+    set_cookies(&jar, "https://example.com/", &["id=jar", "theme=dark"]);
+
+    let mut request = DeboaRequest::get("https://example.com/")?
+        .header(header::COOKIE, "id=user")
+        .build()?;
+    jar::add_cookies(&jar, &mut request);
+    expect(
+        request
+            .headers()
+            .get(header::COOKIE)
+            .cloned(),
+    )
+    .to_be(eq(Some(HeaderValue::from_static("id=user; theme=dark"))));
+    Ok(())
+}
+
+const CURL_COOKIES: &str = "# Netscape HTTP Cookie File
+# https://curl.se/docs/http-cookies.html
+
+.example.com\tTRUE\t/\tFALSE\t4102444800\tdomain\t1
+#HttpOnly_example.com\tFALSE\t/api\tTRUE\t4102444800\tsession\tabc
+example.com\tFALSE\t/\tFALSE\t0\ttransient\t1
+example.com\tFALSE\t/\tFALSE\t1\texpired\t1
+";
+
+#[test]
+fn test_load_netscape() -> TestResult<()> {
+    let jar = CookieJar::new();
+    jar.load_netscape(CURL_COOKIES.as_bytes())?;
+    expect(jar.len()).to_be(eq(3));
+
+    expect(cookie_header(&jar, "https://www.example.com/api")).to_be(eq("domain=1".to_string()));
+    expect(cookie_header(&jar, "https://example.com/api"))
+        .to_be(eq("session=abc; domain=1; transient=1".to_string()));
+
+    let cookies = jar.cookies(&Uri::from_static("https://example.com/api"));
+    expect(cookies[0].http_only()).to_be(eq(Some(true)));
+    expect(cookies[0].secure()).to_be(eq(Some(true)));
+    Ok(())
+}
+
+#[test]
+fn test_load_netscape_invalid_line() {
+    let jar = CookieJar::new();
+    let result = jar.load_netscape("example.com\tMAYBE\t/\tFALSE\t0\tid\t1\n".as_bytes());
+    expect(matches!(result, Err(DeboaError::Cookie { .. }))).to_be(eq(true));
+}
+
+#[test]
+fn test_save_netscape_skips_session_cookies() -> TestResult<()> {
+    let jar = CookieJar::new();
+    set_cookies(
+        &jar,
+        "https://example.com/",
+        &["session=1", "remembered=2; Max-Age=3600; Domain=example.com; HttpOnly"],
+    );
+
+    let mut file = Vec::new();
+    jar.save_netscape(&mut file)?;
+    let file = String::from_utf8(file)?;
+    expect(file.starts_with("# Netscape HTTP Cookie File")).to_be(eq(true));
+    expect(file.contains("session")).to_be(eq(false));
+    expect(file.contains("#HttpOnly_.example.com\tTRUE\t/\tFALSE\t")).to_be(eq(true));
+
+    let loaded = CookieJar::new();
+    loaded.load_netscape(file.as_bytes())?;
+    expect(cookie_header(&loaded, "https://www.example.com/"))
+        .to_be(eq("remembered=2".to_string()));
+    Ok(())
+}
+
+#[cfg(feature = "cookie-json")]
+#[test]
+fn test_json_round_trip() -> TestResult<()> {
+    let jar = CookieJar::new();
+    set_cookies(
+        &jar,
+        "https://example.com/docs/",
+        &["session=1", "id=2; Max-Age=3600; Secure", "old=3; Max-Age=0"],
+    );
+
+    let mut json = Vec::new();
+    jar.save_json(&mut json)?;
+
+    let loaded = CookieJar::new();
+    loaded.load_json(json.as_slice())?;
+    expect(loaded.len()).to_be(eq(1));
+    expect(cookie_header(&loaded, "https://example.com/docs/")).to_be(eq("id=2".to_string()));
+    expect(cookie_header(&loaded, "http://example.com/docs/")).to_be(eq(String::new()));
+    expect(cookie_header(&loaded, "https://www.example.com/docs/")).to_be(eq(String::new()));
+    Ok(())
+}
@@ -3,12 +3,24 @@
 use ::url::Url;
 use http::Uri;
 
+mod alt_svc;
 mod cache;
+mod compression;
 //mod catcher;
 mod cookie;
+mod errors;
 mod form;
+mod happy_eyeballs;
+mod jar;
+mod options;
+mod pool;
+mod protocol;
+mod proxy;
+mod redirect;
 mod request;
 mod response;
+mod retry;
+mod status;
 mod url;
 
 const TEST_URL: &str = "https://localhost:8000";
@@ -0,0 +1,200 @@
+use crate::{
+    errors::DeboaError,
+    options::{self, RequestOptions},
+    redirect::RedirectPolicy,
+    request::DeboaRequest,
+    response::DeboaResponse,
+    retry::{Retry, RetryPolicy},
+    tests::TEST_URL,
+    Result, TestResult,
+};
+use bytes::Bytes;
+use caramelo::{expect, matchers::eq};
+use futures::{executor::block_on, future, stream};
+use http::{Method, StatusCode};
+use http_body::Frame;
+use hyper_body_utils::HttpBody;
+use std::{
+    cell::Cell,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+use tackle::{Chain, Hook};
+
+struct CountingHook {
+    calls: Rc<Cell<u32>>,
+}
+
+impl Hook<DeboaRequest, DeboaResponse> for CountingHook {
+    type Result = Result<DeboaResponse>;
+    type Error = DeboaError;
+
+    async fn call(&self, _request: DeboaRequest) -> Self::Result {
+        self.calls
+            .set(self.calls.get() + 1);
+        Ok(DeboaResponse::builder()
+            .status(StatusCode::SERVICE_UNAVAILABLE)
+            .build())
+    }
+}
+
+fn fast_policy() -> RetryPolicy {
+    RetryPolicy::default()
+        .base_delay(Duration::from_millis(1))
+        .max_delay(Duration::from_millis(5))
+}
+
+fn stalled_response() -> DeboaResponse {
+    DeboaResponse::new(http::Response::new(HttpBody::from_generic_stream(stream::pending())))
+}
+
+#[test]
+fn test_builder_options() -> TestResult<()> {
+    let request = DeboaRequest::get(TEST_URL)?
+        .timeout(Duration::from_secs(10))
+        .connect_timeout(Duration::from_secs(2))
+        .response_timeout(Duration::from_secs(5))
+        .read_timeout(Duration::from_secs(1))
+        .redirect_policy(RedirectPolicy::none())
+        .build()?;
+    let options = request
+        .options()
+        .unwrap();
+    expect(options.timeout).to_be(eq(Some(Duration::from_secs(10))));
+    expect(options.connect_timeout).to_be(eq(Some(Duration::from_secs(2))));
+    expect(options.response_timeout).to_be(eq(Some(Duration::from_secs(5))));
+    expect(options.read_timeout).to_be(eq(Some(Duration::from_secs(1))));
+    expect(matches!(options.redirect_policy, Some(RedirectPolicy::None))).to_be(eq(true));
+
+    let cloned = request.try_clone()?;
+    expect(
+        cloned
+            .options()
+            .and_then(|options| options.read_timeout),
+    )
+    .to_be(eq(Some(Duration::from_secs(1))));
+
+    let request = DeboaRequest::get(TEST_URL)?.build()?;
+    expect(
+        request
+            .options()
+            .is_none(),
+    )
+    .to_be(eq(true));
+    Ok(())
+}
+
+#[test]
+fn test_start_keeps_earliest_deadline() -> TestResult<()> {
+    let mut request = DeboaRequest::get(TEST_URL)?.build()?;
+    expect(options::start(&mut request)).to_be(eq(None));
+
+    let before = Instant::now();
+    let mut request = DeboaRequest::get(TEST_URL)?
+        .timeout(Duration::from_secs(10))
+        .build()?;
+    let deadline = options::start(&mut request).unwrap();
+    expect(deadline >= before + Duration::from_secs(10)).to_be(eq(true));
+    expect(
+        request
+            .options()
+            .and_then(|options| options.timeout),
+    )
+    .to_be(eq(None));
+    // Started once, the clock is not reset.
+    expect(options::start(&mut request)).to_be(eq(Some(deadline)));
+
+    let early = Instant::now() + Duration::from_secs(1);
+    let mut request = DeboaRequest::get(TEST_URL)?
+        .options(RequestOptions {
+            timeout: Some(Duration::from_secs(10)),
+            deadline: Some(early),
+            ..Default::default()
+        })
+        .build()?;
+    expect(options::start(&mut request)).to_be(eq(Some(early)));
+    Ok(())
+}
+
+#[test]
+fn test_with_deadline() -> TestResult<()> {
+    let url = TEST_URL.parse()?;
+    let deadline = Some(Instant::now() + Duration::from_millis(20));
+    let result: Result<()> =
+        block_on(options::with_deadline(deadline, &Method::GET, &url, future::pending()));
+    let error = result.unwrap_err();
+    expect(error.is_timeout()).to_be(eq(true));
+    expect(error.method()).to_be(eq(Some(&Method::GET)));
+    expect(error.url()).to_be(eq(Some(&url)));
+
+    let result = block_on(options::with_deadline(None, &Method::GET, &url, async { Ok(1) }))?;
+    expect(result).to_be(eq(1));
+    Ok(())
+}
+
+#[test]
+fn test_read_timeout_on_body() -> TestResult<()> {
+    let response = options::limit_body(stalled_response(), None, Some(Duration::from_millis(20)));
+    let error = block_on(response.bytes()).unwrap_err();
+    expect(error.is_timeout()).to_be(eq(true));
+    expect(error.is_body()).to_be(eq(true));
+    Ok(())
+}
+
+#[test]
+fn test_deadline_on_body() -> TestResult<()> {
+    let deadline = Instant::now() + Duration::from_millis(20);
+    let response = options::limit_body(stalled_response(), Some(deadline), None);
+    let error = block_on(response.text()).unwrap_err();
+    expect(error.is_timeout()).to_be(eq(true));
+
+    let deadline = Instant::now() + Duration::from_secs(10);
+    let response = DeboaResponse::builder()
+        .body(b"done".as_slice())
+        .build();
+    let response = options::limit_body(response, Some(deadline), Some(Duration::from_millis(20)));
+    expect(block_on(response.text())?).to_be(eq("done".to_string()));
+    Ok(())
+}
+
+#[test]
+fn test_deadline_on_ready_body() -> TestResult<()> {
+    // The body never waits, the deadline must still end it.
+    let chunks = stream::repeat_with(|| Ok(Frame::data(Bytes::from_static(b"chunk"))));
+    let response = DeboaResponse::new(http::Response::new(HttpBody::from_generic_stream(chunks)));
+    let deadline = Instant::now() + Duration::from_millis(20);
+    let response = options::limit_body(response, Some(deadline), None);
+    let error = block_on(response.bytes()).unwrap_err();
+    expect(error.is_timeout()).to_be(eq(true));
+    Ok(())
+}
+
+#[test]
+fn test_retry_policy_override() -> TestResult<()> {
+    let calls = Rc::new(Cell::new(0));
+    let hook =
+        Retry::new(fast_policy().max_retries(3)).chain(CountingHook { calls: calls.clone() });
+    let request = DeboaRequest::get(TEST_URL)?
+        .retry_policy(fast_policy().max_retries(1))
+        .build()?;
+    let response = block_on(hook.call(request))?;
+    expect(response.status()).to_be(eq(StatusCode::SERVICE_UNAVAILABLE));
+    expect(calls.get()).to_be(eq(2));
+    Ok(())
+}
+
+#[test]
+fn test_no_retry_past_deadline() -> TestResult<()> {
+    let policy = RetryPolicy::default()
+        .base_delay(Duration::from_secs(10))
+        .max_delay(Duration::from_secs(10));
+    let calls = Rc::new(Cell::new(0));
+    let hook = Retry::new(policy).chain(CountingHook { calls: calls.clone() });
+    let request = DeboaRequest::get(TEST_URL)?
+        .timeout(Duration::from_secs(1))
+        .build()?;
+    let response = block_on(hook.call(request))?;
+    expect(response.status()).to_be(eq(StatusCode::SERVICE_UNAVAILABLE));
+    expect(calls.get()).to_be(eq(1));
+    Ok(())
+}
@@ -0,0 +1,294 @@
+use crate::{
+    conn::HttpConnectionDispatcher,
+    pool::{Checkout, ConnectionCache, HostLimiter, HostPermit, PoolKey},
+    response::DeboaResponse,
+    Result, TestResult,
+};
+use caramelo::{expect, matchers::eq};
+use futures::{executor::block_on, FutureExt};
+use http::{Request, Version};
+use hyper_body_utils::HttpBody;
+use std::{sync::Arc, time::Duration};
+
+struct FakeConnection {
+    id: u32,
+    multiplexed: bool,
+    ready: bool,
+    closed: bool,
+}
+
+impl FakeConnection {
+    fn http1(id: u32, ready: bool) -> Self {
+        Self { id, multiplexed: false, ready, closed: false }
+    }
+
+    fn closed(mut self) -> Self {
+        self.closed = true;
+        self
+    }
+
+    fn http2(id: u32) -> Self {
+        Self { id, multiplexed: true, ready: true, closed: false }
+    }
+}
+
+impl HttpConnectionDispatcher for FakeConnection {
+    async fn send_request(
+        &mut self,
+        _request: Request<HttpBody>,
+        _timeout: Duration,
+    ) -> Result<DeboaResponse> {
+        Ok(DeboaResponse::builder().build())
+    }
+
+    fn is_multiplexed(&self) -> bool {
+        self.multiplexed
+    }
+
+    fn is_closed(&self) -> bool {
+        self.closed
+    }
+
+    fn is_ready(&self) -> bool {
+        self.ready
+    }
+
+    async fn ready(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    fn try_clone(&self) -> Option<Self> {
+        if self.multiplexed {
+            Some(Self::http2(self.id))
+        } else {
+            None
+        }
+    }
+}
+
+fn key(host: &str, port: u16) -> PoolKey {
+    PoolKey::new("http", host, port, Version::HTTP_11)
+}
+
+fn checkout_id(
+    cache: &mut ConnectionCache<FakeConnection>,
+    key: &PoolKey,
+    permit: Option<&HostPermit>,
+) -> Option<u32> {
+    match cache.checkout(key, permit) {
+        Checkout::Ready(conn) => Some(conn.id),
+        _ => None,
+    }
+}
+
+fn acquire_permit(
+    cache: &mut ConnectionCache<FakeConnection>,
+    key: &PoolKey,
+) -> Option<HostPermit> {
+    match cache.checkout(key, None) {
+        Checkout::Limited(limiter) => Some(block_on(limiter.acquire())),
+        _ => None,
+    }
+}
+
+#[test]
+fn test_checkout_empty() -> TestResult<()> {
+    let mut cache = ConnectionCache::<FakeConnection>::new();
+    let permit = acquire_permit(&mut cache, &key("localhost", 80));
+    expect(permit.is_some()).to_be(eq(true));
+    expect(matches!(cache.checkout(&key("localhost", 80), permit.as_ref()), Checkout::Connect))
+        .to_be(eq(true));
+    expect(cache.is_empty()).to_be(eq(true));
+    Ok(())
+}
+
+#[test]
+fn test_multiplexed_connection_is_shared() -> TestResult<()> {
+    let mut cache = ConnectionCache::new();
+    cache.checkin(&key("localhost", 443), FakeConnection::http2(1));
+
+    expect(checkout_id(&mut cache, &key("localhost", 443), None)).to_be(eq(Some(1)));
+    expect(checkout_id(&mut cache, &key("localhost", 443), None)).to_be(eq(Some(1)));
+
+    cache.checkin(&key("localhost", 443), FakeConnection::http2(2));
+    expect(cache.len()).to_be(eq(1));
+    Ok(())
+}
+
+#[test]
+fn test_http1_connection_is_exclusive() -> TestResult<()> {
+    let mut cache = ConnectionCache::new();
+    cache.checkin(&key("localhost", 80), FakeConnection::http1(1, true));
+
+    let first = acquire_permit(&mut cache, &key("localhost", 80));
+    expect(checkout_id(&mut cache, &key("localhost", 80), first.as_ref())).to_be(eq(Some(1)));
+
+    let second = acquire_permit(&mut cache, &key("localhost", 80));
+    expect(matches!(cache.checkout(&key("localhost", 80), second.as_ref()), Checkout::Connect))
+        .to_be(eq(true));
+    Ok(())
+}
+
+#[test]
+fn test_http1_busy_connection_is_skipped() -> TestResult<()> {
+    let mut cache = ConnectionCache::new();
+    cache.checkin(&key("localhost", 80), FakeConnection::http1(1, false));
+    cache.checkin(&key("localhost", 80), FakeConnection::http1(2, true));
+
+    let permit = acquire_permit(&mut cache, &key("localhost", 80));
+    expect(checkout_id(&mut cache, &key("localhost", 80), permit.as_ref())).to_be(eq(Some(2)));
+    expect(cache.len()).to_be(eq(1));
+    Ok(())
+}
+
+#[test]
+fn test_http1_busy_connection_is_reused_at_limit() -> TestResult<()> {
+    let mut cache = ConnectionCache::new();
+    cache.set_max_connections_per_host(1);
+    cache.checkin(&key("localhost", 80), FakeConnection::http1(1, false));
+
+    let permit = acquire_permit(&mut cache, &key("localhost", 80));
+    let checkout = cache.checkout(&key("localhost", 80), permit.as_ref());
+    expect(matches!(checkout, Checkout::Busy(ref conn) if conn.id == 1)).to_be(eq(true));
+    Ok(())
+}
+
+#[test]
+fn test_limiter_waits_in_order() -> TestResult<()> {
+    let limiter = Arc::new(HostLimiter::new(1));
+    let held = block_on(limiter.acquire());
+    expect(limiter.in_use()).to_be(eq(1));
+
+    let mut first = limiter.acquire();
+    let mut second = limiter.acquire();
+    expect(
+        (&mut first)
+            .now_or_never()
+            .is_none(),
+    )
+    .to_be(eq(true));
+    expect(
+        (&mut second)
+            .now_or_never()
+            .is_none(),
+    )
+    .to_be(eq(true));
+    expect(limiter.waiting()).to_be(eq(2));
+
+    drop(held);
+    expect(limiter.waiting()).to_be(eq(1));
+    expect(
+        (&mut second)
+            .now_or_never()
+            .is_none(),
+    )
+    .to_be(eq(true));
+
+    let granted = (&mut first).now_or_never();
+    expect(granted.is_some()).to_be(eq(true));
+    drop(granted);
+
+    expect(
+        (&mut second)
+            .now_or_never()
+            .is_some(),
+    )
+    .to_be(eq(true));
+    expect(limiter.waiting()).to_be(eq(0));
+    Ok(())
+}
+
+#[test]
+fn test_limiter_cancelled_waiter_passes_slot() -> TestResult<()> {
+    let limiter = Arc::new(HostLimiter::new(1));
+    let held = block_on(limiter.acquire());
+
+    let mut first = limiter.acquire();
+    let mut second = limiter.acquire();
+    expect(
+        (&mut first)
+            .now_or_never()
+            .is_none(),
+    )
+    .to_be(eq(true));
+    expect(
+        (&mut second)
+            .now_or_never()
+            .is_none(),
+    )
+    .to_be(eq(true));
+
+    drop(held);
+    drop(first);
+    expect(
+        (&mut second)
+            .now_or_never()
+            .is_some(),
+    )
+    .to_be(eq(true));
+    Ok(())
+}
+
+#[test]
+fn test_expired_connections_are_evicted() -> TestResult<()> {
+    let mut cache = ConnectionCache::new();
+    cache.set_keep_alive_duration(Duration::from_millis(10));
+    cache.checkin(&key("localhost", 80), FakeConnection::http1(1, true));
+    cache.checkin(&key("localhost", 443), FakeConnection::http2(2));
+    expect(cache.len()).to_be(eq(2));
+
+    std::thread::sleep(Duration::from_millis(20));
+    cache.evict_expired();
+    expect(cache.is_empty()).to_be(eq(true));
+    Ok(())
+}
+
+#[test]
+fn test_least_recently_used_connection_is_evicted() -> TestResult<()> {
+    let mut cache = ConnectionCache::new();
+    cache.set_max_idle_connections(2);
+    cache.checkin(&key("first", 80), FakeConnection::http1(1, true));
+    cache.checkin(&key("second", 80), FakeConnection::http1(2, true));
+    cache.checkin(&key("third", 80), FakeConnection::http1(3, true));
+    expect(cache.len()).to_be(eq(2));
+
+    let permit = acquire_permit(&mut cache, &key("first", 80));
+    expect(matches!(cache.checkout(&key("first", 80), permit.as_ref()), Checkout::Connect))
+        .to_be(eq(true));
+    drop(permit);
+
+    let permit = acquire_permit(&mut cache, &key("third", 80));
+    expect(checkout_id(&mut cache, &key("third", 80), permit.as_ref())).to_be(eq(Some(3)));
+    Ok(())
+}
+
+#[test]
+fn test_closed_connections_are_dropped() -> TestResult<()> {
+    let mut cache = ConnectionCache::new();
+    cache.checkin(&key("localhost", 80), FakeConnection::http1(1, true).closed());
+    expect(cache.is_empty()).to_be(eq(true));
+
+    cache.checkin(&key("localhost", 443), FakeConnection::http2(1).closed());
+    cache.checkin(&key("localhost", 443), FakeConnection::http2(2));
+    expect(checkout_id(&mut cache, &key("localhost", 443), None)).to_be(eq(Some(2)));
+    Ok(())
+}
+
+#[test]
+fn test_pool_key_separates_scheme_and_version() -> TestResult<()> {
+    let mut cache = ConnectionCache::new();
+    cache.checkin(
+        &PoolKey::new("https", "localhost", 443, Version::HTTP_2),
+        FakeConnection::http2(1),
+    );
+
+    let plain = PoolKey::new("http", "localhost", 443, Version::HTTP_2);
+    expect(matches!(cache.checkout(&plain, None), Checkout::Limited(_))).to_be(eq(true));
+
+    let http1 = PoolKey::new("https", "localhost", 443, Version::HTTP_11);
+    expect(matches!(cache.checkout(&http1, None), Checkout::Limited(_))).to_be(eq(true));
+
+    let same = PoolKey::new("HTTPS", "LocalHost", 443, Version::HTTP_2);
+    expect(checkout_id(&mut cache, &same, None)).to_be(eq(Some(1)));
+    Ok(())
+}
@@ -0,0 +1,40 @@
+use crate::protocol::{ProtocolPolicy, ALPN_HTTP1, ALPN_HTTP2};
+use caramelo::{expect, matchers::eq};
+use http::Version;
+use std::error::Error;
+
+#[test]
+fn test_policy_alpn() {
+    expect(ProtocolPolicy::Http1Only.alpn()).to_be(eq(&[ALPN_HTTP1][..]));
+    expect(ProtocolPolicy::Http2Only.alpn()).to_be(eq(&[ALPN_HTTP2][..]));
+    expect(ProtocolPolicy::PreferHttp2.alpn()).to_be(eq(&[ALPN_HTTP2, ALPN_HTTP1][..]));
+    expect(ProtocolPolicy::PreferHttp3.alpn()).to_be(eq(&[ALPN_HTTP2, ALPN_HTTP1][..]));
+}
+
+#[test]
+fn test_policy_negotiated() -> Result<(), Box<dyn Error>> {
+    let policy = ProtocolPolicy::default();
+    expect(policy.negotiated(Some(b"h2"))?).to_be(eq(Version::HTTP_2));
+    expect(policy.negotiated(Some(b"http/1.1"))?).to_be(eq(Version::HTTP_11));
+    expect(policy.negotiated(None)?).to_be(eq(Version::HTTP_11));
+    expect(ProtocolPolicy::Http2Only.negotiated(None)?).to_be(eq(Version::HTTP_2));
+    Ok(())
+}
+
+#[test]
+fn test_policy_rejects_unexpected_alpn() {
+    let policy = ProtocolPolicy::PreferHttp3;
+    for alpn in [&b"h3"[..], b"http1.1", b"spdy/3"] {
+        let error = policy
+            .negotiated(Some(alpn))
+            .unwrap_err();
+        expect(error.is_tls()).to_be(eq(true));
+    }
+
+    expect(
+        ProtocolPolicy::Http1Only
+            .negotiated(Some(b"h2"))
+            .is_err(),
+    )
+    .to_be(eq(true));
+}
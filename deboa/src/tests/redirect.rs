@@ -0,0 +1,240 @@
+use crate::{
+    errors::{DeboaError, RequestError},
+    redirect::{
+        is_same_origin, redirect_method, resolve_location, RedirectAction, RedirectPolicy,
+        Redirector,
+    },
+    request::DeboaRequest,
+    response::DeboaResponse,
+    tests::{test_uri, TEST_URL},
+    TestResult,
+};
+use caramelo::{expect, matchers::eq};
+use futures::FutureExt;
+use http::{header, HeaderValue, Method, StatusCode, Uri, Version};
+use http_body_util::BodyExt;
+
+fn redirect_response(status: StatusCode, location: &str) -> DeboaResponse {
+    DeboaResponse::builder()
+        .status(status)
+        .header(header::LOCATION, location)
+        .empty()
+}
+
+#[test]
+fn test_redirect_method() -> TestResult<()> {
+    expect(redirect_method(StatusCode::SEE_OTHER, &Method::POST))
+        .to_be(eq(Some((Method::GET, false))));
+    expect(redirect_method(StatusCode::SEE_OTHER, &Method::HEAD))
+        .to_be(eq(Some((Method::HEAD, false))));
+    expect(redirect_method(StatusCode::FOUND, &Method::POST)).to_be(eq(Some((Method::GET, false))));
+    expect(redirect_method(StatusCode::MOVED_PERMANENTLY, &Method::PUT))
+        .to_be(eq(Some((Method::PUT, true))));
+    expect(redirect_method(StatusCode::TEMPORARY_REDIRECT, &Method::POST))
+        .to_be(eq(Some((Method::POST, true))));
+    expect(redirect_method(StatusCode::PERMANENT_REDIRECT, &Method::GET))
+        .to_be(eq(Some((Method::GET, false))));
+    expect(redirect_method(StatusCode::NOT_MODIFIED, &Method::GET)).to_be(eq(None));
+    Ok(())
+}
+
+#[test]
+fn test_resolve_location() -> TestResult<()> {
+    let base = Uri::from_static("https://localhost:8000/posts/1");
+    expect(resolve_location(&base, "/comments")?)
+        .to_be(eq(Uri::from_static("https://localhost:8000/comments")));
+    expect(resolve_location(&base, "2")?)
+        .to_be(eq(Uri::from_static("https://localhost:8000/posts/2")));
+    expect(resolve_location(&base, "http://example.com/")?)
+        .to_be(eq(Uri::from_static("http://example.com/")));
+    Ok(())
+}
+
+#[test]
+fn test_is_same_origin() -> TestResult<()> {
+    expect(is_same_origin(
+        &Uri::from_static("https://localhost/a"),
+        &Uri::from_static("https://LOCALHOST:443/b"),
+    ))
+    .to_be(eq(true));
+    expect(is_same_origin(
+        &Uri::from_static("https://localhost/a"),
+        &Uri::from_static("http://localhost/a"),
+    ))
+    .to_be(eq(false));
+    expect(is_same_origin(
+        &Uri::from_static("https://localhost:8000/a"),
+        &Uri::from_static("https://localhost:8001/a"),
+    ))
+    .to_be(eq(false));
+    Ok(())
+}
+
+#[test]
+fn test_see_other_rewrites_to_get() -> TestResult<()> {
+    let policy = RedirectPolicy::default();
+    let request = DeboaRequest::post(TEST_URL)?
+        .text("hello")
+        .build()?;
+
+    let (mut redirector, _) = Redirector::new(&policy, request)?;
+    let next = redirector
+        .next(&redirect_response(StatusCode::SEE_OTHER, "/done"))?
+        .unwrap();
+
+    expect(next.method()).to_be(eq(&Method::GET));
+    expect(next.uri()).to_be(eq(&Uri::from_static("https://localhost:8000/done")));
+    expect(
+        next.headers()
+            .contains_key(header::CONTENT_TYPE),
+    )
+    .to_be(eq(false));
+
+    let response = redirector.finish(DeboaResponse::builder().build());
+    expect(response.redirects()).to_be(eq(&[test_uri()][..]));
+    Ok(())
+}
+
+#[test]
+fn test_temporary_redirect_keeps_method_and_body() -> TestResult<()> {
+    let policy = RedirectPolicy::default();
+    let request = DeboaRequest::post(TEST_URL)?
+        .text("hello")
+        .build()?;
+
+    let (mut redirector, _) = Redirector::new(&policy, request)?;
+    let next = redirector
+        .next(&redirect_response(StatusCode::TEMPORARY_REDIRECT, "/other"))?
+        .unwrap();
+
+    expect(next.method()).to_be(eq(&Method::POST));
+    let (_, body) = next.into_parts();
+    let body = body
+        .collect()
+        .now_or_never()
+        .unwrap()?
+        .to_bytes();
+    expect(body.as_ref()).to_be(eq(b"hello".as_ref()));
+    Ok(())
+}
+
+#[test]
+fn test_cross_origin_drops_credentials() -> TestResult<()> {
+    let policy = RedirectPolicy::default();
+    let request = DeboaRequest::get(TEST_URL)?
+        .bearer_auth("token")
+        .header(header::COOKIE, "session=1")
+        .build()?;
+
+    let (mut redirector, _) = Redirector::new(&policy, request)?;
+    let same = redirector
+        .next(&redirect_response(StatusCode::FOUND, "/same"))?
+        .unwrap();
+    expect(
+        same.headers()
+            .contains_key(header::AUTHORIZATION),
+    )
+    .to_be(eq(true));
+
+    let other = redirector
+        .next(&redirect_response(StatusCode::FOUND, "https://example.com/"))?
+        .unwrap();
+    expect(
+        other
+            .headers()
+            .contains_key(header::AUTHORIZATION),
+    )
+    .to_be(eq(false));
+    expect(
+        other
+            .headers()
+            .contains_key(header::COOKIE),
+    )
+    .to_be(eq(false));
+    expect(
+        other
+            .headers()
+            .get(header::HOST)
+            .unwrap(),
+    )
+    .to_be(eq(&HeaderValue::from_static("example.com")));
+
+    let ported = redirector
+        .next(&redirect_response(StatusCode::FOUND, "https://other.example.com:8443/"))?
+        .unwrap();
+    expect(
+        ported
+            .headers()
+            .get(header::HOST)
+            .unwrap(),
+    )
+    .to_be(eq(&HeaderValue::from_static("other.example.com:8443")));
+    Ok(())
+}
+
+#[test]
+fn test_scheme_change_resets_version() -> TestResult<()> {
+    let policy = RedirectPolicy::default();
+    let request = DeboaRequest::get(TEST_URL)?.build()?;
+
+    let (mut redirector, _) = Redirector::new(&policy, request)?;
+    let same = redirector
+        .next(&redirect_response(StatusCode::FOUND, "/same"))?
+        .unwrap();
+    expect(same.version()).to_be(eq(Version::HTTP_2));
+
+    let plain = redirector
+        .next(&redirect_response(StatusCode::FOUND, "http://example.com/"))?
+        .unwrap();
+    expect(plain.version()).to_be(eq(Version::HTTP_11));
+    Ok(())
+}
+
+#[test]
+fn test_redirect_loop() -> TestResult<()> {
+    let policy = RedirectPolicy::default();
+    let request = DeboaRequest::get(TEST_URL)?.build()?;
+
+    let (mut redirector, _) = Redirector::new(&policy, request)?;
+    redirector.next(&redirect_response(StatusCode::FOUND, "/a"))?;
+    let result = redirector.next(&redirect_response(StatusCode::FOUND, "/"));
+
+    expect(matches!(result, Err(DeboaError::Request(RequestError::Redirect { .. }))))
+        .to_be(eq(true));
+    Ok(())
+}
+
+#[test]
+fn test_limited_policy() -> TestResult<()> {
+    let policy = RedirectPolicy::limited(1);
+    let request = DeboaRequest::get(TEST_URL)?.build()?;
+
+    let (mut redirector, _) = Redirector::new(&policy, request)?;
+    redirector.next(&redirect_response(StatusCode::FOUND, "/a"))?;
+    let result = redirector.next(&redirect_response(StatusCode::FOUND, "/b"));
+
+    expect(matches!(result, Err(DeboaError::Request(RequestError::Redirect { .. }))))
+        .to_be(eq(true));
+    Ok(())
+}
+
+#[test]
+fn test_custom_policy() -> TestResult<()> {
+    let policy = RedirectPolicy::custom(|attempt| {
+        if attempt
+            .location()
+            .host()
+            == Some("localhost")
+        {
+            RedirectAction::Follow
+        } else {
+            RedirectAction::Stop
+        }
+    });
+    let request = DeboaRequest::get(TEST_URL)?.build()?;
+
+    let (mut redirector, _) = Redirector::new(&policy, request)?;
+    let next = redirector.next(&redirect_response(StatusCode::FOUND, "https://example.com/"))?;
+    expect(next.is_none()).to_be(eq(true));
+    Ok(())
+}
@@ -3,7 +3,7 @@ use crate::{
     tests::{test_uri, test_url, TEST_URL},
 };
 use caramelo::{expect, matchers::eq};
-use http::{header, HeaderValue, Method, Uri};
+use http::{header, HeaderValue, Method, Uri, Version};
 use std::{error::Error, str::FromStr};
 
 #[test]
@@ -46,6 +46,21 @@ fn test_str_method_ext_to_url() -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+#[test]
+fn test_default_version() -> Result<(), Box<dyn Error>> {
+    let request = DeboaRequest::get(TEST_URL)?.build()?;
+    expect(request.version()).to_be(eq(Version::HTTP_2));
+
+    let request = DeboaRequest::get("http://localhost:8000")?.build()?;
+    expect(request.version()).to_be(eq(Version::HTTP_11));
+
+    let request = DeboaRequest::get("http://localhost:8000")?
+        .version(Version::HTTP_2)
+        .build()?;
+    expect(request.version()).to_be(eq(Version::HTTP_2));
+    Ok(())
+}
+
 #[test]
 fn test_into_url() -> Result<(), Box<dyn Error>> {
     let url = test_url();
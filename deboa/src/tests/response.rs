@@ -19,6 +19,15 @@ fn test_status() -> TestResult<()> {
     Ok(())
 }
 
+#[test]
+fn test_version() -> TestResult<()> {
+    let response = DeboaResponse::builder()
+        .version(http::Version::HTTP_2)
+        .empty();
+    assert_eq!(response.version(), http::Version::HTTP_2);
+    Ok(())
+}
+
 #[test]
 fn test_headers() -> TestResult<()> {
     let response = DeboaResponse::builder()
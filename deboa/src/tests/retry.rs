@@ -0,0 +1,166 @@
+use crate::{
+    errors::{ConnectionError, DeboaError},
+    request::DeboaRequest,
+    response::DeboaResponse,
+    retry::{retry_after, Retry, RetryBudget, RetryPolicy},
+    tests::TEST_URL,
+    Result, TestResult,
+};
+use caramelo::{expect, matchers::eq};
+use http::{header, Method, StatusCode};
+use std::{cell::Cell, time::Duration};
+use tackle::{Chain, Hook};
+
+struct FlakyHook {
+    calls: Cell<u32>,
+    failures: u32,
+    status: StatusCode,
+}
+
+impl FlakyHook {
+    fn new(failures: u32, status: StatusCode) -> Self {
+        Self { calls: Cell::new(0), failures, status }
+    }
+}
+
+impl Hook<DeboaRequest, DeboaResponse> for FlakyHook {
+    type Result = Result<DeboaResponse>;
+    type Error = DeboaError;
+
+    async fn call(&self, _request: DeboaRequest) -> Self::Result {
+        let calls = self.calls.get() + 1;
+        self.calls
+            .set(calls);
+        if calls <= self.failures {
+            Ok(DeboaResponse::builder()
+                .status(self.status)
+                .build())
+        } else {
+            Ok(DeboaResponse::builder()
+                .status(StatusCode::OK)
+                .build())
+        }
+    }
+}
+
+fn fast_policy() -> RetryPolicy {
+    RetryPolicy::default()
+        .base_delay(Duration::from_millis(1))
+        .max_delay(Duration::from_millis(5))
+}
+
+#[test]
+fn test_try_clone() -> TestResult<()> {
+    let request = DeboaRequest::post(TEST_URL)?
+        .text("hello")
+        .build()?;
+    let cloned = request.try_clone()?;
+    expect(cloned.method()).to_be(eq(request.method()));
+    expect(cloned.uri()).to_be(eq(request.uri()));
+    expect(cloned.headers()).to_be(eq(request.headers()));
+    Ok(())
+}
+
+#[test]
+fn test_is_retryable() -> TestResult<()> {
+    let policy = RetryPolicy::default();
+    expect(policy.is_retryable(&Method::GET)).to_be(eq(true));
+    expect(policy.is_retryable(&Method::PUT)).to_be(eq(true));
+    expect(policy.is_retryable(&Method::POST)).to_be(eq(false));
+
+    let policy = policy.retry_non_idempotent(true);
+    expect(policy.is_retryable(&Method::POST)).to_be(eq(true));
+    Ok(())
+}
+
+#[test]
+fn test_retry_after_seconds() -> TestResult<()> {
+    let response = DeboaResponse::builder()
+        .status(StatusCode::SERVICE_UNAVAILABLE)
+        .header(header::RETRY_AFTER, "2")
+        .build();
+    expect(retry_after(&response)).to_be(eq(Some(Duration::from_secs(2))));
+    Ok(())
+}
+
+#[test]
+fn test_retry_after_date_in_past() -> TestResult<()> {
+    let response = DeboaResponse::builder()
+        .status(StatusCode::TOO_MANY_REQUESTS)
+        .header(header::RETRY_AFTER, "Wed, 21 Oct 2015 07:28:00 GMT")
+        .build();
+    expect(retry_after(&response)).to_be(eq(Some(Duration::ZERO)));
+    Ok(())
+}
+
+#[test]
+fn test_retry_delay() -> TestResult<()> {
+    let policy = fast_policy();
+
+    let ok = Ok(DeboaResponse::builder()
+        .status(StatusCode::OK)
+        .build());
+    expect(policy.retry_delay(&ok, 0)).to_be(eq(None));
+
+    let error = Err(DeboaError::Connection(ConnectionError::Tcp {
+        message: "reset".to_string(),
+        source: None,
+    }));
+    expect(
+        policy
+            .retry_delay(&error, 0)
+            .is_some(),
+    )
+    .to_be(eq(true));
+
+    let too_long = Ok(DeboaResponse::builder()
+        .status(StatusCode::SERVICE_UNAVAILABLE)
+        .header(header::RETRY_AFTER, "60")
+        .build());
+    expect(policy.retry_delay(&too_long, 0)).to_be(eq(None));
+    Ok(())
+}
+
+#[test]
+fn test_retry_budget() -> TestResult<()> {
+    let budget = RetryBudget::new(0.5, 1);
+    expect(budget.withdraw()).to_be(eq(true));
+    expect(budget.withdraw()).to_be(eq(false));
+    budget.deposit();
+    budget.deposit();
+    expect(budget.withdraw()).to_be(eq(true));
+    Ok(())
+}
+
+#[test]
+fn test_retry_hook() -> TestResult<()> {
+    let hook = Retry::new(fast_policy()).chain(FlakyHook::new(2, StatusCode::SERVICE_UNAVAILABLE));
+    let request = DeboaRequest::get(TEST_URL)?.build()?;
+
+    let response = futures::executor::block_on(hook.call(request))?;
+    expect(response.status()).to_be(eq(StatusCode::OK));
+    Ok(())
+}
+
+#[test]
+fn test_retry_hook_skips_non_idempotent() -> TestResult<()> {
+    let hook = Retry::new(fast_policy()).chain(FlakyHook::new(1, StatusCode::SERVICE_UNAVAILABLE));
+    let request = DeboaRequest::post(TEST_URL)?
+        .text("hello")
+        .build()?;
+
+    let response = futures::executor::block_on(hook.call(request))?;
+    expect(response.status()).to_be(eq(StatusCode::SERVICE_UNAVAILABLE));
+    Ok(())
+}
+
+#[test]
+fn test_retry_hook_gives_up() -> TestResult<()> {
+    let hook =
+        Retry::new(fast_policy().max_retries(1)).chain(FlakyHook::new(5, StatusCode::BAD_GATEWAY));
+    let request = DeboaRequest::get(TEST_URL)?.build()?;
+
+    let response = futures::executor::block_on(hook.call(request))?;
+    expect(response.status()).to_be(eq(StatusCode::BAD_GATEWAY));
+    Ok(())
+}
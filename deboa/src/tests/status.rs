@@ -0,0 +1,150 @@
+use crate::{
+    errors::{ContentError, DeboaError, ResponseError},
+    request::DeboaRequest,
+    response::DeboaResponse,
+    serde::ResponseBody,
+    status::ErrorForStatus,
+    tests::TEST_URL,
+    Result, TestResult,
+};
+use caramelo::{expect, matchers::eq};
+use futures::executor::block_on;
+use http::{header, StatusCode};
+use serde::{de::value::StrDeserializer, Deserialize};
+use tackle::{Chain, Hook};
+
+struct StatusHook {
+    status: StatusCode,
+    body: &'static str,
+}
+
+impl Hook<DeboaRequest, DeboaResponse> for StatusHook {
+    type Result = Result<DeboaResponse>;
+    type Error = DeboaError;
+
+    async fn call(&self, _request: DeboaRequest) -> Self::Result {
+        Ok(DeboaResponse::builder()
+            .status(self.status)
+            .header(header::CONTENT_TYPE, "text/plain")
+            .body(self.body.as_bytes())
+            .build())
+    }
+}
+
+struct TextBody;
+
+impl ResponseBody for TextBody {
+    fn deserialize<T: for<'a> Deserialize<'a>>(&self, value: Vec<u8>) -> Result<T> {
+        let text = String::from_utf8(value).map_err(|e| {
+            DeboaError::Content(ContentError::Deserialization { message: e.to_string() })
+        })?;
+        T::deserialize(StrDeserializer::<serde::de::value::Error>::new(&text)).map_err(|e| {
+            DeboaError::Content(ContentError::Deserialization { message: e.to_string() })
+        })
+    }
+}
+
+fn send(chain: &ErrorForStatus, status: StatusCode, body: &'static str) -> Result<DeboaResponse> {
+    let hook = chain.chain(StatusHook { status, body });
+    block_on(hook.call(DeboaRequest::get(TEST_URL)?.build()?))
+}
+
+#[test]
+fn test_error_for_status() -> TestResult<()> {
+    let response = DeboaResponse::builder()
+        .status(StatusCode::NOT_FOUND)
+        .header(header::CONTENT_TYPE, "text/plain")
+        .body(b"missing".as_slice())
+        .build();
+    let error = block_on(response.error_for_status()).unwrap_err();
+    expect(error.status()).to_be(eq(Some(StatusCode::NOT_FOUND)));
+    match error {
+        DeboaError::Response(ResponseError::Status { headers, body, details, .. }) => {
+            expect(headers.get(header::CONTENT_TYPE))
+                .to_be(eq(Some(&header::HeaderValue::from_static("text/plain"))));
+            expect(body.as_ref()).to_be(eq(b"missing".as_slice()));
+            expect(details.is_none()).to_be(eq(true));
+        }
+        error => panic!("unexpected error: {error:?}"),
+    }
+
+    let response = DeboaResponse::builder()
+        .status(StatusCode::NO_CONTENT)
+        .build();
+    let response = block_on(response.error_for_status())?;
+    expect(response.status()).to_be(eq(StatusCode::NO_CONTENT));
+    Ok(())
+}
+
+#[test]
+fn test_error_for_status_bounds_preview() -> TestResult<()> {
+    let body = vec![b'a'; 4096];
+    let response = DeboaResponse::builder()
+        .status(StatusCode::INTERNAL_SERVER_ERROR)
+        .body(body)
+        .build();
+    match block_on(response.error_for_status()) {
+        Err(DeboaError::Response(ResponseError::Status { body, .. })) => {
+            expect(body.len()).to_be(eq(crate::response::DEFAULT_BODY_PREVIEW_SIZE));
+        }
+        result => panic!("unexpected result: {result:?}"),
+    }
+    Ok(())
+}
+
+#[test]
+fn test_chain_matches_ranges() -> TestResult<()> {
+    let chain = ErrorForStatus::ranges([500..=599, 404..=404]);
+    let response = send(&chain, StatusCode::BAD_REQUEST, "bad")?;
+    expect(response.status()).to_be(eq(StatusCode::BAD_REQUEST));
+
+    let error = send(&chain, StatusCode::NOT_FOUND, "missing").unwrap_err();
+    expect(error.status()).to_be(eq(Some(StatusCode::NOT_FOUND)));
+
+    let error = send(&chain, StatusCode::BAD_GATEWAY, "down").unwrap_err();
+    expect(error.status()).to_be(eq(Some(StatusCode::BAD_GATEWAY)));
+
+    let response = send(&ErrorForStatus::new(), StatusCode::FOUND, "")?;
+    expect(response.status()).to_be(eq(StatusCode::FOUND));
+    Ok(())
+}
+
+#[test]
+fn test_chain_preview_size() -> TestResult<()> {
+    let chain = ErrorForStatus::new().max_preview_size(4);
+    match send(&chain, StatusCode::CONFLICT, "already exists") {
+        Err(DeboaError::Response(ResponseError::Status { body, .. })) => {
+            expect(body.as_ref()).to_be(eq(b"alre".as_slice()));
+        }
+        result => panic!("unexpected result: {result:?}"),
+    }
+    Ok(())
+}
+
+#[test]
+fn test_chain_error_body() -> TestResult<()> {
+    let chain = ErrorForStatus::new()
+        .max_preview_size(4)
+        .error_body::<String, _>(TextBody);
+    let error = send(&chain, StatusCode::UNPROCESSABLE_ENTITY, "invalid email").unwrap_err();
+    expect(error.error_body::<String>()).to_be(eq(Some(&"invalid email".to_string())));
+    expect(error.error_body::<u32>()).to_be(eq(None));
+    match error {
+        DeboaError::Response(ResponseError::Status { body, .. }) => {
+            expect(body.as_ref()).to_be(eq(b"inva".as_slice()));
+        }
+        error => panic!("unexpected error: {error:?}"),
+    }
+    Ok(())
+}
+
+#[test]
+fn test_chain_error_body_too_large() -> TestResult<()> {
+    let chain = ErrorForStatus::new()
+        .max_body_size(4)
+        .error_body::<String, _>(TextBody);
+    let error = send(&chain, StatusCode::BAD_REQUEST, "invalid email").unwrap_err();
+    expect(error.error_body::<String>()).to_be(eq(None));
+    expect(error.status()).to_be(eq(Some(StatusCode::BAD_REQUEST)));
+    Ok(())
+}
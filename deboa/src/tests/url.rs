@@ -16,3 +16,24 @@ fn test_url_invalid() {
     let url = url_str.parse_url();
     assert!(url.is_err());
 }
+
+#[test]
+fn test_is_relative() {
+    assert!(crate::url::is_relative("/users"));
+    assert!(crate::url::is_relative("users/1"));
+    assert!(!crate::url::is_relative("http://example.com/users"));
+}
+
+#[test]
+fn test_resolve() {
+    let base_url = ::url::Url::parse("http://example.com/api/v1").unwrap();
+    let url = crate::url::resolve(&base_url, "/users/1").unwrap();
+    assert_eq!(url.as_str(), "http://example.com/api/v1/users/1");
+
+    let url = crate::url::resolve(&base_url, "users?page=2").unwrap();
+    assert_eq!(url.as_str(), "http://example.com/api/v1/users?page=2");
+
+    let base_url = ::url::Url::parse("http://example.com").unwrap();
+    let url = crate::url::resolve(&base_url, "users").unwrap();
+    assert_eq!(url.as_str(), "http://example.com/users");
+}
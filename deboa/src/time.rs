@@ -0,0 +1,54 @@
+//! Timer helpers independent of the async runtime.
+use futures::future::{select, Either};
+use futures_timer::Delay;
+use hyper::rt::{Sleep, Timer};
+use std::{
+    future::Future,
+    pin::{pin, Pin},
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+/// Run a future, giving up once the duration elapsed.
+///
+/// # Arguments
+///
+/// * `duration` - The time limit.
+/// * `future` - The future to run.
+///
+/// # Returns
+///
+/// * `Option<F::Output>` - The output, or `None` if the time limit was reached.
+///
+pub(crate) async fn timeout<F: Future>(duration: Duration, future: F) -> Option<F::Output> {
+    match select(pin!(future), Delay::new(duration)).await {
+        Either::Left((output, _)) => Some(output),
+        Either::Right(_) => None,
+    }
+}
+
+/// Timer of the HTTP/2 connections, for keep-alive PINGs.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct DelayTimer;
+
+impl Timer for DelayTimer {
+    fn sleep(&self, duration: Duration) -> Pin<Box<dyn Sleep>> {
+        Box::pin(DelaySleep(Delay::new(duration)))
+    }
+
+    fn sleep_until(&self, deadline: Instant) -> Pin<Box<dyn Sleep>> {
+        self.sleep(deadline.saturating_duration_since(Instant::now()))
+    }
+}
+
+struct DelaySleep(Delay);
+
+impl Future for DelaySleep {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        Pin::new(&mut self.0).poll(cx)
+    }
+}
+
+impl Sleep for DelaySleep {}
@@ -78,6 +78,54 @@ impl IntoUrl for String {
     }
 }
 
+/// Check if a url is a path, to be resolved against a base url.
+///
+/// # Arguments
+///
+/// * `url` - The url.
+///
+/// # Returns
+///
+/// * `bool` - True if the url has no scheme.
+///
+pub(crate) fn is_relative(url: &str) -> bool {
+    matches!(Url::parse(url), Err(url::ParseError::RelativeUrlWithoutBase))
+}
+
+/// Resolve a path against a base url.
+///
+/// The path is appended to the path of the base url, whether it starts with
+/// a `/` or not, so `https://api.example.com/v1` and `/users` resolve to
+/// `https://api.example.com/v1/users`.
+///
+/// # Arguments
+///
+/// * `base_url` - The base url.
+/// * `path` - The path, with an optional query and fragment.
+///
+/// # Returns
+///
+/// * `Result<Url>` - The url.
+///
+pub fn resolve(base_url: &Url, path: &str) -> Result<Url> {
+    let mut base_url = base_url.clone();
+    if !base_url
+        .path()
+        .ends_with('/')
+    {
+        let path = format!("{}/", base_url.path());
+        base_url.set_path(&path);
+    }
+
+    base_url
+        .join(path.trim_start_matches('/'))
+        .map_err(|e| {
+            DeboaError::Request(RequestError::UrlParse {
+                message: format!("Failed to resolve {} against {}: {}", path, base_url, e),
+            })
+        })
+}
+
 /// Sealed trait to prevent external implementation.
 ///
 /// This is used to ensure that the `IntoUrl` trait can only be implemented